@@ -10,6 +10,12 @@ use wallet_lib::command::Command;
 use wallet_lib::ext::StringExt;
 use wallet_lib::types::Number;
 use wallet_lib::date::Date;
+use wallet_lib::export::ExportFormat;
+use wallet_lib::import::ImportFormat;
+use wallet_lib::search::Search;
+use wallet_lib::query::Expr;
+use wallet_lib::sort::{SortBy, GroupBy};
+use wallet_lib::merge::MergeStrategy;
 
 // const APP_NAME: &'static str = env!("CARGO_PKG_NAME");
 const APP_NAME: &'static str = "WalletRust";
@@ -30,7 +36,11 @@ fn main() {
 
     // Init Sub Command
     let init_subcmd = App::new("init")
-        .about("Initialize a new wallet.");
+        .about("Initialize a new wallet.")
+        .arg(Arg::with_name("git")
+            .long("git")
+            .help("Commit every change to a git repository in the wallet directory.")
+            .takes_value(false));
 
     // Add Sub Command
     let add_subcmd = App::new("add")
@@ -77,7 +87,11 @@ fn main() {
         .arg(Arg::with_name("force")
             .short("f")
             .long("force")
-            .help("Force add, even if ID already exists.")
+            .help("Also add entries in sealed or closed months.")
+            .takes_value(false))
+        .arg(Arg::with_name("allow_duplicate")
+            .long("allow-duplicate")
+            .help("Add even if the ID already exists.")
             .takes_value(false))
         .arg(Arg::with_name("epic")
             .short("x")
@@ -87,7 +101,7 @@ fn main() {
 
     // Epic Sub Command
     let epic_subcmd = App::new("epic")
-        .about("Add a new epic.")
+        .about("Add a new epic. Or list, edit, rename, remove and merge epics.")
         .arg(Arg::with_name("title")
             .short("t")
             .long("title")
@@ -100,8 +114,279 @@ fn main() {
         .arg(Arg::with_name("bgcolor")
             .long("bgcolor")
             .help("Background Color (HTML)")
+            .takes_value(true))
+        .subcommand(App::new("list")
+            .about("List epics with number of entries and totals."))
+        .subcommand(App::new("edit")
+            .about("Edit title and background color of an epic.")
+            .arg(Arg::with_name("handle")
+                .help("Handle")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .help("Title")
+                .takes_value(true))
+            .arg(Arg::with_name("bgcolor")
+                .long("bgcolor")
+                .help("Background Color (HTML)")
+                .takes_value(true)))
+        .subcommand(App::new("rename")
+            .about("Rename an epic and all its entries.")
+            .arg(Arg::with_name("handle")
+                .help("Handle")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("new_handle")
+                .help("New Handle")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Also change entries in sealed or closed months.")
+                .takes_value(false)))
+        .subcommand(App::new("remove")
+            .about("Remove an epic.")
+            .arg(Arg::with_name("handle")
+                .help("Handle")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Remove even when entries use the epic. They are moved to the default epic, also in sealed or closed months.")
+                .takes_value(false)))
+        .subcommand(App::new("merge")
+            .about("Move all entries of an epic to another one and remove it.")
+            .arg(Arg::with_name("handle")
+                .help("Handle of the epic to merge")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("new_handle")
+                .help("Handle of the epic to merge into")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Also change entries in sealed or closed months.")
+                .takes_value(false)));
+
+    // Category Sub Command
+    let category_subcmd = App::new("category")
+        .about("List, rename and merge categories.")
+        .subcommand(App::new("list")
+            .about("List categories with number of entries and totals."))
+        .subcommand(App::new("rename")
+            .about("Rename a category in all entries.")
+            .arg(Arg::with_name("category")
+                .help("Category")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("new_category")
+                .help("New Category")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Also change entries in sealed or closed months.")
+                .takes_value(false)))
+        .subcommand(App::new("merge")
+            .about("Move all entries of a category to another existing one.")
+            .arg(Arg::with_name("category")
+                .help("Category to merge")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("new_category")
+                .help("Category to merge into")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Also change entries in sealed or closed months.")
+                .takes_value(false)));
+
+    // Bulk Edit Sub Command
+    let bulk_edit_subcmd = App::new("bulk-edit")
+        .about("Change title, category, epic or comment of all entries matching a filter.")
+        .arg(Arg::with_name("revenue")
+            .short("r")
+            .long("revenue")
+            .help("Filter only revenues.")
+            .takes_value(false))
+        .arg(Arg::with_name("expense")
+            .short("e")
+            .long("expense")
+            .help("Filter only expenses.")
+            .takes_value(false))
+        .arg(Arg::with_name("category")
+            .short("c")
+            .long("category")
+            .help("Category")
+            .takes_value(true))
+        .arg(Arg::with_name("date")
+            .short("d")
+            .long("date")
+            .help("Date")
+            .takes_value(true))
+        .arg(Arg::with_name("epic")
+            .short("x")
+            .long("epic")
+            .help("Epic")
+            .takes_value(true))
+        .arg(Arg::with_name("search")
+            .long("search")
+            .value_name("QUERY")
+            .help("Search in title and comment, case-insensitive.")
+            .takes_value(true))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false))
+        .arg(Arg::with_name("min")
+            .long("min")
+            .value_name("AMOUNT")
+            .help("Minimum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("max")
+            .long("max")
+            .value_name("AMOUNT")
+            .help("Maximum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("query")
+            .long("query")
+            .value_name("QUERY")
+            .help("Filter query, like: category:food and (amount>50 or epic:trip) and not title~refund")
+            .takes_value(true))
+        .arg(Arg::with_name("set-title")
+            .long("set-title")
+            .value_name("TITLE")
+            .help("New title.")
+            .takes_value(true))
+        .arg(Arg::with_name("set-category")
+            .long("set-category")
+            .value_name("CATEGORY")
+            .help("New category.")
+            .takes_value(true))
+        .arg(Arg::with_name("set-epic")
+            .long("set-epic")
+            .value_name("EPIC")
+            .help("New epic handle.")
+            .takes_value(true))
+        .arg(Arg::with_name("set-comment")
+            .long("set-comment")
+            .value_name("COMMENT")
+            .help("New comment.")
+            .takes_value(true))
+        .arg(Arg::with_name("dry-run")
+            .short("n")
+            .long("dry-run")
+            .help("Only show the changes.")
+            .takes_value(false))
+        .arg(Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Apply the changes without asking, also in sealed or closed months.")
+            .takes_value(false));
+
+    // Undo Sub Command
+    let undo_subcmd = App::new("undo")
+        .about("Undo the last change of the wallet.")
+        .arg(Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Also undo changes of sealed or closed months.")
+            .takes_value(false));
+
+    // Redo Sub Command
+    let redo_subcmd = App::new("redo")
+        .about("Redo the last undone change.")
+        .arg(Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Also redo changes of sealed or closed months.")
+            .takes_value(false));
+
+    // History Sub Command
+    let history_subcmd = App::new("history")
+        .about("List recent changes of the wallet.")
+        .arg(Arg::with_name("limit")
+            .short("n")
+            .long("limit")
+            .value_name("N")
+            .help("Number of operations. Default: 20")
             .takes_value(true));
 
+    // Log Sub Command
+    let log_subcmd = App::new("log")
+        .about("List the git commits of the wallet. (init --git)")
+        .arg(Arg::with_name("limit")
+            .short("n")
+            .long("limit")
+            .value_name("N")
+            .help("Number of commits. Default: 20")
+            .takes_value(true));
+
+    // Show Sub Command
+    let show_subcmd = App::new("show")
+        .about("Show a git commit of the wallet. (init --git)")
+        .arg(Arg::with_name("rev")
+            .help("Revision")
+            .required(true)
+            .index(1));
+
+    // Merge Sub Command
+    let merge_subcmd = App::new("merge")
+        .about("Add the entries and epics of another wallet, matched by ID.")
+        .arg(Arg::with_name("path")
+            .help("Path to the other wallet")
+            .required(true)
+            .index(1))
+        .arg(Arg::with_name("strategy")
+            .long("strategy")
+            .help("Resolve conflicts with newest, ours, theirs or interactive. Default: newest")
+            .takes_value(true));
+
+    // Sync Sub Command
+    let sync_subcmd = App::new("sync")
+        .about("Two-way sync with another wallet directory. Conflicts are reported, not overwritten.")
+        .arg(Arg::with_name("path")
+            .help("Path to the other wallet")
+            .required(true)
+            .index(1));
+
+    // Encrypt Sub Command
+    let encrypt_subcmd = App::new("encrypt")
+        .about("Encrypt the data files with a passphrase. Uses WALLET_PASSPHRASE when set. Refused when git is enabled, because the git history is not rewritten.");
+
+    // Decrypt Sub Command
+    let decrypt_subcmd = App::new("decrypt")
+        .about("Decrypt the data files.");
+
+    // Verify Sub Command
+    let verify_subcmd = App::new("verify")
+        .about("Check the hash chain of the month files.");
+
+    // Seal Sub Command
+    let seal_subcmd = App::new("seal")
+        .about("Seal a month and all months before it. Sealed months are read-only unless --force is given.")
+        .arg(Arg::with_name("date")
+            .help("Month like 2020-03")
+            .required(true)
+            .index(1));
+
+    // Close Sub Command
+    let close_subcmd = App::new("close")
+        .about("Close a month after reconciling it. Closed months are read-only unless --force is given.")
+        .arg(Arg::with_name("date")
+            .help("Month like 2020-03")
+            .required(true)
+            .index(1));
+
     // List Sub Command
     let list_subcmd = App::new("list")
         .about("List entries.")
@@ -139,6 +424,152 @@ fn main() {
             .short("s")
             .long("short")
             .help("Short lines.")
+            .takes_value(false))
+        .arg(Arg::with_name("search")
+            .long("search")
+            .value_name("QUERY")
+            .help("Search in title and comment, case-insensitive.")
+            .takes_value(true))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false))
+        .arg(Arg::with_name("min")
+            .long("min")
+            .value_name("AMOUNT")
+            .help("Minimum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("max")
+            .long("max")
+            .value_name("AMOUNT")
+            .help("Maximum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("query")
+            .long("query")
+            .value_name("QUERY")
+            .help("Filter query, like: category:food and (amount>50 or epic:trip) and not title~refund")
+            .takes_value(true))
+        .arg(Arg::with_name("sort")
+            .long("sort")
+            .value_name("FIELD")
+            .help("Sort by date, amount, category or title. Default: date")
+            .takes_value(true))
+        .arg(Arg::with_name("reverse")
+            .long("reverse")
+            .help("Reverse the order.")
+            .takes_value(false))
+        .arg(Arg::with_name("group-by")
+            .long("group-by")
+            .value_name("GROUP")
+            .help("Group by day, week, month, category or epic, with subtotals.")
+            .takes_value(true))
+        .arg(Arg::with_name("carry-over")
+            .long("carry-over")
+            .help("Start the running balance from the balance of all earlier entries.")
+            .takes_value(false));
+
+    // Export Sub Command
+    let export_subcmd = App::new("export")
+        .about("Export entries as JSON, CSV, TSV or plain-text accounting journal.")
+        .arg(Arg::with_name("format")
+            .long("format")
+            .help("Output format: json, csv, tsv, ledger, hledger or beancount. Default: json")
+            .takes_value(true))
+        .arg(Arg::with_name("currency")
+            .long("currency")
+            .help("Commodity for ledger, hledger and beancount amounts. (beancount default: EUR)")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Output file. Default: stdout")
+            .takes_value(true))
+        .arg(Arg::with_name("aggregates")
+            .short("a")
+            .long("aggregates")
+            .help("Include years, categories and epics summaries. (JSON only)")
+            .takes_value(false))
+        .arg(Arg::with_name("revenue")
+            .short("r")
+            .long("revenue")
+            .help("Filter only revenues.")
+            .takes_value(false))
+        .arg(Arg::with_name("expense")
+            .short("e")
+            .long("expense")
+            .help("Filter only expenses.")
+            .takes_value(false))
+        .arg(Arg::with_name("category")
+            .short("c")
+            .long("category")
+            .help("Category")
+            .takes_value(true))
+        .arg(Arg::with_name("date")
+            .short("d")
+            .long("date")
+            .help("Date")
+            .takes_value(true))
+        .arg(Arg::with_name("epic")
+            .short("x")
+            .long("epic")
+            .help("Epic")
+            .takes_value(true))
+        .arg(Arg::with_name("search")
+            .long("search")
+            .value_name("QUERY")
+            .help("Search in title and comment, case-insensitive.")
+            .takes_value(true))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false))
+        .arg(Arg::with_name("min")
+            .long("min")
+            .value_name("AMOUNT")
+            .help("Minimum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("max")
+            .long("max")
+            .value_name("AMOUNT")
+            .help("Maximum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("query")
+            .long("query")
+            .value_name("QUERY")
+            .help("Filter query, like: category:food and (amount>50 or epic:trip) and not title~refund")
+            .takes_value(true))
+        .arg(Arg::with_name("sort")
+            .long("sort")
+            .value_name("FIELD")
+            .help("Sort by date, amount, category or title. Default: date")
+            .takes_value(true))
+        .arg(Arg::with_name("reverse")
+            .long("reverse")
+            .help("Reverse the order.")
+            .takes_value(false));
+
+    // Import Sub Command
+    let import_subcmd = App::new("import")
+        .about("Import entries from a file.")
+        .arg(Arg::with_name("format")
+            .value_name("FORMAT")
+            .help("Input format: ledger, hledger, ofx, qif, camt or mt940")
+            .required(true)
+            .index(1))
+        .arg(Arg::with_name("file")
+            .value_name("FILE")
+            .help("Input file")
+            .required(true)
+            .index(2))
+        .arg(Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Also add entries in sealed or closed months.")
+            .takes_value(false))
+        .arg(Arg::with_name("allow_duplicate")
+            .long("allow-duplicate")
+            .help("Add even if the ID already exists.")
             .takes_value(false));
 
     // HTML Sub Command
@@ -148,7 +579,58 @@ fn main() {
             .short("p")
             .long("path")
             .help("Output directory")
-            .takes_value(true));
+            .takes_value(true))
+        .arg(Arg::with_name("revenue")
+            .short("r")
+            .long("revenue")
+            .help("Filter only revenues.")
+            .takes_value(false))
+        .arg(Arg::with_name("expense")
+            .short("e")
+            .long("expense")
+            .help("Filter only expenses.")
+            .takes_value(false))
+        .arg(Arg::with_name("search")
+            .long("search")
+            .value_name("QUERY")
+            .help("Search in title and comment, case-insensitive.")
+            .takes_value(true))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false))
+        .arg(Arg::with_name("min")
+            .long("min")
+            .value_name("AMOUNT")
+            .help("Minimum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("max")
+            .long("max")
+            .value_name("AMOUNT")
+            .help("Maximum amount. Revenue with -r, expense with -e, otherwise absolute balance.")
+            .takes_value(true))
+        .arg(Arg::with_name("query")
+            .long("query")
+            .value_name("QUERY")
+            .help("Filter query, like: category:food and (amount>50 or epic:trip) and not title~refund")
+            .takes_value(true))
+        .arg(Arg::with_name("carry-over")
+            .long("carry-over")
+            .help("Start the running balance from the balance of all earlier entries.")
+            .takes_value(false));
+
+    // Search Sub Command
+    let search_subcmd = App::new("search")
+        .about("Search entries by title and comment.")
+        .arg(Arg::with_name("search")
+            .value_name("QUERY")
+            .help("Search query, case-insensitive.")
+            .required(true)
+            .index(1))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false));
 
     // Common Arguments
     let wallet_arg = Arg::with_name("wallet")
@@ -158,6 +640,60 @@ fn main() {
         .help("Path to the wallet directory.")
         .takes_value(true);
 
+    // TUI Sub Command
+    let tui_subcmd = App::new("tui")
+        .about("Browse and edit entries in a full-screen terminal UI.");
+
+    // Stats Sub Command
+    let stats_subcmd = App::new("stats")
+        .about("Averages, medians, largest entries and trends.")
+        .arg(Arg::with_name("json")
+            .long("json")
+            .help("Print JSON instead of text.")
+            .takes_value(false))
+        .arg(Arg::with_name("category")
+            .short("c")
+            .long("category")
+            .help("Category")
+            .takes_value(true))
+        .arg(Arg::with_name("date")
+            .short("d")
+            .long("date")
+            .help("Date")
+            .takes_value(true))
+        .arg(Arg::with_name("epic")
+            .short("x")
+            .long("epic")
+            .help("Epic")
+            .takes_value(true))
+        .arg(Arg::with_name("search")
+            .long("search")
+            .value_name("QUERY")
+            .help("Search in title and comment, case-insensitive.")
+            .takes_value(true))
+        .arg(Arg::with_name("regex")
+            .long("regex")
+            .help("Use the search query as regular expression.")
+            .takes_value(false))
+        .arg(Arg::with_name("query")
+            .long("query")
+            .value_name("QUERY")
+            .help("Filter query, like: category:food and (amount>50 or epic:trip) and not title~refund")
+            .takes_value(true));
+
+    // Forecast Sub Command
+    let forecast_subcmd = App::new("forecast")
+        .about("Project future balances from category averages and future entries.")
+        .arg(Arg::with_name("months")
+            .long("months")
+            .value_name("N")
+            .help("Number of months. Default: 6")
+            .takes_value(true))
+        .arg(Arg::with_name("json")
+            .long("json")
+            .help("Print JSON instead of text.")
+            .takes_value(false));
+
     // Main App
     let app = App::new(APP_NAME)
         .version(APP_VERSION)
@@ -168,8 +704,28 @@ fn main() {
         .subcommand(init_subcmd)
         .subcommand(add_subcmd)
         .subcommand(epic_subcmd)
+        .subcommand(category_subcmd)
+        .subcommand(bulk_edit_subcmd)
+        .subcommand(undo_subcmd)
+        .subcommand(redo_subcmd)
+        .subcommand(history_subcmd)
+        .subcommand(log_subcmd)
+        .subcommand(show_subcmd)
+        .subcommand(merge_subcmd)
+        .subcommand(sync_subcmd)
+        .subcommand(encrypt_subcmd)
+        .subcommand(decrypt_subcmd)
+        .subcommand(verify_subcmd)
+        .subcommand(seal_subcmd)
+        .subcommand(close_subcmd)
         .subcommand(list_subcmd)
         .subcommand(html_subcmd)
+        .subcommand(export_subcmd)
+        .subcommand(import_subcmd)
+        .subcommand(tui_subcmd)
+        .subcommand(search_subcmd)
+        .subcommand(stats_subcmd)
+        .subcommand(forecast_subcmd)
         .arg(wallet_arg);
 
     // Command Options
@@ -201,6 +757,11 @@ fn main() {
 
             // Cmd
             cmd_kind = CommandKind::InitCommand;
+
+            // Git
+            if init_matches.is_present("git") {
+                cmd_options.git = true;
+            }
         },
         ("add", Some(add_matches)) => {
             println!("-> cmd: add ({:?})", add_matches);
@@ -211,6 +772,7 @@ fn main() {
             // Interactive
             if add_matches.is_present("interactive") {
                 println!("-> interactive is present");
+                cmd_options.interactive = true;
             }
 
             // Title
@@ -266,22 +828,187 @@ fn main() {
             }
 
             // Force
-            if add_matches.is_present("force") {
-                let v = add_matches.value_of("force");
-                println!("-> force is present: '{:?}'", v);
-
-                cmd_options.force = true;
+            set_force(add_matches, &mut cmd_options);
+            if add_matches.is_present("allow_duplicate") {
+                cmd_options.allow_duplicate = true;
             }
         },
         ("epic", Some(epic_matches)) => {
             println!("-> cmd: epic ({:?})", epic_matches);
 
+            match epic_matches.subcommand() {
+                ("list", _) => {
+                    cmd_kind = CommandKind::EpicListCommand;
+                },
+                ("edit", Some(edit_matches)) => {
+                    cmd_kind = CommandKind::EpicEditCommand;
+
+                    set_handle(edit_matches, &mut cmd_options);
+                    set_title(edit_matches, &mut cmd_options);
+                    set_bgcolor(edit_matches, &mut cmd_options);
+                },
+                ("rename", Some(rename_matches)) => {
+                    cmd_kind = CommandKind::EpicRenameCommand;
+
+                    set_handle(rename_matches, &mut cmd_options);
+                    set_new_handle(rename_matches, &mut cmd_options);
+                    set_force(rename_matches, &mut cmd_options);
+                },
+                ("remove", Some(remove_matches)) => {
+                    cmd_kind = CommandKind::EpicRemoveCommand;
+
+                    set_handle(remove_matches, &mut cmd_options);
+                    set_force(remove_matches, &mut cmd_options);
+                },
+                ("merge", Some(merge_matches)) => {
+                    cmd_kind = CommandKind::EpicMergeCommand;
+
+                    set_handle(merge_matches, &mut cmd_options);
+                    set_new_handle(merge_matches, &mut cmd_options);
+                    set_force(merge_matches, &mut cmd_options);
+                },
+                _ => {
+                    // Cmd
+                    cmd_kind = CommandKind::EpicCommand;
+
+                    set_handle(epic_matches, &mut cmd_options);
+                    set_title(epic_matches, &mut cmd_options);
+                    set_bgcolor(epic_matches, &mut cmd_options);
+                },
+            }
+        },
+        ("category", Some(category_matches)) => {
+            println!("-> cmd: category ({:?})", category_matches);
+
+            match category_matches.subcommand() {
+                ("rename", Some(rename_matches)) => {
+                    cmd_kind = CommandKind::CategoryRenameCommand;
+
+                    set_category(rename_matches, &mut cmd_options);
+                    set_new_category(rename_matches, &mut cmd_options);
+                    set_force(rename_matches, &mut cmd_options);
+                },
+                ("merge", Some(merge_matches)) => {
+                    cmd_kind = CommandKind::CategoryMergeCommand;
+
+                    set_category(merge_matches, &mut cmd_options);
+                    set_new_category(merge_matches, &mut cmd_options);
+                    set_force(merge_matches, &mut cmd_options);
+                },
+                _ => {
+                    cmd_kind = CommandKind::CategoryListCommand;
+                },
+            }
+        },
+        ("bulk-edit", Some(bulk_edit_matches)) => {
+            println!("-> cmd: bulk-edit");
+
             // Cmd
-            cmd_kind = CommandKind::EpicCommand;
+            cmd_kind = CommandKind::BulkEditCommand;
+
+            // Filter
+            set_date_silent(bulk_edit_matches, &mut cmd_options);
+            set_category(bulk_edit_matches, &mut cmd_options);
+            set_epic(bulk_edit_matches, &mut cmd_options);
+            if bulk_edit_matches.is_present("revenue") {
+                cmd_options.filter_revenue = Some(true);
+            }
+            if bulk_edit_matches.is_present("expense") {
+                cmd_options.filter_expense = Some(true);
+            }
+            set_search(bulk_edit_matches, &mut cmd_options);
+            set_min_max(bulk_edit_matches, &mut cmd_options);
+            set_query(bulk_edit_matches, &mut cmd_options);
+
+            // Changes
+            set_changes(bulk_edit_matches, &mut cmd_options);
+
+            if bulk_edit_matches.is_present("dry-run") {
+                cmd_options.dry_run = true;
+            }
+            set_force(bulk_edit_matches, &mut cmd_options);
+        },
+        ("undo", Some(undo_matches)) => {
+            println!("-> cmd: undo");
 
-            set_handle(epic_matches, &mut cmd_options);
-            set_title(epic_matches, &mut cmd_options);
-            set_bgcolor(epic_matches, &mut cmd_options);
+            cmd_kind = CommandKind::UndoCommand;
+            set_force(undo_matches, &mut cmd_options);
+        },
+        ("redo", Some(redo_matches)) => {
+            println!("-> cmd: redo");
+
+            cmd_kind = CommandKind::RedoCommand;
+            set_force(redo_matches, &mut cmd_options);
+        },
+        ("history", Some(history_matches)) => {
+            println!("-> cmd: history");
+
+            cmd_kind = CommandKind::HistoryCommand;
+
+            if let Some(limit) = history_matches.value_of("limit") {
+                cmd_options.limit = Some(limit.parse().expect("Invalid limit"));
+            }
+        },
+        ("log", Some(log_matches)) => {
+            println!("-> cmd: log");
+
+            cmd_kind = CommandKind::LogCommand;
+
+            if let Some(limit) = log_matches.value_of("limit") {
+                cmd_options.limit = Some(limit.parse().expect("Invalid limit"));
+            }
+        },
+        ("show", Some(show_matches)) => {
+            println!("-> cmd: show");
+
+            cmd_kind = CommandKind::ShowCommand;
+            cmd_options.rev = show_matches.value_of("rev").map(|vs| vs.to_string());
+        },
+        ("merge", Some(merge_matches)) => {
+            println!("-> cmd: merge");
+
+            cmd_kind = CommandKind::MergeCommand;
+            cmd_options.merge_path = merge_matches.value_of("path").map(|vs| vs.to_string());
+
+            if let Some(strategy) = merge_matches.value_of("strategy") {
+                match MergeStrategy::from_str(strategy) {
+                    Ok(strategy) => cmd_options.merge_strategy = Some(strategy),
+                    Err(error) => panic!("{}", error),
+                }
+            }
+        },
+        ("sync", Some(sync_matches)) => {
+            println!("-> cmd: sync");
+
+            cmd_kind = CommandKind::SyncCommand;
+            cmd_options.sync_path = sync_matches.value_of("path").map(|vs| vs.to_string());
+        },
+        ("encrypt", Some(_)) => {
+            println!("-> cmd: encrypt");
+
+            cmd_kind = CommandKind::EncryptCommand;
+        },
+        ("decrypt", Some(_)) => {
+            println!("-> cmd: decrypt");
+
+            cmd_kind = CommandKind::DecryptCommand;
+        },
+        ("verify", Some(_)) => {
+            println!("-> cmd: verify");
+
+            cmd_kind = CommandKind::VerifyCommand;
+        },
+        ("seal", Some(seal_matches)) => {
+            println!("-> cmd: seal");
+
+            cmd_kind = CommandKind::SealCommand;
+            set_date_fill(seal_matches, &mut cmd_options);
+        },
+        ("close", Some(close_matches)) => {
+            println!("-> cmd: close");
+
+            cmd_kind = CommandKind::CloseCommand;
+            set_date_fill(close_matches, &mut cmd_options);
         },
         ("list", Some(list_matches)) => {
             println!("-> cmd: list");
@@ -308,6 +1035,26 @@ fn main() {
                 cmd_options.filter_expense = Some(true);
             }
 
+            // Search
+            set_search(list_matches, &mut cmd_options);
+
+            // Amount
+            set_min_max(list_matches, &mut cmd_options);
+
+            // Query
+            set_query(list_matches, &mut cmd_options);
+
+            // Running Balance
+            if list_matches.is_present("carry-over") {
+                cmd_options.carry_over = true;
+            }
+
+            // Sort
+            set_sort(list_matches, &mut cmd_options);
+
+            // Group
+            set_group_by(list_matches, &mut cmd_options);
+
             // Format
             set_long(list_matches, &mut cmd_options);
             set_short(list_matches, &mut cmd_options);
@@ -324,6 +1071,20 @@ fn main() {
             // Epic
             set_path(html_matches, &mut cmd_options);
 
+            // Search
+            set_search(html_matches, &mut cmd_options);
+
+            // Amount
+            set_min_max(html_matches, &mut cmd_options);
+
+            // Query
+            set_query(html_matches, &mut cmd_options);
+
+            // Running Balance
+            if html_matches.is_present("carry-over") {
+                cmd_options.carry_over = true;
+            }
+
             // Date
             // set_date_silent(html_matches, &mut cmd_options);
 
@@ -334,14 +1095,154 @@ fn main() {
             // set_epic(html_matches, &mut cmd_options);
 
             // Revenue
-            // if html_matches.is_present("revenue") {
-            //     cmd_options.filter_revenue = Some(true);
-            // }
+            if html_matches.is_present("revenue") {
+                cmd_options.filter_revenue = Some(true);
+            }
+
+            // Expense
+            if html_matches.is_present("expense") {
+                cmd_options.filter_expense = Some(true);
+            }
+        },
+        ("export", Some(export_matches)) => {
+            println!("-> cmd: export");
+
+            // Cmd
+            cmd_kind = CommandKind::ExportCommand;
+
+            // Format
+            if export_matches.is_present("format") {
+                let vs = export_matches.value_of("format").unwrap();
+                let format = ExportFormat::from_str(vs).expect("Unknown export format");
+                cmd_options.export_format = Some(format);
+            }
+
+            // Output
+            if export_matches.is_present("output") {
+                let vs = export_matches.value_of("output").unwrap();
+                cmd_options.output_path = Some(vs.to_string());
+            }
+
+            // Currency
+            if export_matches.is_present("currency") {
+                let vs = export_matches.value_of("currency").unwrap();
+                cmd_options.currency = Some(vs.to_string());
+            }
+
+            // Aggregates
+            if export_matches.is_present("aggregates") {
+                cmd_options.export_aggregates = true;
+            }
+
+            // Date
+            set_date_silent(export_matches, &mut cmd_options);
+
+            // Category
+            set_category(export_matches, &mut cmd_options);
+
+            // Epic
+            set_epic(export_matches, &mut cmd_options);
+
+            // Search
+            set_search(export_matches, &mut cmd_options);
+
+            // Amount
+            set_min_max(export_matches, &mut cmd_options);
+
+            // Query
+            set_query(export_matches, &mut cmd_options);
+
+            // Sort
+            set_sort(export_matches, &mut cmd_options);
+
+            // Revenue
+            if export_matches.is_present("revenue") {
+                cmd_options.filter_revenue = Some(true);
+            }
 
             // Expense
-            // if html_matches.is_present("expense") {
-            //     cmd_options.filter_expense = Some(true);
-            // }
+            if export_matches.is_present("expense") {
+                cmd_options.filter_expense = Some(true);
+            }
+        },
+        ("import", Some(import_matches)) => {
+            println!("-> cmd: import");
+
+            // Cmd
+            cmd_kind = CommandKind::ImportCommand;
+
+            // Format
+            let vs = import_matches.value_of("format").unwrap();
+            let format = ImportFormat::from_str(vs).expect("Unknown import format");
+            cmd_options.import_format = Some(format);
+
+            // File
+            let vs = import_matches.value_of("file").unwrap();
+            cmd_options.import_path = Some(vs.to_string());
+
+            // Force
+            set_force(import_matches, &mut cmd_options);
+            if import_matches.is_present("allow_duplicate") {
+                cmd_options.allow_duplicate = true;
+            }
+        },
+        ("search", Some(search_matches)) => {
+            println!("-> cmd: search");
+
+            // Cmd
+            cmd_kind = CommandKind::SearchCommand;
+
+            set_search(search_matches, &mut cmd_options);
+        },
+        ("stats", Some(stats_matches)) => {
+            println!("-> cmd: stats");
+
+            // Cmd
+            cmd_kind = CommandKind::StatsCommand;
+
+            // JSON
+            if stats_matches.is_present("json") {
+                cmd_options.json = true;
+            }
+
+            // Date
+            set_date_silent(stats_matches, &mut cmd_options);
+
+            // Category
+            set_category(stats_matches, &mut cmd_options);
+
+            // Epic
+            set_epic(stats_matches, &mut cmd_options);
+
+            // Search
+            set_search(stats_matches, &mut cmd_options);
+
+            // Query
+            set_query(stats_matches, &mut cmd_options);
+        },
+        ("forecast", Some(forecast_matches)) => {
+            println!("-> cmd: forecast");
+
+            // Cmd
+            cmd_kind = CommandKind::ForecastCommand;
+
+            // Months
+            if forecast_matches.is_present("months") {
+                let vs = forecast_matches.value_of("months").unwrap();
+                let months: u32 = vs.parse().expect("Invalid number of months");
+                cmd_options.forecast_months = Some(months);
+            }
+
+            // JSON
+            if forecast_matches.is_present("json") {
+                cmd_options.json = true;
+            }
+        },
+        ("tui", _) => {
+            println!("-> cmd: tui");
+
+            // Cmd
+            cmd_kind = CommandKind::TuiCommand;
         },
         _ => {
             println!("No command.");
@@ -354,6 +1255,71 @@ fn main() {
     println!("-> end");
 }
 
+fn set_min_max(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if matches.is_present("min") {
+        let vs = matches.value_of("min").unwrap().to_string();
+        cmd_options.min = Some(vs.replace_comma().to_num());
+    }
+
+    if matches.is_present("max") {
+        let vs = matches.value_of("max").unwrap().to_string();
+        cmd_options.max = Some(vs.replace_comma().to_num());
+    }
+}
+
+fn set_sort(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if matches.is_present("sort") {
+        let vs = matches.value_of("sort").unwrap();
+        match SortBy::from_str(vs) {
+            Ok(sort) => cmd_options.sort = Some(sort),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    if matches.is_present("reverse") {
+        cmd_options.reverse = true;
+    }
+}
+
+fn set_group_by(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("group-by") {
+        return;
+    }
+
+    let vs = matches.value_of("group-by").unwrap();
+    match GroupBy::from_str(vs) {
+        Ok(group_by) => cmd_options.group_by = Some(group_by),
+        Err(error) => panic!("{}", error),
+    }
+}
+
+fn set_query(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("query") {
+        return;
+    }
+
+    let vs = matches.value_of("query").unwrap();
+    match Expr::from_str(vs) {
+        Ok(expr) => cmd_options.query = Some(expr),
+        Err(error) => panic!("Invalid query: {}", error),
+    }
+}
+
+fn set_search(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("search") {
+        return;
+    }
+
+    let vs = matches.value_of("search").unwrap();
+    let search = if matches.is_present("regex") {
+        Search::regex(vs).expect("Invalid search regex")
+    } else {
+        Search::new(vs)
+    };
+
+    cmd_options.search = Some(search);
+}
+
 fn set_title(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     if !matches.is_present("title") {
         return;
@@ -430,6 +1396,24 @@ fn set_category(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     cmd_options.category = Some(vs.to_string());
 }
 
+fn set_new_category(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("new_category") {
+        return;
+    }
+
+    let vs = matches.value_of("new_category").unwrap();
+    cmd_options.new_category = Some(vs.to_string());
+}
+
+fn set_changes(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    let value = |name: &str| matches.value_of(name).map(|vs| vs.to_string());
+
+    cmd_options.changes.title = value("set-title");
+    cmd_options.changes.category = value("set-category");
+    cmd_options.changes.epic = value("set-epic");
+    cmd_options.changes.comment = value("set-comment");
+}
+
 fn set_comment(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     if !matches.is_present("comment") {
         return;
@@ -460,6 +1444,15 @@ fn set_handle(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     cmd_options.handle = Some(vs.to_string());
 }
 
+fn set_new_handle(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("new_handle") {
+        return;
+    }
+
+    let vs = matches.value_of("new_handle").unwrap();
+    cmd_options.new_handle = Some(vs.to_string());
+}
+
 fn set_bgcolor(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     if !matches.is_present("bgcolor") {
         return;
@@ -486,6 +1479,14 @@ fn set_short(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     cmd_options.long = Some(false);
 }
 
+fn set_force(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
+    if !matches.is_present("force") {
+        return;
+    }
+
+    cmd_options.force = true;
+}
+
 fn set_path(matches: &ArgMatches, cmd_options: &mut CommandOptions) {
     if !matches.is_present("path") {
         return;
@@ -0,0 +1,284 @@
+//! ISO 20022 bank statements (camt.053) and notifications (camt.054).
+
+use std::str::FromStr;
+use roxmltree::{Document, Node};
+use uuid::Uuid;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::number::NumberType;
+use crate::import::{ImportResult, ImportError, parse_amount};
+
+/// First child element with the given local name. Namespaces differ
+/// between camt versions, so only the local name is compared.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn children<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Follow a path of child elements.
+fn path<'a, 'input>(node: Node<'a, 'input>, names: &[&str]) -> Option<Node<'a, 'input>> {
+    let mut current = node;
+    for name in names {
+        current = child(current, name)?;
+    }
+    Some(current)
+}
+
+fn text(node: Node, names: &[&str]) -> Option<String> {
+    let text = path(node, names)?.text()?.trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM-DDThh:mm:ss+hh:mm`. Only the day is used.
+fn parse_date(s: &str) -> Option<Date> {
+    let s = s.get(..10)?;
+    match Date::from_str(s) {
+        Ok(date) if date.has_year() && date.has_month() && date.has_day() => Some(date),
+        _ => None,
+    }
+}
+
+/// Booking date, either as date or as date-time.
+fn booking_date(ntry: Node) -> Option<Date> {
+    text(ntry, &["BookgDt", "Dt"])
+        .or_else(|| text(ntry, &["BookgDt", "DtTm"]))
+        .or_else(|| text(ntry, &["ValDt", "Dt"]))
+        .and_then(|s| parse_date(&s))
+}
+
+/// Older versions use `<Sts>BOOK</Sts>`, newer ones `<Sts><Cd>BOOK</Cd></Sts>`.
+fn is_booked(ntry: Node) -> bool {
+    let status = text(ntry, &["Sts", "Cd"]).or_else(|| text(ntry, &["Sts"]));
+    status.as_deref() == Some("BOOK")
+}
+
+/// Unstructured remittance information, one line per `<Ustrd>`.
+fn remittance(tx: Node) -> Option<String> {
+    let rmt_inf = child(tx, "RmtInf")?;
+    let lines: Vec<String> = children(rmt_inf, "Ustrd")
+        .filter_map(|n| n.text())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
+
+/// The other party: the creditor of a debit, the debtor of a credit.
+fn counterparty(tx: Node, credit: bool) -> Option<String> {
+    let (first, second) = if credit { ("Dbtr", "Cdtr") } else { ("Cdtr", "Dbtr") };
+
+    text(tx, &["RltdPties", first, "Nm"])
+        .or_else(|| text(tx, &["RltdPties", first, "Pty", "Nm"]))
+        .or_else(|| text(tx, &["RltdPties", second, "Nm"]))
+}
+
+fn line_of(doc: &Document, node: Node) -> usize {
+    doc.text_pos_at(node.range().start).row as usize
+}
+
+/// Read a camt.053 statement or camt.054 notification.
+///
+/// Only booked entries are read. The bank's entry reference is used
+/// as entry ID, so importing the same statement twice is blocked by
+/// the wallet index. Batch entries with several transaction details
+/// become one entry per transaction.
+pub fn read_camt(raw: &str) -> Result<ImportResult, ImportError> {
+    let doc = Document::parse(raw).map_err(|_| ImportError::InvalidFormat)?;
+
+    let root = doc.root_element();
+    if root.tag_name().name() != "Document" {
+        return Err(ImportError::InvalidFormat);
+    }
+
+    let mut result = ImportResult::new();
+
+    let ntries = root.descendants().filter(|n| n.is_element() && n.tag_name().name() == "Ntry");
+    for ntry in ntries {
+        if !is_booked(ntry) {
+            result.skipped += 1;
+            continue;
+        }
+
+        let line = line_of(&doc, ntry);
+        let date = booking_date(ntry).ok_or(ImportError::InvalidDate(line))?;
+        let credit = match text(ntry, &["CdtDbtInd"]).as_deref() {
+            Some("CRDT") => true,
+            Some("DBIT") => false,
+            _ => return Err(ImportError::InvalidAmount(line)),
+        };
+        let amount = text(ntry, &["Amt"])
+            .and_then(|s| parse_amount(&s))
+            .ok_or(ImportError::InvalidAmount(line))?;
+
+        let reference = text(ntry, &["AcctSvcrRef"]).or_else(|| text(ntry, &["NtryRef"]));
+        let additional = text(ntry, &["AddtlNtryInf"]);
+
+        let details: Vec<Node> = match child(ntry, "NtryDtls") {
+            Some(dtls) => children(dtls, "TxDtls").collect(),
+            None => vec![],
+        };
+
+        // Entry without details, or a single transaction.
+        if details.len() <= 1 {
+            let (name, info) = match details.first() {
+                Some(tx) => (counterparty(*tx, credit), remittance(*tx).or(additional)),
+                None => (None, additional),
+            };
+
+            let id = reference.unwrap_or_else(|| fallback_id(&date, amount, credit, &info));
+            result.entries.push(create_entry(id, date, amount, credit, name, info));
+            continue;
+        }
+
+        // Batch booking.
+        for (index, tx) in details.iter().enumerate() {
+            let tx_line = line_of(&doc, *tx);
+            let tx_amount = text(*tx, &["AmtDtls", "TxAmt", "Amt"])
+                .or_else(|| text(*tx, &["Amt"]))
+                .and_then(|s| parse_amount(&s))
+                .ok_or(ImportError::InvalidAmount(tx_line))?;
+
+            let name = counterparty(*tx, credit);
+            let info = remittance(*tx).or_else(|| additional.clone());
+
+            let id = match (text(*tx, &["Refs", "AcctSvcrRef"]), &reference) {
+                (Some(tx_reference), _) => tx_reference,
+                (None, Some(reference)) => format!("{}-{}", reference, index + 1),
+                (None, None) => fallback_id(&date, tx_amount, credit, &info),
+            };
+
+            result.entries.push(create_entry(id, date, tx_amount, credit, name, info));
+        }
+    }
+
+    // Statement period.
+    if let Some(fr_to_dt) = root.descendants().find(|n| n.is_element() && n.tag_name().name() == "FrToDt") {
+        result.start = text(fr_to_dt, &["FrDtTm"]).and_then(|s| parse_date(&s));
+        result.end = text(fr_to_dt, &["ToDtTm"]).and_then(|s| parse_date(&s));
+    }
+
+    Ok(result)
+}
+
+/// Like OFX and QIF: the counterparty is the title, the remittance
+/// information is the comment. Without a counterparty the remittance
+/// information is the title.
+fn create_entry(id: String, date: Date, amount: NumberType, credit: bool, name: Option<String>, info: Option<String>) -> Entry {
+    let mut entry = Entry::new();
+    entry.set_id(id);
+    entry.set_date(date);
+
+    match (name, info) {
+        (Some(name), Some(info)) => {
+            entry.set_title(name);
+            entry.set_comment(info);
+        },
+        (Some(title), None) | (None, Some(title)) => entry.set_title(title),
+        (None, None) => (),
+    }
+
+    if credit {
+        entry.set_revenue(amount);
+    } else {
+        entry.set_expense(amount);
+    }
+
+    entry
+}
+
+/// Stable ID for entries without any bank reference.
+fn fallback_id(date: &Date, amount: NumberType, credit: bool, info: &Option<String>) -> String {
+    let key = format!("camt|{}|{:.2}|{}|{}", date, amount, credit, info.as_deref().unwrap_or(""));
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes()).to_string()
+}
+
+#[cfg(test)]
+mod tests_read {
+    use super::read_camt;
+    use crate::import::ImportError;
+
+    const CAMT053: &str = include_str!("../../resources/tests/camt/camt053_batch.xml");
+    const CAMT054: &str = include_str!("../../resources/tests/camt/camt054.xml");
+
+    #[test]
+    fn test_read_camt053_batch1() {
+        let r1 = read_camt(CAMT053).unwrap();
+        assert_eq!(4, r1.entries.len());
+        assert_eq!(1, r1.skipped);
+        assert_eq!("2020-01-01", r1.start.unwrap().to_string());
+        assert_eq!("2020-01-31", r1.end.unwrap().to_string());
+
+        let e1 = &r1.entries[0];
+        assert_eq!("REF-0001", e1.id());
+        assert_eq!("2020-01-02", e1.date().to_string());
+        assert_eq!("ACME Corp", e1.title());
+        assert_eq!("Salary January", e1.comment());
+        assert_eq!(1000.0, e1.revenue().unwrap());
+        assert!(!e1.has_expense());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("REF-0002", e2.id());
+        assert_eq!("2020-01-05", e2.date().to_string());
+        assert_eq!("Supermarket", e2.title());
+        assert_eq!("Card 1234 Vienna", e2.comment());
+        assert_eq!(-23.4, e2.expense().unwrap());
+
+        // Batch
+        let e3 = &r1.entries[2];
+        assert_eq!("REF-0003-A", e3.id());
+        assert_eq!("Landlord", e3.title());
+        assert_eq!("Rent January", e3.comment());
+        assert_eq!(-100.0, e3.expense().unwrap());
+
+        let e4 = &r1.entries[3];
+        assert_eq!("REF-0003-2", e4.id());
+        assert_eq!("Electricity", e4.title());
+        assert_eq!(-50.0, e4.expense().unwrap());
+    }
+
+    #[test]
+    fn test_read_camt054_1() {
+        let r1 = read_camt(CAMT054).unwrap();
+        assert_eq!(2, r1.entries.len());
+        assert!(r1.start.is_none());
+
+        let e1 = &r1.entries[0];
+        assert_eq!("N-1", e1.id());
+        assert_eq!("Refund", e1.title());
+        assert_eq!(12.5, e1.revenue().unwrap());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("N-2", e2.id());
+        assert_eq!("Coffee", e2.title());
+        assert_eq!(-4.2, e2.expense().unwrap());
+    }
+
+    #[test]
+    fn test_read_camt_stable_ids1() {
+        let raw = "<Document><Ntry><Amt>1.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts>BOOK</Sts><BookgDt><Dt>2020-01-01</Dt></BookgDt></Ntry></Document>";
+        let r1 = read_camt(raw).unwrap();
+        let r2 = read_camt(raw).unwrap();
+        assert_eq!(r1.entries[0].id(), r2.entries[0].id());
+    }
+
+    #[test]
+    fn test_read_camt_bad1() {
+        assert_eq!(ImportError::InvalidFormat, read_camt("<Document>").unwrap_err());
+        assert_eq!(ImportError::InvalidFormat, read_camt("<Other/>").unwrap_err());
+
+        let raw = "<Document>\n<Ntry><Sts>BOOK</Sts></Ntry></Document>";
+        assert_eq!(ImportError::InvalidDate(2), read_camt(raw).unwrap_err());
+    }
+}
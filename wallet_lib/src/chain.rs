@@ -0,0 +1,189 @@
+//! Hash chain over the month files.
+//!
+//! Every month file carries a SHA-256 hash of its days, its seal and
+//! close and of the hash of the previous month file, in `meta.hash` and
+//! `meta.prev_hash`. YamlFile updates both on every write, so a change
+//! outside of the wallet breaks the chain. `wallet verify` shows where
+//! it breaks. A broken month keeps its hash when it is written again.
+//!
+//! Sealed months are read-only unless unsealed. Sealing a month also seals
+//! all months before it. Closed months are read-only too, but do not
+//! close the months before.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use sha2::{Digest, Sha256};
+
+/// Why a month cannot be changed.
+#[derive(Debug, PartialEq)]
+pub enum SealError {
+    /// Month like `2020-03`.
+    Sealed(String),
+    Closed(String),
+    NotFound(String),
+    /// First month where the chain breaks.
+    Broken(String),
+}
+
+impl Display for SealError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            SealError::Sealed(month) => write!(f, "Month {} is sealed, use --force to change it", month),
+            SealError::Closed(month) => write!(f, "Month {} is closed, use --force to change it", month),
+            SealError::NotFound(month) => write!(f, "Month {} has no entries", month),
+            SealError::Broken(month) => write!(f, "The hash chain is broken at {}, see verify", month),
+        }
+    }
+}
+
+/// Hash of a month file. `days` is the dumped days of the month.
+pub fn hash(prev_hash: &str, days: &str, sealed_at: Option<&str>, closed_at: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(days.as_bytes());
+    if let Some(sealed_at) = sealed_at {
+        hasher.update(b"\nsealed_at: ");
+        hasher.update(sealed_at.as_bytes());
+    }
+    if let Some(closed_at) = closed_at {
+        hasher.update(b"\nclosed_at: ");
+        hasher.update(closed_at.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Chain fields of a month file.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub hash: Option<String>,
+    pub prev_hash: Option<String>,
+    /// Hash of the days, the seal and the close with the stored previous hash.
+    pub computed: String,
+    pub sealed_at: Option<String>,
+    pub closed_at: Option<String>,
+}
+
+impl Link {
+    /// The days were not changed since the hash was written.
+    pub fn is_valid(&self) -> bool {
+        self.hash.as_ref() == Some(&self.computed)
+    }
+
+    /// Changed outside of the wallet. Files written before the chain
+    /// existed have neither hash.
+    pub fn is_broken(&self) -> bool {
+        match self.hash {
+            Some(_) => !self.is_valid(),
+            None => self.prev_hash.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainBreak {
+    /// Written before the chain existed or removed.
+    MissingHash,
+    /// The days do not match the hash.
+    Content,
+    /// The previous hash does not match the month before.
+    Link,
+}
+
+impl Display for ChainBreak {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            ChainBreak::MissingHash => write!(f, "no hash"),
+            ChainBreak::Content => write!(f, "entries changed"),
+            ChainBreak::Link => write!(f, "previous month changed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MonthStatus {
+    pub month: String,
+    pub hash: String,
+    pub sealed: bool,
+    pub error: Option<ChainBreak>,
+}
+
+/// Check every link against its days and the month before.
+/// `links` must be sorted by month.
+pub fn verify(links: &[(String, Link)]) -> Vec<MonthStatus> {
+    let mut prev = String::new();
+
+    links.iter()
+        .map(|(month, link)| {
+            let error = if link.hash.is_none() {
+                Some(ChainBreak::MissingHash)
+            } else if !link.is_valid() {
+                Some(ChainBreak::Content)
+            } else if link.prev_hash.as_deref().unwrap_or_default() != prev {
+                Some(ChainBreak::Link)
+            } else {
+                None
+            };
+
+            let hash = link.hash.clone().unwrap_or_else(|| link.computed.clone());
+            prev = hash.clone();
+
+            MonthStatus {
+                month: month.clone(),
+                hash,
+                sealed: link.sealed_at.is_some(),
+                error,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests_chain {
+    use super::{hash, verify, Link, ChainBreak};
+
+    fn link(prev: &str, days: &str) -> Link {
+        Link {
+            hash: Some(hash(prev, days, None, None)),
+            prev_hash: Some(prev.to_string()),
+            computed: hash(prev, days, None, None),
+            sealed_at: None,
+            closed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_verify1() {
+        assert_eq!(64, hash("", "a", None, None).len());
+        assert_ne!(hash("", "a", None, None), hash("x", "a", None, None));
+        assert_ne!(hash("", "a", None, None), hash("", "a", Some("2020-04-01T00:00:00+00:00"), None));
+        assert_ne!(hash("", "a", Some("2020-04-01T00:00:00+00:00"), None), hash("", "a", None, Some("2020-04-01T00:00:00+00:00")));
+
+        let l1 = link("", "a");
+        let l2 = link(l1.hash.as_ref().unwrap(), "b");
+        let l3 = link(l2.hash.as_ref().unwrap(), "c");
+
+        let s1 = verify(&[("2020-01".to_string(), l1.clone()), ("2020-02".to_string(), l2.clone()), ("2020-03".to_string(), l3.clone())]);
+        assert!(s1.iter().all(|status| status.error.is_none()));
+        assert_eq!(l3.hash, Some(s1[2].hash.clone()));
+
+        // Changed days
+        let mut changed = l2.clone();
+        changed.computed = hash(l1.hash.as_ref().unwrap(), "x", None, None);
+        assert!(changed.is_broken());
+        let s2 = verify(&[("2020-01".to_string(), l1.clone()), ("2020-02".to_string(), changed), ("2020-03".to_string(), l3.clone())]);
+        assert_eq!(Some(ChainBreak::Content), s2[1].error);
+        assert_eq!(None, s2[2].error);
+
+        // Removed month
+        let s3 = verify(&[("2020-01".to_string(), l1), ("2020-03".to_string(), l3)]);
+        assert_eq!(Some(ChainBreak::Link), s3[1].error);
+
+        // Without hash
+        let mut missing = link("", "a");
+        missing.hash = None;
+        assert!(missing.is_broken());
+        missing.prev_hash = None;
+        assert!(!missing.is_broken());
+        assert_eq!(Some(ChainBreak::MissingHash), verify(&[("2020-01".to_string(), missing)])[0].error);
+    }
+}
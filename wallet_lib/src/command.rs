@@ -1,12 +1,30 @@
 
 use std::convert::From;
+use std::fs::{File, read_to_string};
+use std::io::stdout;
+use std::env;
 use crate::wallet::{Wallet, FilterOptions};
-use crate::entry::Entry;
-// use crate::entry::{EntryDisplay, EntryDisplayKind};
+use crate::entry::{Entry, EntryChanges};
+use crate::entry::{EntryDisplay, EntryDisplayKind};
 use crate::epic::Epic;
-use crate::number::NumberType;
+use crate::number::{Number, NumberType, ToDisplay};
 use crate::date::Date;
 use crate::ext::BoolExt;
+use crate::export::{Exporter, ExportFormat};
+use crate::import::{ImportFormat, parse as parse_import, parse_amount};
+use crate::prompt::{Prompt, PromptError, parse_date};
+use crate::tui::Tui;
+use crate::search::Search;
+use crate::query::Expr;
+use crate::sort::{SortBy, GroupBy};
+use crate::stats::Stats;
+use crate::forecast::{Forecast, FORECAST_MONTHS};
+use crate::wallet::AddResult;
+use crate::merge::{MergeStrategy, Resolution};
+use crate::crypt::{self, CryptError};
+
+/// Default number of operations shown by the history and log commands.
+const HISTORY_LIMIT: usize = 20;
 
 /// Command options hold all available options for ALL commands.
 /// Not all commands will us all options.
@@ -22,12 +40,42 @@ pub struct CommandOptions {
     pub filter_revenue: Option<bool>,
     pub filter_expense: Option<bool>,
     pub category: Option<String>,
+    pub new_category: Option<String>,
     pub comment: Option<String>,
+    /// Also change sealed or closed months.
     pub force: bool,
+    /// Add Entries even if their ID already exists.
+    pub allow_duplicate: bool,
+    pub interactive: bool,
     pub epic: Option<String>,
     pub handle: Option<String>,
+    pub new_handle: Option<String>,
     pub bgcolor: Option<String>,
     pub long: Option<bool>, // true = long, false = short
+    pub export_format: Option<ExportFormat>,
+    pub export_aggregates: bool,
+    pub output_path: Option<String>,
+    pub currency: Option<String>,
+    pub import_format: Option<ImportFormat>,
+    pub import_path: Option<String>,
+    pub search: Option<Search>,
+    pub min: Option<NumberType>,
+    pub max: Option<NumberType>,
+    pub query: Option<Expr>,
+    pub sort: Option<SortBy>,
+    pub reverse: bool,
+    pub group_by: Option<GroupBy>,
+    pub carry_over: bool,
+    pub json: bool,
+    pub forecast_months: Option<u32>,
+    pub changes: EntryChanges,
+    pub dry_run: bool,
+    pub limit: Option<usize>,
+    pub git: bool,
+    pub rev: Option<String>,
+    pub merge_path: Option<String>,
+    pub merge_strategy: Option<MergeStrategy>,
+    pub sync_path: Option<String>,
 }
 
 /// Common Options for commands.
@@ -45,12 +93,40 @@ impl CommandOptions {
             filter_revenue: None,
             filter_expense: None,
             category: None,
+            new_category: None,
             comment: None,
             force: false,
+            allow_duplicate: false,
+            interactive: false,
             epic: None,
             handle: None,
+            new_handle: None,
             bgcolor: None,
             long: None,
+            export_format: None,
+            export_aggregates: false,
+            output_path: None,
+            currency: None,
+            import_format: None,
+            import_path: None,
+            search: None,
+            min: None,
+            max: None,
+            query: None,
+            sort: None,
+            reverse: false,
+            group_by: None,
+            carry_over: false,
+            json: false,
+            forecast_months: None,
+            changes: EntryChanges::new(),
+            dry_run: false,
+            limit: None,
+            git: false,
+            rev: None,
+            merge_path: None,
+            merge_strategy: None,
+            sync_path: None,
         }
     }
 
@@ -68,8 +144,35 @@ pub enum CommandKind {
     InitCommand,
     AddCommand,
     EpicCommand,
+    EpicListCommand,
+    EpicEditCommand,
+    EpicRenameCommand,
+    EpicRemoveCommand,
+    EpicMergeCommand,
+    CategoryListCommand,
+    CategoryRenameCommand,
+    CategoryMergeCommand,
+    BulkEditCommand,
+    UndoCommand,
+    RedoCommand,
+    HistoryCommand,
+    LogCommand,
+    ShowCommand,
+    MergeCommand,
+    SyncCommand,
+    EncryptCommand,
+    DecryptCommand,
+    VerifyCommand,
+    SealCommand,
+    CloseCommand,
     ListCommand,
     HtmlCommand,
+    ExportCommand,
+    ImportCommand,
+    TuiCommand,
+    SearchCommand,
+    StatsCommand,
+    ForecastCommand,
 }
 
 #[derive(Debug)]
@@ -91,38 +194,242 @@ impl Command {
     pub fn exec(&self) {
         println!("-> Command::exec()");
 
+        // Encrypted wallets need the passphrase first. Encrypt asks for a new one.
+        if !matches!(self.kind, CommandKind::None | CommandKind::EncryptCommand) {
+            let wallet = Wallet::new(self.options.get_wallet_path());
+            if !unlock(&wallet) {
+                return;
+            }
+            if let Err(error) = wallet.check_files() {
+                println!("Error: {}", error);
+                return;
+            }
+        }
+
         match self.kind {
             CommandKind::None => (),
             CommandKind::InitCommand => self.exec_init(),
             CommandKind::AddCommand => self.exec_add(),
             CommandKind::EpicCommand => self.exec_epic(),
+            CommandKind::EpicListCommand => self.exec_epic_list(),
+            CommandKind::EpicEditCommand => self.exec_epic_edit(),
+            CommandKind::EpicRenameCommand => self.exec_epic_rename(),
+            CommandKind::EpicRemoveCommand => self.exec_epic_remove(),
+            CommandKind::EpicMergeCommand => self.exec_epic_merge(),
+            CommandKind::CategoryListCommand => self.exec_category_list(),
+            CommandKind::CategoryRenameCommand => self.exec_category_rename(),
+            CommandKind::CategoryMergeCommand => self.exec_category_merge(),
+            CommandKind::BulkEditCommand => self.exec_bulk_edit(),
+            CommandKind::UndoCommand => self.exec_undo(),
+            CommandKind::RedoCommand => self.exec_redo(),
+            CommandKind::HistoryCommand => self.exec_history(),
+            CommandKind::LogCommand => self.exec_log(),
+            CommandKind::ShowCommand => self.exec_show(),
+            CommandKind::MergeCommand => self.exec_merge(),
+            CommandKind::SyncCommand => self.exec_sync(),
+            CommandKind::EncryptCommand => self.exec_encrypt(),
+            CommandKind::DecryptCommand => self.exec_decrypt(),
+            CommandKind::VerifyCommand => self.exec_verify(),
+            CommandKind::SealCommand => self.exec_seal(),
+            CommandKind::CloseCommand => self.exec_close(),
             CommandKind::ListCommand => self.exec_list(),
             CommandKind::HtmlCommand => self.exec_html(),
+            CommandKind::ExportCommand => self.exec_export(),
+            CommandKind::ImportCommand => self.exec_import(),
+            CommandKind::TuiCommand => self.exec_tui(),
+            CommandKind::SearchCommand => self.exec_search(),
+            CommandKind::StatsCommand => self.exec_stats(),
+            CommandKind::ForecastCommand => self.exec_forecast(),
         }
     }
 
     /// Init
     fn exec_init(&self) {
         println!("-> Command::exec_init()");
-        Wallet::new(self.options.get_wallet_path());
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+
+        if self.options.git {
+            match wallet.init_git() {
+                Ok(_) => println!("Git: Yes"),
+                Err(error) => println!("Error: {}", error),
+            }
+        }
     }
 
     /// Add
     fn exec_add(&self) {
         println!("-> Command::exec_add()");
 
+        if self.options.interactive {
+            self.exec_add_interactive();
+            return;
+        }
+
         let entry = Entry::from(self.options.clone());
-        let wallet = Wallet::new(self.options.get_wallet_path());
-        let added = wallet.add(entry, self.options.force);
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+        let added = wallet.add(entry, self.options.allow_duplicate);
         println!("Added: {}", added.to_string());
     }
 
+    /// Add in interactive mode. Asks for one Entry after another
+    /// until the user declines to add another one.
+    fn exec_add_interactive(&self) {
+        println!("-> Command::exec_add_interactive()");
+
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        // Completion values.
+        let mut categories = wallet.categories();
+        let mut epics: Vec<String> = wallet.epics().iter().map(|epic| epic.handle()).collect();
+        for epic in wallet.filter(FilterOptions::new()).epics.keys() {
+            if !epics.contains(epic) {
+                epics.push(epic.clone());
+            }
+        }
+        epics.sort();
+
+        let mut prompt = Prompt::new();
+
+        loop {
+            let entry = match self.ask_entry(&mut prompt, &categories, &epics) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                },
+            };
+
+            // Summary
+            println!();
+            println!("Title:    {}", entry.title());
+            println!("Date:     {}", entry.date().ymd());
+            if entry.has_revenue() {
+                println!("Revenue:  {:.2}", entry.revenue().unwrap());
+            }
+            if entry.has_expense() {
+                println!("Expense:  {:.2}", entry.expense().unwrap());
+            }
+            println!("Category: {}", entry.category());
+            println!("Epic:     {}", entry.epic());
+            if !entry.comment().is_empty() {
+                println!("Comment:  {}", entry.comment());
+            }
+            println!();
+
+            match prompt.confirm("Add this entry?", true) {
+                Ok(true) => {
+                    if !categories.contains(&entry.category()) {
+                        categories.push(entry.category());
+                        categories.sort();
+                    }
+                    if !epics.contains(&entry.epic()) {
+                        epics.push(entry.epic());
+                        epics.sort();
+                    }
+
+                    let added = wallet.add(entry, self.options.allow_duplicate);
+                    println!("Added: {}", added);
+                },
+                Ok(false) => (),
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                },
+            }
+
+            match prompt.confirm("Add another entry?", false) {
+                Ok(true) => (),
+                _ => return,
+            }
+        }
+    }
+
+    /// Ask for all fields of one Entry. Values given on the
+    /// command line are the defaults.
+    fn ask_entry(&self, prompt: &mut Prompt, categories: &[String], epics: &[String]) -> Result<Entry, PromptError> {
+        let defaults = Entry::from(self.options.clone());
+        let mut entry = Entry::new();
+
+        // Title
+        loop {
+            let title = prompt.ask("Title", &defaults.title())?;
+            if !title.is_empty() {
+                entry.set_title(title);
+                break;
+            }
+        }
+
+        // Date, defaults to today.
+        let today = defaults.date();
+        loop {
+            let date = prompt.ask("Date", &today.ymd())?;
+            match parse_date(&date, today) {
+                Some(date) => {
+                    entry.set_date(date);
+                    break;
+                },
+                None => println!("Invalid date: {}", date),
+            }
+        }
+
+        // Revenue or Expense
+        let kind_default = if defaults.has_revenue() { "r" } else { "e" };
+        let revenue = loop {
+            let kind = prompt.ask_complete("Revenue or expense (r/e)", kind_default, &["revenue".to_string(), "expense".to_string()])?;
+            match kind.to_lowercase().as_str() {
+                "r" | "revenue" => break true,
+                "e" | "expense" => break false,
+                _ => println!("Please answer r or e."),
+            }
+        };
+
+        let amount_default = if revenue {
+            defaults.revenue().unwrap()
+        } else {
+            -defaults.expense().unwrap()
+        };
+        let amount_default = if amount_default > 0.0 {
+            format!("{:.2}", amount_default)
+        } else {
+            String::new()
+        };
+
+        loop {
+            let amount = prompt.ask("Amount", &amount_default)?;
+            match parse_amount(&amount) {
+                Some(amount) => {
+                    if revenue {
+                        entry.set_revenue(amount);
+                    } else {
+                        entry.set_expense(amount);
+                    }
+                    break;
+                },
+                None => println!("Invalid amount: {}", amount),
+            }
+        }
+
+        // Category
+        let category = prompt.ask_complete("Category", &defaults.category(), categories)?;
+        entry.set_category(category);
+
+        // Epic
+        let epic = prompt.ask_complete("Epic", &defaults.epic(), epics)?;
+        entry.set_epic(epic);
+
+        // Comment
+        let comment = prompt.ask("Comment", &defaults.comment())?;
+        entry.set_comment(comment);
+
+        Ok(entry)
+    }
+
     /// Epic
     fn exec_epic(&self) {
         println!("-> Command::exec_epic()");
 
-        // TODO: --remove
-
         let mut epic = Epic::new();
 
         if let Some(handle) = &self.options.handle {
@@ -140,6 +447,454 @@ impl Command {
         println!("Added: {}", added.yn());
     }
 
+    /// Epic List
+    fn exec_epic_list(&self) {
+        println!("-> Command::exec_epic_list()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let result = wallet.filter(FilterOptions::new());
+
+        println!("Handle               Title                Color    Entries    Revenue    Expense    Balance");
+        for epic in wallet.epics() {
+            let handle = epic.handle();
+            let count = result.entries.iter().filter(|entry| entry.epic() == handle).count();
+
+            let (revenue, expense, balance) = match result.epics.get(&handle) {
+                Some(epic_sum) => (epic_sum.revenue, epic_sum.expense, epic_sum.balance),
+                None => (Number::new(), Number::new(), Number::new()),
+            };
+
+            println!("{:<20} {:<20} {:<8} {:>7} {:>10.2} {:>10.2} {:>10.2}",
+                handle,
+                epic.title(),
+                epic.bgcolor(),
+                count,
+                revenue.to_display(),
+                expense.to_display(),
+                balance.to_display());
+        }
+    }
+
+    /// Epic Edit
+    fn exec_epic_edit(&self) {
+        println!("-> Command::exec_epic_edit()");
+
+        let handle = self.options.handle.as_ref().expect("No epic handle given.");
+        let wallet = Wallet::new(self.options.get_wallet_path());
+
+        let mut epic = wallet.find_epic(handle).unwrap_or_else(|error| panic!("{}", error));
+        if let Some(title) = &self.options.title {
+            epic.set_title(title.into());
+        }
+        if let Some(bgcolor) = &self.options.bgcolor {
+            epic.set_bgcolor(bgcolor.into());
+        }
+
+        match wallet.update_epic(epic) {
+            Ok(_) => println!("Updated: Yes"),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Epic Rename
+    fn exec_epic_rename(&self) {
+        println!("-> Command::exec_epic_rename()");
+
+        let from = self.options.handle.as_ref().expect("No epic handle given.");
+        let to = self.options.new_handle.as_ref().expect("No new epic handle given.");
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        match wallet.rename_epic(from, to) {
+            Ok(n) => println!("Renamed: {} -> {} ({} entries changed)", from, to, n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Epic Remove
+    fn exec_epic_remove(&self) {
+        println!("-> Command::exec_epic_remove()");
+
+        let handle = self.options.handle.as_ref().expect("No epic handle given.");
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        match wallet.remove_epic(handle, self.options.force) {
+            Ok(n) => println!("Removed: {} ({} entries moved to default)", handle, n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Epic Merge
+    fn exec_epic_merge(&self) {
+        println!("-> Command::exec_epic_merge()");
+
+        let from = self.options.handle.as_ref().expect("No epic handle given.");
+        let into = self.options.new_handle.as_ref().expect("No target epic handle given.");
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        match wallet.merge_epics(from, into) {
+            Ok(n) => println!("Merged: {} -> {} ({} entries changed)", from, into, n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Category List
+    fn exec_category_list(&self) {
+        println!("-> Command::exec_category_list()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let result = wallet.filter(FilterOptions::new());
+
+        let mut names: Vec<&String> = result.categories.keys().collect();
+        names.sort();
+
+        println!("Category             Entries    Revenue    Expense    Balance");
+        for name in names {
+            let category_sum = &result.categories[name];
+            let count = result.entries.iter().filter(|entry| &entry.category() == name).count();
+
+            println!("{:<20} {:>7} {:>10.2} {:>10.2} {:>10.2}",
+                name,
+                count,
+                category_sum.revenue.to_display(),
+                category_sum.expense.to_display(),
+                category_sum.balance.to_display());
+        }
+    }
+
+    /// Category Rename
+    fn exec_category_rename(&self) {
+        println!("-> Command::exec_category_rename()");
+
+        let from = self.options.category.as_ref().expect("No category given.");
+        let to = self.options.new_category.as_ref().expect("No new category given.");
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        match wallet.rename_category(from, to) {
+            Ok(n) => println!("Renamed: {} -> {} ({} entries changed)", from, to, n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Category Merge
+    fn exec_category_merge(&self) {
+        println!("-> Command::exec_category_merge()");
+
+        let from = self.options.category.as_ref().expect("No category given.");
+        let into = self.options.new_category.as_ref().expect("No target category given.");
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        match wallet.merge_categories(from, into) {
+            Ok(n) => println!("Merged: {} -> {} ({} entries changed)", from, into, n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Bulk Edit
+    fn exec_bulk_edit(&self) {
+        println!("-> Command::exec_bulk_edit()");
+
+        let changes = &self.options.changes;
+        if changes.is_empty() {
+            println!("Error: No changes given, use --set-title, --set-category, --set-epic or --set-comment.");
+            return;
+        }
+
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        // Preview
+        let preview = wallet.bulk_edit_preview(FilterOptions::from(self.options.clone()), changes);
+        if preview.is_empty() {
+            println!("No entries to change.");
+            return;
+        }
+
+        for (entry, diff) in &preview {
+            println!("{} {} {}", entry.id(), entry.date().ymd(), entry.title());
+            for change in diff {
+                println!("  {:<9} '{}' -> '{}'", change.field, change.old, change.new);
+            }
+        }
+        println!();
+        println!("Entries: {}", preview.len());
+
+        if self.options.dry_run {
+            return;
+        }
+
+        if !self.options.force {
+            let mut prompt = Prompt::new();
+            match prompt.confirm("Apply these changes?", false) {
+                Ok(true) => (),
+                Ok(false) => return,
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                },
+            }
+        }
+
+        match wallet.bulk_edit(FilterOptions::from(self.options.clone()), changes) {
+            Ok(n) => println!("Changed: {} entries", n),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Undo
+    fn exec_undo(&self) {
+        println!("-> Command::exec_undo()");
+
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+        match wallet.undo() {
+            Ok(operation) => println!("Undone: {} ({})", operation.name, operation.time),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Redo
+    fn exec_redo(&self) {
+        println!("-> Command::exec_redo()");
+
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+        match wallet.redo() {
+            Ok(operation) => println!("Redone: {} ({})", operation.name, operation.time),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// History
+    fn exec_history(&self) {
+        println!("-> Command::exec_history()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let history = wallet.history();
+        let operations = history.operations();
+
+        if operations.is_empty() {
+            println!("No operations found.");
+            return;
+        }
+
+        let limit = self.options.limit.unwrap_or(HISTORY_LIMIT);
+        let skip = operations.len().saturating_sub(limit);
+
+        println!("  #  Time                 Files  Operation");
+        for (n, (operation, applied)) in operations.iter().enumerate().skip(skip) {
+            println!("{:>3}  {}  {:>5}  {}{}",
+                n + 1,
+                operation.time,
+                operation.files.len(),
+                operation.name,
+                if *applied { "" } else { " (undone)" });
+        }
+    }
+
+    /// Log
+    fn exec_log(&self) {
+        println!("-> Command::exec_log()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let git = match wallet.git() {
+            Some(git) => git,
+            None => {
+                println!("Error: Git is not enabled, use init --git");
+                return;
+            },
+        };
+
+        match git.log(self.options.limit.unwrap_or(HISTORY_LIMIT)) {
+            Ok(commits) => {
+                for commit in commits {
+                    println!("{}  {}  {}", commit.rev, commit.time, commit.message);
+                }
+            },
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Show
+    fn exec_show(&self) {
+        println!("-> Command::exec_show()");
+
+        let rev = self.options.rev.as_ref().expect("No revision given.");
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let git = match wallet.git() {
+            Some(git) => git,
+            None => {
+                println!("Error: Git is not enabled, use init --git");
+                return;
+            },
+        };
+
+        match git.show(rev) {
+            Ok(output) => print!("{}", output),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Merge
+    fn exec_merge(&self) {
+        println!("-> Command::exec_merge()");
+
+        let path = self.options.merge_path.as_ref().expect("No wallet path given.");
+        let strategy = self.options.merge_strategy.unwrap_or(MergeStrategy::Newest);
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let other = Wallet::new(path.to_string());
+        if !unlock(&other) {
+            return;
+        }
+
+        let mut prompt = Prompt::new();
+        let report = wallet.merge(&other, strategy, |ours, theirs| {
+            println!();
+            println!("Conflict: {}", ours.id());
+            println!("  ours    {} {:>10.2}  {:<10} {:<10} {}", ours.date().ymd(), ours.balance().unwrap(), ours.category(), ours.epic(), ours.title());
+            println!("  theirs  {} {:>10.2}  {:<10} {:<10} {}", theirs.date().ymd(), theirs.balance().unwrap(), theirs.category(), theirs.epic(), theirs.title());
+
+            loop {
+                match prompt.ask("Keep ours or theirs? (o/t)", "o").as_deref() {
+                    Ok("o") | Ok("ours") => return Resolution::Ours,
+                    Ok("t") | Ok("theirs") => return Resolution::Theirs,
+                    Ok(_) => println!("Please answer o or t."),
+                    // Keep our Entry when aborted.
+                    Err(_) => return Resolution::Ours,
+                }
+            }
+        });
+
+        println!();
+        report.show();
+    }
+
+    /// Sync
+    fn exec_sync(&self) {
+        println!("-> Command::exec_sync()");
+
+        let path = self.options.sync_path.as_ref().expect("No wallet path given.");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let other = Wallet::new(path.to_string());
+        if !unlock(&other) {
+            return;
+        }
+
+        let report = wallet.sync(&other);
+        report.show();
+    }
+
+    /// Encrypt
+    fn exec_encrypt(&self) {
+        println!("-> Command::exec_encrypt()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        if wallet.is_encrypted() {
+            println!("Error: {}", CryptError::AlreadyEncrypted);
+            return;
+        }
+        if wallet.git().is_some() {
+            println!("Error: {}", CryptError::Git);
+            return;
+        }
+
+        let passphrase = crypt::passphrase("New passphrase");
+        if passphrase.is_empty() {
+            println!("Error: Empty passphrase");
+            return;
+        }
+        if env::var(crypt::PASSPHRASE_ENV).is_err() && crypt::passphrase("Repeat passphrase") != passphrase {
+            println!("Error: Passphrases do not match");
+            return;
+        }
+
+        match wallet.encrypt(&passphrase) {
+            Ok(n) => {
+                println!("Encrypted: {} files", n);
+                println!("The history was cleared.");
+            },
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Decrypt
+    fn exec_decrypt(&self) {
+        println!("-> Command::exec_decrypt()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        match wallet.decrypt() {
+            Ok(n) => {
+                println!("Decrypted: {} files", n);
+                println!("The history was cleared.");
+            },
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Verify
+    fn exec_verify(&self) {
+        println!("-> Command::exec_verify()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let status = wallet.verify();
+
+        if status.is_empty() {
+            println!("No months found.");
+            return;
+        }
+
+        println!("Month    Status                  Sealed  Hash");
+        for month in &status {
+            println!("{:<8} {:<23} {:<6}  {}",
+                month.month,
+                month.error.map_or("ok".to_string(), |error| error.to_string()),
+                if month.sealed { "Yes" } else { "" },
+                &month.hash[..16]);
+        }
+
+        match status.iter().find(|month| month.error.is_some()) {
+            Some(broken) => println!("Chain: broken at {}", broken.month),
+            None => {
+                println!("Chain: OK");
+                println!("Head:  {}", status.last().unwrap().hash);
+            },
+        }
+    }
+
+    /// Seal
+    fn exec_seal(&self) {
+        println!("-> Command::exec_seal()");
+
+        let date = self.options.date.as_ref().expect("No month given.");
+        let wallet = Wallet::new(self.options.get_wallet_path());
+
+        match wallet.seal(date) {
+            Ok(months) if months.is_empty() => println!("Sealed: already sealed"),
+            Ok(months) => println!("Sealed: {}", months.join(", ")),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    /// Close
+    fn exec_close(&self) {
+        println!("-> Command::exec_close()");
+
+        let date = self.options.date.as_ref().expect("No month given.");
+        let wallet = Wallet::new(self.options.get_wallet_path());
+
+        if wallet.close(date) {
+            println!("Closed: {}", date.ym());
+        } else {
+            println!("Closed: {} was closed already", date.ym());
+        }
+    }
+
     /// List
     ///
     /// https://doc.rust-lang.org/std/fmt/#named-parameters
@@ -147,30 +902,44 @@ impl Command {
     fn exec_list(&self) {
         println!("-> Command::exec_list()");
 
-        // let options = FilterOptions::from(self.options.clone());
-        // let wallet = Wallet::new(self.options.get_wallet_path());
+        let options = FilterOptions::from(self.options.clone());
+        let wallet = Wallet::new(self.options.get_wallet_path());
 
-        // let result = wallet.filter(options);
+        let result = wallet.filter(options);
 
         // TODO: dynamic DisplayKind. use terminal width to determine which EntryDisplayKind value to use when no option is provided. maybe calculate width.
 
         // Kind
-        // let mut kind = EntryDisplayKind::Normal;
+        let mut kind = EntryDisplayKind::Normal;
 
-        // if let Some(long_opt) = self.options.long {
-        //     if long_opt {
-        //         kind = EntryDisplayKind::Long;
-        //     } else {
-        //         kind = EntryDisplayKind::Short;
-        //     }
-        // }
+        if let Some(long_opt) = self.options.long {
+            if long_opt {
+                kind = EntryDisplayKind::Long;
+            } else {
+                kind = EntryDisplayKind::Short;
+            }
+        }
+
+        let entries: Vec<Entry> = result.entries.iter().map(|entry| (**entry).clone()).collect();
+        let mut entry_display = EntryDisplay::new(entries, kind);
+        if let Some(group_by) = self.options.group_by {
+            entry_display.set_group_by(group_by, self.options.reverse);
+        }
 
-        // TODO: use filterresult here
-        // let entry_display = EntryDisplay::new(result, kind);
-        // entry_display.show();
+        // Carry over the balance from before the first entry.
+        if self.options.carry_over {
+            let first_date = result.entries.iter()
+                .map(|entry| entry.date())
+                .fold(None, |first: Option<Date>, date| match first {
+                    Some(first) if first < date => Some(first),
+                    _ => Some(date),
+                });
 
-        // Debug
-        // println!("-> result: {:?}", result);
+            if let Some(first_date) = first_date {
+                entry_display.set_start_balance(wallet.balance_before(first_date));
+            }
+        }
+        entry_display.show();
     }
 
     /// HTML
@@ -187,7 +956,167 @@ impl Command {
             println!("-> found html path: {}", html_path);
             wallet.set_html_path(html_path.into());
         }
-        wallet.html(options);
+        wallet.html(options, self.options.carry_over);
+    }
+
+    /// Export
+    fn exec_export(&self) {
+        println!("-> Command::exec_export()");
+
+        let options = FilterOptions::from(self.options.clone());
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let result = wallet.filter(options);
+
+        let format = self.options.export_format.unwrap_or(ExportFormat::Json);
+        let mut exporter = Exporter::new(format);
+        exporter.set_aggregates(self.options.export_aggregates);
+        exporter.set_currency(self.options.currency.clone());
+
+        if let Some(output_path) = &self.options.output_path {
+            println!("-> output_path: {}", output_path);
+
+            let file = File::create(output_path).expect("Cannot create export file.");
+            exporter.export(&result, file).expect("Export failed.");
+        } else {
+            exporter.export(&result, stdout()).expect("Export failed.");
+        }
+    }
+
+    /// Import
+    fn exec_import(&self) {
+        println!("-> Command::exec_import()");
+
+        let format = self.options.import_format.expect("No import format given.");
+        let path = self.options.import_path.as_ref().expect("No import file given.");
+
+        let raw = read_to_string(path).expect("Cannot read import file.");
+        let result = match parse_import(format, &raw) {
+            Ok(result) => result,
+            Err(error) => {
+                println!("Import failed: {}", error);
+                return;
+            },
+        };
+
+        let mut wallet = Wallet::new(self.options.get_wallet_path());
+        wallet.set_unseal(self.options.force);
+
+        let start = result.start;
+        let end = result.end;
+        let skipped = result.skipped;
+        let entries = result.entries;
+
+        // One operation in the history.
+        let mut added: u64 = 0;
+        let mut exists: u64 = 0;
+        let mut read_only: u64 = 0;
+        wallet.record(format!("import: {}", path), || {
+            for entry in entries {
+                match wallet.add(entry, self.options.allow_duplicate) {
+                    AddResult::Added(_) => added += 1,
+                    AddResult::ExistsInIndex => exists += 1,
+                    AddResult::ReadOnly(_) => read_only += 1,
+                }
+            }
+        });
+
+        println!("Added: {}", added);
+        println!("Exists: {}", exists);
+        println!("Read-only: {}", read_only);
+        println!("Skipped: {}", skipped);
+
+        if let (Some(start), Some(end)) = (start, end) {
+            println!("Period: {} - {}", start, end);
+        }
+    }
+
+    /// Search
+    fn exec_search(&self) {
+        println!("-> Command::exec_search()");
+
+        let search = self.options.search.as_ref().expect("No search query given.");
+        let wallet = Wallet::new(self.options.get_wallet_path());
+
+        let hits = wallet.search(search);
+        if hits.is_empty() {
+            println!("No entries found.");
+            return;
+        }
+
+        println!("Score  Date          Balance  File               Title");
+        for hit in &hits {
+            println!("{:>5}  {} {:>10.2}  {:<18} {}",
+                hit.score,
+                hit.entry.date().ymd(),
+                hit.entry.balance().unwrap(),
+                hit.month_file,
+                hit.entry.title());
+
+            let comment = hit.entry.comment();
+            if !comment.is_empty() {
+                println!("{:>49} {}", "", comment);
+            }
+        }
+        println!("{} entries found.", hits.len());
+    }
+
+    /// Stats
+    fn exec_stats(&self) {
+        println!("-> Command::exec_stats()");
+
+        let options = FilterOptions::from(self.options.clone());
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let result = wallet.filter(options);
+
+        let stats = Stats::from_result(&result);
+        if self.options.json {
+            serde_json::to_writer_pretty(stdout(), &stats).expect("Cannot write JSON.");
+            println!();
+        } else {
+            stats.show();
+        }
+    }
+
+    /// Forecast
+    fn exec_forecast(&self) {
+        println!("-> Command::exec_forecast()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let result = wallet.filter(FilterOptions::new());
+
+        let months = self.options.forecast_months.unwrap_or(FORECAST_MONTHS);
+        let forecast = Forecast::from_result(&result, Date::today(), months);
+        if self.options.json {
+            serde_json::to_writer_pretty(stdout(), &forecast).expect("Cannot write JSON.");
+            println!();
+        } else {
+            forecast.show();
+        }
+    }
+
+    /// TUI
+    fn exec_tui(&self) {
+        println!("-> Command::exec_tui()");
+
+        let wallet = Wallet::new(self.options.get_wallet_path());
+        let mut tui = Tui::new(wallet);
+        tui.run().expect("Terminal UI failed.");
+    }
+}
+
+/// Ask for the passphrase when the wallet is encrypted.
+/// Returns false when the wallet stays locked.
+fn unlock(wallet: &Wallet) -> bool {
+    if !wallet.is_locked() {
+        return true;
+    }
+
+    match wallet.unlock(&crypt::passphrase("Passphrase")) {
+        Ok(_) => true,
+        Err(error) => {
+            println!("Error: {}", error);
+            false
+        },
     }
 }
 
@@ -0,0 +1,295 @@
+//! Encryption of the data files at rest.
+//!
+//! The key is derived from a passphrase with Argon2id and the salt from
+//! `data/crypt.json`. Each file is encrypted with XChaCha20-Poly1305 and
+//! a random nonce, and stored as one line of text, so the history and
+//! git still work on encrypted files. The file name is authenticated
+//! too, so files cannot be swapped. `crypt.json` also holds an
+//! encrypted check value to tell a wrong passphrase apart.
+//!
+//! Keys of unlocked wallets are kept for the whole process. YamlFile
+//! looks up the key by the directory of the file.
+
+use std::path::{Path, PathBuf};
+use std::fs::{read_to_string, remove_file, write};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::env;
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use argon2::Argon2;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce, Key};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use serde::{Serialize, Deserialize};
+
+const CRYPT_FILE: &str = "crypt.json";
+
+/// Start of an encrypted file.
+const PREFIX: &str = "wallet-encrypted:1:";
+
+const CHECK: &str = "wallet";
+
+/// Environment variable for the passphrase, instead of asking.
+pub const PASSPHRASE_ENV: &str = "WALLET_PASSPHRASE";
+
+#[derive(Debug, PartialEq)]
+pub enum CryptError {
+    WrongPassphrase,
+    /// The wallet is encrypted but was not unlocked.
+    Locked,
+    NotEncrypted,
+    AlreadyEncrypted,
+    /// File name.
+    Corrupt(String),
+    /// File name of a plain file in an encrypted wallet.
+    Plain(String),
+    /// Git commits would keep the plain files.
+    Git,
+}
+
+impl Display for CryptError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            CryptError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            CryptError::Locked => write!(f, "The wallet is encrypted, passphrase required"),
+            CryptError::NotEncrypted => write!(f, "The wallet is not encrypted"),
+            CryptError::AlreadyEncrypted => write!(f, "The wallet is already encrypted"),
+            CryptError::Corrupt(file) => write!(f, "Cannot decrypt '{}', the file is corrupt", file),
+            CryptError::Plain(file) => write!(f, "The file '{}' is not encrypted, but the wallet is", file),
+            CryptError::Git => write!(f, "The wallet uses git, older commits would keep the plain files"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CryptData {
+    salt: String,
+    check: String,
+}
+
+fn keys() -> &'static Mutex<HashMap<PathBuf, [u8; 32]>> {
+    static KEYS: OnceLock<Mutex<HashMap<PathBuf, [u8; 32]>>> = OnceLock::new();
+    KEYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Same key for different spellings of a directory.
+fn dir_key(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("Cannot derive key");
+    key
+}
+
+/// Name of a data file, authenticated with its content.
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// `file` is the file name the content belongs to.
+fn encrypt(key: &[u8; 32], file: &str, plain: &str) -> String {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let payload = Payload { msg: plain.as_bytes(), aad: file.as_bytes() };
+    let data = cipher.encrypt(&nonce, payload).expect("Cannot encrypt");
+
+    format!("{}{}:{}\n", PREFIX, hex::encode(nonce), hex::encode(data))
+}
+
+/// None when the key is wrong, the content was changed or it belongs
+/// to another file.
+fn decrypt(key: &[u8; 32], file: &str, raw: &str) -> Option<String> {
+    let mut parts = raw.trim().strip_prefix(PREFIX)?.splitn(2, ':');
+    let nonce = hex::decode(parts.next()?).ok()?;
+    let data = hex::decode(parts.next()?).ok()?;
+    if nonce.len() != 24 {
+        return None;
+    }
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let payload = Payload { msg: data.as_ref(), aad: file.as_bytes() };
+    let plain = cipher.decrypt(XNonce::from_slice(&nonce), payload).ok()?;
+    String::from_utf8(plain).ok()
+}
+
+pub fn is_encrypted(data_dir: &Path) -> bool {
+    data_dir.join(CRYPT_FILE).exists()
+}
+
+pub fn is_unlocked(data_dir: &Path) -> bool {
+    keys().lock().unwrap().contains_key(&dir_key(data_dir))
+}
+
+/// Check the passphrase and keep the key.
+pub fn unlock(data_dir: &Path, passphrase: &str) -> Result<(), CryptError> {
+    println!("-> crypt::unlock({:?})", data_dir);
+
+    let raw = read_to_string(data_dir.join(CRYPT_FILE)).map_err(|_| CryptError::NotEncrypted)?;
+    let data: CryptData = serde_json::from_str(&raw).map_err(|_| CryptError::Corrupt(CRYPT_FILE.to_string()))?;
+    let salt = hex::decode(&data.salt).map_err(|_| CryptError::Corrupt(CRYPT_FILE.to_string()))?;
+
+    let key = derive_key(passphrase, &salt);
+    if decrypt(&key, CRYPT_FILE, &data.check).as_deref() != Some(CHECK) {
+        return Err(CryptError::WrongPassphrase);
+    }
+
+    keys().lock().unwrap().insert(dir_key(data_dir), key);
+    Ok(())
+}
+
+/// Forget the key.
+pub fn lock(data_dir: &Path) {
+    keys().lock().unwrap().remove(&dir_key(data_dir));
+}
+
+/// Salt and key for a wallet which is not encrypted yet.
+pub struct NewKey {
+    salt: [u8; 16],
+    key: [u8; 32],
+}
+
+impl NewKey {
+    /// Encrypted content of a data file.
+    pub fn encode(&self, path: &Path, plain: &str) -> String {
+        encrypt(&self.key, &file_name(path), plain)
+    }
+}
+
+/// Create a new salt and derive the key. Nothing is written yet.
+pub fn new_key(data_dir: &Path, passphrase: &str) -> Result<NewKey, CryptError> {
+    println!("-> crypt::new_key({:?})", data_dir);
+
+    if is_encrypted(data_dir) {
+        return Err(CryptError::AlreadyEncrypted);
+    }
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt);
+
+    Ok(NewKey { salt, key })
+}
+
+/// Write the salt and keep the key. Files written afterwards are
+/// encrypted. Existing files must be encrypted with the key before.
+pub fn setup(data_dir: &Path, new_key: NewKey) {
+    println!("-> crypt::setup({:?})", data_dir);
+
+    let data = CryptData {
+        salt: hex::encode(new_key.salt),
+        check: encrypt(&new_key.key, CRYPT_FILE, CHECK),
+    };
+    write(data_dir.join(CRYPT_FILE), serde_json::to_string(&data).unwrap())
+        .expect("Cannot write crypt file");
+
+    keys().lock().unwrap().insert(dir_key(data_dir), new_key.key);
+}
+
+/// Remove the salt. Files written afterwards are plain.
+pub fn remove(data_dir: &Path) -> Result<(), CryptError> {
+    println!("-> crypt::remove({:?})", data_dir);
+
+    if !is_encrypted(data_dir) {
+        return Err(CryptError::NotEncrypted);
+    }
+    if !is_unlocked(data_dir) {
+        return Err(CryptError::Locked);
+    }
+
+    remove_file(data_dir.join(CRYPT_FILE)).expect("Cannot remove crypt file");
+    lock(data_dir);
+    Ok(())
+}
+
+/// Content to write to a data file. Encrypted when its wallet is encrypted.
+pub fn encode(path: &Path, plain: String) -> Result<String, CryptError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    if !is_encrypted(dir) {
+        return Ok(plain);
+    }
+
+    match keys().lock().unwrap().get(&dir_key(dir)) {
+        Some(key) => Ok(encrypt(key, &file_name(path), &plain)),
+        None => Err(CryptError::Locked),
+    }
+}
+
+/// Content read from a data file. Plain files are returned as they are,
+/// unless the wallet is encrypted.
+pub fn decode(path: &Path, raw: String) -> Result<String, CryptError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    if !raw.starts_with(PREFIX) {
+        if is_encrypted(dir) {
+            return Err(CryptError::Plain(file_name(path)));
+        }
+        return Ok(raw);
+    }
+
+    let keys = keys().lock().unwrap();
+    let key = keys.get(&dir_key(dir)).ok_or(CryptError::Locked)?;
+
+    decrypt(key, &file_name(path), &raw).ok_or_else(|| CryptError::Corrupt(file_name(path)))
+}
+
+/// From the environment or asked without echo.
+pub fn passphrase(label: &str) -> String {
+    match env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password(format!("{}: ", label)).expect("Cannot read passphrase"),
+    }
+}
+
+#[cfg(test)]
+mod tests_crypt {
+    use std::fs::{create_dir_all, remove_dir_all};
+    use std::path::PathBuf;
+    use super::{new_key, setup, unlock, lock, remove, encode, decode, is_unlocked, CryptError};
+
+    #[test]
+    fn test_crypt1() {
+        let dir = PathBuf::from("../tmp/tests/crypt1");
+        remove_dir_all(&dir).ok();
+        create_dir_all(&dir).unwrap();
+        let file = dir.join("index.yml");
+
+        // Plain
+        assert_eq!(Ok("a".to_string()), encode(&file, "a".to_string()));
+        assert_eq!(Ok("a".to_string()), decode(&file, "a".to_string()));
+
+        let key = new_key(&dir, "secret").unwrap();
+        let raw = key.encode(&file, "index: []");
+        setup(&dir, key);
+        assert!(matches!(new_key(&dir, "secret"), Err(CryptError::AlreadyEncrypted)));
+        assert!(raw.starts_with("wallet-encrypted:1:"));
+        assert!(!raw.contains("index"));
+        assert_eq!(Ok("index: []".to_string()), decode(&file, raw.clone()));
+
+        // Changed content
+        let changed = raw.replace(":1:", ":1:00");
+        assert_eq!(Err(CryptError::Corrupt("index.yml".to_string())), decode(&file, changed));
+
+        // Other file
+        let other = dir.join("month_2020_01.yml");
+        assert_eq!(Err(CryptError::Corrupt("month_2020_01.yml".to_string())), decode(&other, raw.clone()));
+
+        // Plain file
+        assert_eq!(Err(CryptError::Plain("index.yml".to_string())), decode(&file, "index: []".to_string()));
+
+        lock(&dir);
+        assert_eq!(Err(CryptError::Locked), decode(&file, raw.clone()));
+        assert_eq!(Err(CryptError::Locked), encode(&file, "x".to_string()));
+        assert_eq!(Err(CryptError::WrongPassphrase), unlock(&dir, "wrong"));
+        assert!(!is_unlocked(&dir));
+
+        unlock(&dir, "secret").unwrap();
+        assert_eq!(Ok("index: []".to_string()), decode(&file, raw));
+
+        remove(&dir).unwrap();
+        assert_eq!(Ok("x".to_string()), encode(&file, "x".to_string()));
+    }
+}
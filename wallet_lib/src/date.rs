@@ -1,10 +1,13 @@
 
-use chrono::{NaiveDate, Datelike};
+use chrono::{NaiveDate, Datelike, Weekday, Local};
 use std::fmt::{Display, Formatter, Result as FmtRes, Debug};
 use std::str::FromStr;
 use regex::Regex;
 use std::vec;
 use std::convert::From;
+use std::cmp::Ordering;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error as DeError;
 
 #[derive(Debug)]
 pub enum DateError {
@@ -37,6 +40,17 @@ impl Date {
         }
     }
 
+    /// Today, in local time.
+    pub fn today() -> Self {
+        let now = Local::now().naive_local().date();
+
+        let mut date = Self::new();
+        date.set_year(now.year());
+        date.set_month(now.month());
+        date.set_day(now.day());
+        date
+    }
+
     fn has(&self, p: Parts) -> bool {
         let i = p as u8 - 1;
 
@@ -146,6 +160,15 @@ impl Date {
         items.join("_")
     }
 
+    pub fn weekday(&self) -> Weekday {
+        self.date.weekday()
+    }
+
+    /// ISO Year-Week, like `2020-W02`.
+    pub fn yw(&self) -> String {
+        self.date.format("%G-W%V").to_string()
+    }
+
     /// Year-Month-Day
     pub fn ymd(&self) -> String {
         let mut items: Vec<String> = vec![];
@@ -267,14 +290,15 @@ impl FromStr for Date {
         // println!("-> ymd: {}-{}-{}", y, m, d);
 
         if usage == 0 {
-            Err(DateError::InvalidDate)
-        } else {
-            let d = Self {
-                date: NaiveDate::from_ymd(y, m, d),
-                used: usage,
-            };
+            return Err(DateError::InvalidDate);
+        }
 
-            Ok(d)
+        match NaiveDate::from_ymd_opt(y, m, d) {
+            Some(date) => Ok(Self {
+                date,
+                used: usage,
+            }),
+            None => Err(DateError::InvalidDate),
         }
     }
 }
@@ -319,6 +343,20 @@ impl Debug for Date {
     }
 }
 
+/// Serialized as the same string `Display` produces, e.g. `2019-12-31`.
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Date::from_str(&s).map_err(|_| D::Error::custom(format!("Invalid date: '{}'", s)))
+    }
+}
+
 impl PartialEq for Date {
     fn eq(&self, other: &Self) -> bool {
         self.date.year() == other.date.year() &&
@@ -327,6 +365,13 @@ impl PartialEq for Date {
     }
 }
 
+/// Compares year, month and day, like `PartialEq`.
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.date.cmp(&other.date))
+    }
+}
+
 #[cfg(test)]
 mod tests_basic {
     use super::Date;
@@ -417,6 +462,15 @@ mod tests_eq {
         let d2 = Date::from("1988".to_string());
         assert!(d1 != d2);
     }
+
+    #[test]
+    fn test_date_ord_ymd() {
+        let d1 = Date::from("1987-02-21");
+        let d2 = Date::from("1987-03-01");
+        assert!(d1 < d2);
+        assert!(d2 > d1);
+        assert!(d1 <= Date::from("1987-02-21"));
+    }
 }
 
 #[cfg(test)]
@@ -424,6 +478,13 @@ mod tests_from_str {
     use super::{Date, DateError};
     use std::str::FromStr;
 
+    #[test]
+    fn test_date_from_str_invalid1() {
+        assert!(Date::from_str("2020-13-01").is_err());
+        assert!(Date::from_str("2020-02-30").is_err());
+        assert!(Date::from_str("32").is_err());
+    }
+
     #[test]
     fn test_date_from_str_ok1() {
         let d1 = Date::from_str("1987-02-21").unwrap();
@@ -570,3 +631,27 @@ mod tests_from_trait {
         assert_eq!("1987-02-21", d1.to_string());
     }
 }
+
+#[cfg(test)]
+mod tests_serde {
+    use super::Date;
+
+    #[test]
+    fn test_date_serialize1() {
+        let d1 = Date::from("1987-02-21");
+        assert_eq!("\"1987-02-21\"", serde_json::to_string(&d1).unwrap());
+    }
+
+    #[test]
+    fn test_date_deserialize1() {
+        let d1: Date = serde_json::from_str("\"1987-02-21\"").unwrap();
+        assert_eq!("1987-02-21", d1.to_string());
+        assert!(d1.has_day());
+    }
+
+    #[test]
+    fn test_date_deserialize_bad1() {
+        let d1: Result<Date, _> = serde_json::from_str("\"x\"");
+        assert!(d1.is_err());
+    }
+}
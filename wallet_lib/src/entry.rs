@@ -3,15 +3,18 @@ use std::fmt::{Display, Formatter, Result as FmtRes};
 use std::convert::From;
 use std::str::FromStr;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use crate::date::Date;
 use crate::number::{Number, NumberType, ToDisplay};
 use crate::command::CommandOptions;
 use crate::yaml::{ToYaml, FromYaml};
 use crate::string::ShortString;
+use crate::sort::{group_indices, GroupBy, SortBy};
 use yaml_rust::Yaml;
 use yaml_rust::yaml::Hash;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     id: String,
     title: String,
@@ -22,6 +25,9 @@ pub struct Entry {
     category: String,
     comment: String,
     epic: String,
+    /// Time of the last change. None for Entries written before it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<String>,
 }
 
 impl Entry {
@@ -38,6 +44,7 @@ impl Entry {
             category: "default".to_string(),
             comment: String::new(),
             epic: "default".to_string(),
+            updated_at: None,
         }
     }
 
@@ -83,7 +90,8 @@ impl Entry {
     }
 
     pub fn set_expense(&mut self, v: NumberType) {
-        self.expense = Number::from(-v.abs());
+        // Adding 0.0 turns -0.0 into 0.0.
+        self.expense = Number::from(-v.abs() + 0.0);
         self.calc();
     }
 
@@ -115,10 +123,55 @@ impl Entry {
         self.epic = v;
     }
 
+    pub fn updated_at(&self) -> Option<String> {
+        self.updated_at.clone()
+    }
+
+    pub fn set_updated_at(&mut self, v: Option<String>) {
+        self.updated_at = v;
+    }
+
+    /// Set the time of the last change to now.
+    pub fn touch(&mut self) {
+        let utc: DateTime<Utc> = Utc::now();
+        self.updated_at = Some(utc.format("%FT%T%:z").to_string());
+    }
+
     pub fn balance(&self) -> Number {
         self.balance.clone()
     }
 
+    /// All fields except the ID are equal.
+    pub fn same_content(&self, other: &Entry) -> bool {
+        self.title == other.title
+            && self.date.to_string() == other.date.to_string()
+            && self.revenue.unwrap() == other.revenue.unwrap()
+            && self.expense.unwrap() == other.expense.unwrap()
+            && self.category == other.category
+            && self.comment == other.comment
+            && self.epic == other.epic
+    }
+
+    /// Hash of all fields, which changes with every edit.
+    /// FNV-1a, so it stays the same across builds.
+    pub fn version(&self) -> String {
+        let content = format!("{}\t{}\t{}\t{:.2}\t{:.2}\t{}\t{}\t{}",
+            self.id,
+            self.title,
+            self.date,
+            self.revenue.unwrap(),
+            self.expense.unwrap(),
+            self.category,
+            self.comment,
+            self.epic);
+
+        let hash = content.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+        format!("{:016x}", hash)
+    }
+
     fn calc(&mut self) {
         self.balance = Number::from(self.revenue.unwrap() + self.expense.unwrap());
     }
@@ -221,6 +274,9 @@ impl ToYaml for Entry {
         entry.insert("category".to_string().to_yaml(), self.category().to_yaml());
         entry.insert("comment".to_string().to_yaml(), self.comment().to_yaml());
         entry.insert("epic".to_string().to_yaml(), self.epic().to_yaml());
+        if let Some(updated_at) = self.updated_at() {
+            entry.insert("updated_at".to_string().to_yaml(), updated_at.to_yaml());
+        }
 
         Yaml::Hash(entry)
     }
@@ -319,6 +375,12 @@ impl FromYaml for Entry {
                     entry.epic = epic.to_string();
                 }
             }
+
+            // Updated At
+            let key = "updated_at".to_string().to_yaml();
+            if let Some(Yaml::String(updated_at)) = item_ref.get(&key) {
+                entry.updated_at = Some(updated_at.to_string());
+            }
         }
 
         entry
@@ -359,6 +421,150 @@ impl EntrySum {
     fn inc_balance(&mut self, v: Number) {
         self.balance += v;
     }
+
+    pub fn add(&mut self, entry: &Entry) {
+        self.inc();
+        self.inc_revenue(entry.revenue());
+        self.inc_expense(entry.expense());
+        self.inc_balance(entry.balance());
+    }
+}
+
+/// Balance after each Entry, summed up in chronological order from `start`.
+/// The result has the same order as `entries`.
+pub fn running_balances(entries: &[Entry], start: Number) -> Vec<Number> {
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by(|a, b| SortBy::Date.cmp(&entries[*a], &entries[*b]));
+
+    let mut balances = vec![Number::new(); entries.len()];
+    let mut balance = start;
+    for i in order {
+        balance += entries[i].balance();
+        balances[i] = balance;
+    }
+
+    balances
+}
+
+/// A field which can be changed for many Entries at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryField {
+    Title,
+    Category,
+    Epic,
+    Comment,
+}
+
+impl EntryField {
+    pub const ALL: [EntryField; 4] = [EntryField::Title, EntryField::Category, EntryField::Epic, EntryField::Comment];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EntryField::Title => "title",
+            EntryField::Category => "category",
+            EntryField::Epic => "epic",
+            EntryField::Comment => "comment",
+        }
+    }
+
+    fn get(self, entry: &Entry) -> String {
+        match self {
+            EntryField::Title => entry.title(),
+            EntryField::Category => entry.category(),
+            EntryField::Epic => entry.epic(),
+            EntryField::Comment => entry.comment(),
+        }
+    }
+
+    fn set(self, entry: &mut Entry, value: String) {
+        match self {
+            EntryField::Title => entry.set_title(value),
+            EntryField::Category => entry.set_category(value),
+            EntryField::Epic => entry.set_epic(value),
+            EntryField::Comment => entry.set_comment(value),
+        }
+    }
+}
+
+impl Display for EntryField {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        f.pad(self.name())
+    }
+}
+
+/// Field updates for many Entries at once. Unset fields stay as they are.
+#[derive(Debug, Clone, Default)]
+pub struct EntryChanges {
+    pub title: Option<String>,
+    pub category: Option<String>,
+    pub epic: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl Display for EntryChanges {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        let changes: Vec<String> = EntryField::ALL.iter()
+            .filter_map(|field| self.get(*field).as_ref().map(|value| format!("{}={}", field, value)))
+            .collect();
+
+        write!(f, "{}", changes.join(", "))
+    }
+}
+
+/// A field with its old and new value.
+#[derive(Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: EntryField,
+    pub old: String,
+    pub new: String,
+}
+
+impl EntryChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// New value of a field. None when it stays as it is.
+    pub fn get(&self, field: EntryField) -> &Option<String> {
+        match field {
+            EntryField::Title => &self.title,
+            EntryField::Category => &self.category,
+            EntryField::Epic => &self.epic,
+            EntryField::Comment => &self.comment,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        EntryField::ALL.iter().all(|field| self.get(*field).is_none())
+    }
+
+    /// Fields which would change, without changing the Entry.
+    pub fn diff(&self, entry: &Entry) -> Vec<FieldChange> {
+        EntryField::ALL.iter()
+            .filter_map(|field| {
+                let old = field.get(entry);
+                match self.get(*field) {
+                    Some(new) if *new != old => Some(FieldChange {
+                        field: *field,
+                        old,
+                        new: new.clone(),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns true when the Entry has changed.
+    pub fn apply(&self, entry: &mut Entry) -> bool {
+        let diff = self.diff(entry);
+
+        for change in &diff {
+            change.field.set(entry, change.new.clone());
+        }
+
+        !diff.is_empty()
+    }
 }
 
 pub enum EntryDisplayKind {
@@ -370,6 +576,9 @@ pub enum EntryDisplayKind {
 pub struct EntryDisplay {
     entries: Vec<Entry>,
     kind: EntryDisplayKind,
+    group_by: Option<GroupBy>,
+    reverse: bool,
+    start_balance: Option<Number>,
 }
 
 impl EntryDisplay {
@@ -377,130 +586,140 @@ impl EntryDisplay {
         EntryDisplay {
             entries,
             kind,
+            group_by: None,
+            reverse: false,
+            start_balance: None,
         }
     }
 
+    /// Start the running balance from the balance carried over
+    /// from earlier entries, instead of zero.
+    pub fn set_start_balance(&mut self, start_balance: Number) {
+        self.start_balance = Some(start_balance);
+    }
+
+    /// Show the entries in groups, each with a subtotal.
+    pub fn set_group_by(&mut self, group_by: GroupBy, reverse: bool) {
+        self.group_by = Some(group_by);
+        self.reverse = reverse;
+    }
+
     pub fn show(&self) {
+        println!("-> EntryDisplay::show()");
+
         if self.entries.len() == 0 {
             println!("No entries found.");
             return;
         }
 
-        match self.kind {
-            EntryDisplayKind::Short => self.show_short(),
-            EntryDisplayKind::Normal => self.show_normal(),
-            EntryDisplayKind::Long => self.show_long(),
-        }
-    }
+        self.show_header();
+
+        // Running Balance
+        let start_balance = self.start_balance.unwrap_or_else(Number::new);
+        let running = running_balances(&self.entries, start_balance);
 
-    fn show_short(&self) {
-        println!("-> EntryDisplay::show_short()");
+        if self.start_balance.is_some() {
+            println!("{:<16}{:>10} {:>10} {:>10} {:>10.2}", "CARRIED OVER", "", "", "", start_balance.unwrap());
+        }
 
         let mut sum = EntrySum::new();
 
-        println!("#### Date          Revenue    Expense    Balance  Title");
+        match self.group_by {
+            Some(group_by) => {
+                for (name, indices) in group_indices(&self.entries, group_by, self.reverse) {
+                    println!("---- {}", name);
 
-        for entry in &self.entries {
-            let revenue_number = entry.revenue();
-            let expense_number = entry.expense();
-            let balance_number = entry.balance();
+                    let mut group_sum = EntrySum::new();
+                    for i in indices {
+                        let entry = &self.entries[i];
+                        sum.add(entry);
+                        group_sum.add(entry);
 
-            sum.inc();
-            // sum.inc_revenue(revenue_number.clone());
-            // sum.inc_expense(expense_number.clone());
-            // sum.inc_balance(balance_number.clone());
+                        self.show_entry(sum.n, entry, running[i]);
+                    }
 
-            let title = ShortString::from(entry.title(), 23);
+                    self.show_sum("SUBTOTAL", &group_sum);
+                }
+            },
+            None => {
+                for (entry, balance) in self.entries.iter().zip(&running) {
+                    sum.add(entry);
 
-            println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2}  {}",
-                sum.n,
-                entry.date().ymd(),
-                revenue_number.to_display(),
-                expense_number.to_display(),
-                balance_number.to_display(),
-                title,
-            );
+                    self.show_entry(sum.n, entry, *balance);
+                }
+            },
         }
 
-        println!("TOTAL           {:>10.2} {:>10.2} {:>10.2}",
-            sum.revenue.to_display(),
-            sum.expense.to_display(),
-            sum.balance.to_display());
+        self.show_sum("TOTAL", &sum);
     }
 
-    fn show_normal(&self) {
-        println!("-> EntryDisplay::show_normal()");
-
-        let mut sum = EntrySum::new();
-
-        println!("#### Date          Revenue    Expense    Balance   Category       Epic  Title");
-
-        for entry in &self.entries {
-            let revenue_number = entry.revenue();
-            let expense_number = entry.expense();
-            let balance_number = entry.balance();
-
-            sum.inc();
-            // sum.inc_revenue(revenue_number.clone());
-            // sum.inc_expense(expense_number.clone());
-            // sum.inc_balance(balance_number.clone());
-
-            let category = ShortString::from(entry.category(), 10);
-            let mut epic = ShortString::from(entry.epic(), 10); // TODO: use EpicDisplay here
-            let title = ShortString::from(entry.title(), 23);
-
-            if epic.to_string() == "default".to_string() {
-                epic = ShortString::new();
-            }
-
-            println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2} {:>10} {:>10}  {}",
-                sum.n,
-                entry.date().ymd(),
-                revenue_number.to_display(),
-                expense_number.to_display(),
-                balance_number.to_display(),
-                category.to_string(),
-                epic.to_string(),
-                title.to_string(),
-            );
+    fn show_header(&self) {
+        match self.kind {
+            EntryDisplayKind::Short => println!("#### Date          Revenue    Expense    Balance    Running  Title"),
+            EntryDisplayKind::Normal => println!("#### Date          Revenue    Expense    Balance    Running   Category       Epic  Title"),
+            EntryDisplayKind::Long => println!("#### Date          Revenue    Expense    Balance    Running             Category                 Epic   Title"),
         }
-
-        println!("TOTAL           {:>10.2} {:>10.2} {:>10.2}",
-            sum.revenue.to_display(),
-            sum.expense.to_display(),
-            sum.balance.to_display());
     }
 
-    fn show_long(&self) {
-        println!("-> EntryDisplay::show_long()");
+    fn show_entry(&self, n: u64, entry: &Entry, running: Number) {
+        let revenue_number = entry.revenue();
+        let expense_number = entry.expense();
+        let balance_number = entry.balance();
 
-        let mut sum = EntrySum::new();
+        match self.kind {
+            EntryDisplayKind::Short => {
+                let title = ShortString::from(entry.title(), 23);
+
+                println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2} {:>10.2}  {}",
+                    n,
+                    entry.date().ymd(),
+                    revenue_number.to_display(),
+                    expense_number.to_display(),
+                    balance_number.to_display(),
+                    running.unwrap(),
+                    title,
+                );
+            },
+            EntryDisplayKind::Normal => {
+                let category = ShortString::from(entry.category(), 10);
+                let mut epic = ShortString::from(entry.epic(), 10); // TODO: use EpicDisplay here
+                let title = ShortString::from(entry.title(), 23);
+
+                if epic.to_string() == "default".to_string() {
+                    epic = ShortString::new();
+                }
 
-        println!("#### Date          Revenue    Expense    Balance             Category                 Epic   Title");
-
-        for entry in &self.entries {
-            let revenue_number = entry.revenue();
-            let expense_number = entry.expense();
-            let balance_number = entry.balance();
-
-            sum.inc();
-            // sum.inc_revenue(revenue_number.clone());
-            // sum.inc_expense(expense_number.clone());
-            // sum.inc_balance(balance_number.clone());
-
-            println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2} {:>20} {:>20}   {}",
-                sum.n,
-                entry.date().ymd(),
-                revenue_number.to_display(),
-                expense_number.to_display(),
-                balance_number.to_display(),
-                entry.category(),
-                entry.epic(),
-                entry.title(),
-            );
+                println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10} {:>10}  {}",
+                    n,
+                    entry.date().ymd(),
+                    revenue_number.to_display(),
+                    expense_number.to_display(),
+                    balance_number.to_display(),
+                    running.unwrap(),
+                    category.to_string(),
+                    epic.to_string(),
+                    title.to_string(),
+                );
+            },
+            EntryDisplayKind::Long => {
+                println!("{:<4} {} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>20} {:>20}   {}",
+                    n,
+                    entry.date().ymd(),
+                    revenue_number.to_display(),
+                    expense_number.to_display(),
+                    balance_number.to_display(),
+                    running.unwrap(),
+                    entry.category(),
+                    entry.epic(),
+                    entry.title(),
+                );
+            },
         }
+    }
 
-        println!("TOTAL           {:>10.2} {:>10.2} {:>10.2}",
+    fn show_sum(&self, label: &str, sum: &EntrySum) {
+        println!("{:<16}{:>10.2} {:>10.2} {:>10.2}",
+            label,
             sum.revenue.to_display(),
             sum.expense.to_display(),
             sum.balance.to_display());
@@ -667,3 +886,62 @@ mod tests_display {
         EntryDisplay::new(list, EntryDisplayKind::Normal);
     }
 }
+
+#[cfg(test)]
+mod tests_entrysum {
+    use super::{Entry, EntrySum, running_balances};
+    use crate::number::Number;
+
+    #[test]
+    fn test_entrysum_add1() {
+        let mut sum = EntrySum::new();
+        sum.add(&Entry::from("a/2020-01-01/10/0"));
+        sum.add(&Entry::from("b/2020-01-02/0/4"));
+
+        assert_eq!(2, sum.n);
+        assert_eq!(10.0, sum.revenue.unwrap());
+        assert_eq!(-4.0, sum.expense.unwrap());
+        assert_eq!(6.0, sum.balance.unwrap());
+    }
+
+    #[test]
+    fn test_running_balances1() {
+        let entries = vec![
+            Entry::from("b/2020-01-03/0/5"),
+            Entry::from("a/2020-01-01/20/0"),
+            Entry::from("c/2020-01-02/0/10"),
+        ];
+
+        let b1: Vec<f64> = running_balances(&entries, Number::new()).iter().map(|n| n.unwrap()).collect();
+        assert_eq!(vec![5.0, 20.0, 10.0], b1);
+
+        let b2: Vec<f64> = running_balances(&entries, Number::from(100.0)).iter().map(|n| n.unwrap()).collect();
+        assert_eq!(vec![105.0, 120.0, 110.0], b2);
+    }
+}
+
+#[cfg(test)]
+mod tests_entrychanges {
+    use super::{Entry, EntryChanges, EntryField, FieldChange};
+
+    #[test]
+    fn test_entrychanges1() {
+        let mut e1 = Entry::from("Netflix/2019-03-01/0/10");
+        e1.set_category("default".to_string());
+
+        let mut c1 = EntryChanges::new();
+        assert!(c1.is_empty());
+        c1.category = Some("subscriptions".to_string());
+        c1.title = Some("Netflix".to_string());
+
+        assert_eq!(vec![FieldChange {
+            field: EntryField::Category,
+            old: "default".to_string(),
+            new: "subscriptions".to_string(),
+        }], c1.diff(&e1));
+
+        assert!(c1.apply(&mut e1));
+        assert_eq!("subscriptions", e1.category());
+        assert!(!c1.apply(&mut e1));
+    }
+}
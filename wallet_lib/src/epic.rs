@@ -1,10 +1,42 @@
 
 use uuid::Uuid;
-use crate::yaml::ToYaml;
+use crate::yaml::{ToYaml, FromYaml};
 use yaml_rust::Yaml;
 use yaml_rust::yaml::Hash;
+use serde::{Serialize, Deserialize};
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use crate::chain::SealError;
+
+#[derive(Debug, PartialEq)]
+pub enum EpicError {
+    NotFound(String),
+    Exists(String),
+    /// Handle and number of Entries.
+    InUse(String, usize),
+    /// The default Epic cannot be renamed, removed or merged.
+    Default,
+    Sealed(SealError),
+}
+
+impl Display for EpicError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            EpicError::NotFound(handle) => write!(f, "Epic '{}' not found", handle),
+            EpicError::Exists(handle) => write!(f, "Epic '{}' already exists", handle),
+            EpicError::InUse(handle, n) => write!(f, "Epic '{}' is used by {} entries, use --force to move them to the default epic", handle, n),
+            EpicError::Default => write!(f, "The default epic cannot be changed"),
+            EpicError::Sealed(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<SealError> for EpicError {
+    fn from(error: SealError) -> Self {
+        EpicError::Sealed(error)
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Epic {
     id: String,
     handle: String,
@@ -67,6 +99,42 @@ impl ToYaml for Epic {
     }
 }
 
+impl FromYaml for Epic {
+    fn from_yaml(yaml: &Yaml) -> Self {
+        // println!("-> Epic::from_yaml()");
+
+        let mut epic = Epic::new();
+
+        if let Yaml::Hash(ref item_ref) = yaml {
+            // ID
+            let key = "id".to_string().to_yaml();
+            if let Some(Yaml::String(id)) = item_ref.get(&key) {
+                epic.id = id.to_string();
+            }
+
+            // Handle
+            let key = "handle".to_string().to_yaml();
+            if let Some(Yaml::String(handle)) = item_ref.get(&key) {
+                epic.handle = handle.to_string();
+            }
+
+            // Title
+            let key = "title".to_string().to_yaml();
+            if let Some(Yaml::String(title)) = item_ref.get(&key) {
+                epic.title = title.to_string();
+            }
+
+            // Background Color
+            let key = "bg_color".to_string().to_yaml();
+            if let Some(Yaml::String(bgcolor)) = item_ref.get(&key) {
+                epic.bgcolor = bgcolor.to_string();
+            }
+        }
+
+        epic
+    }
+}
+
 #[cfg(test)]
 mod tests_basic {
     use super::Epic;
@@ -80,6 +148,27 @@ mod tests_basic {
     }
 }
 
+#[cfg(test)]
+mod tests_from_yaml {
+    use super::Epic;
+    use crate::yaml::{ToYaml, FromYaml};
+
+    #[test]
+    fn test_epic_from_yaml1() {
+        let mut e1 = Epic::new();
+        e1.set_handle("trip".to_string());
+        e1.set_title("Trip".to_string());
+        e1.set_bgcolor("#ff0000".to_string());
+        let id = e1.id();
+
+        let e2 = Epic::from_yaml(&e1.to_yaml());
+        assert_eq!(id, e2.id());
+        assert_eq!("trip", e2.handle());
+        assert_eq!("Trip", e2.title());
+        assert_eq!("#ff0000", e2.bgcolor());
+    }
+}
+
 #[cfg(test)]
 mod tests_to_yaml {
     use super::Epic;
@@ -0,0 +1,249 @@
+//! Export filtered entries to machine-readable and plain-text accounting formats.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use std::io::{Write, Result as IoResult, Error as IoError};
+use std::str::FromStr;
+use crate::wallet::FilterResult;
+use crate::number::Number;
+use crate::entry::Entry;
+use crate::journal::{JournalWriter, JournalFormat};
+
+#[derive(Debug)]
+pub enum ExportError {
+    InvalidFormat,
+}
+
+/// All supported export formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Tsv,
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "tsv" => Ok(ExportFormat::Tsv),
+            "ledger" => Ok(ExportFormat::Ledger),
+            "hledger" => Ok(ExportFormat::Hledger),
+            "beancount" => Ok(ExportFormat::Beancount),
+            _ => Err(ExportError::InvalidFormat),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        write!(f, "{}", match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Ledger => "ledger",
+            ExportFormat::Hledger => "hledger",
+            ExportFormat::Beancount => "beancount",
+        })
+    }
+}
+
+/// Column names used for CSV and TSV output. One column per Entry field.
+const COLUMNS: [&str; 9] = [
+    "id", "title", "date", "revenue", "expense", "balance", "category", "comment", "epic",
+];
+
+pub struct Exporter {
+    format: ExportFormat,
+    aggregates: bool,
+    currency: Option<String>,
+}
+
+impl Exporter {
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            aggregates: false,
+            currency: None,
+        }
+    }
+
+    /// Include the years, categories and epics summaries.
+    /// Only used by the JSON format.
+    pub fn set_aggregates(&mut self, aggregates: bool) {
+        self.aggregates = aggregates;
+    }
+
+    /// Commodity for the plain-text accounting formats.
+    pub fn set_currency(&mut self, currency: Option<String>) {
+        self.currency = currency;
+    }
+
+    /// Write the entries of the given result to a writer.
+    pub fn export<W: Write>(&self, result: &FilterResult, writer: W) -> IoResult<()> {
+        match self.format {
+            ExportFormat::Json => self.export_json(result, writer),
+            ExportFormat::Csv => self.export_separated(result, writer, b','),
+            ExportFormat::Tsv => self.export_separated(result, writer, b'\t'),
+            ExportFormat::Ledger | ExportFormat::Hledger => self.export_journal(result, writer, JournalFormat::Ledger),
+            ExportFormat::Beancount => self.export_journal(result, writer, JournalFormat::Beancount),
+        }
+    }
+
+    fn export_json<W: Write>(&self, result: &FilterResult, mut writer: W) -> IoResult<()> {
+        if self.aggregates {
+            serde_json::to_writer_pretty(&mut writer, result)?;
+        } else {
+            serde_json::to_writer_pretty(&mut writer, &result.entries)?;
+        }
+        writeln!(writer)
+    }
+
+    fn export_separated<W: Write>(&self, result: &FilterResult, writer: W, delimiter: u8) -> IoResult<()> {
+        let mut csv_writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(writer);
+
+        csv_writer.write_record(COLUMNS).map_err(to_io_error)?;
+
+        for entry in &result.entries {
+            csv_writer.write_record(&[
+                entry.id(),
+                entry.title(),
+                entry.date().to_string(),
+                format_amount(entry.revenue()),
+                format_amount(entry.expense()),
+                format_amount(entry.balance()),
+                entry.category(),
+                entry.comment(),
+                entry.epic(),
+            ]).map_err(to_io_error)?;
+        }
+
+        csv_writer.flush()
+    }
+
+    fn export_journal<W: Write>(&self, result: &FilterResult, writer: W, format: JournalFormat) -> IoResult<()> {
+        let entries: Vec<&Entry> = result.entries.iter().map(|entry| entry.as_ref()).collect();
+
+        let mut journal = JournalWriter::new(format);
+        journal.set_currency(self.currency.clone());
+        journal.write(&entries, writer)
+    }
+}
+
+/// Format with two decimals. Avoids `-0.00` for empty expenses.
+fn format_amount(n: Number) -> String {
+    format!("{:.2}", n.unwrap() + 0.0)
+}
+
+fn to_io_error(error: csv::Error) -> IoError {
+    IoError::other(error)
+}
+
+#[cfg(test)]
+mod tests_format {
+    use std::str::FromStr;
+    use super::ExportFormat;
+
+    #[test]
+    fn test_export_format_from_str1() {
+        assert_eq!(ExportFormat::Json, ExportFormat::from_str("json").unwrap());
+        assert_eq!(ExportFormat::Csv, ExportFormat::from_str("CSV").unwrap());
+        assert_eq!(ExportFormat::Tsv, ExportFormat::from_str("tsv").unwrap());
+        assert_eq!(ExportFormat::Hledger, ExportFormat::from_str("hledger").unwrap());
+        assert_eq!(ExportFormat::Beancount, ExportFormat::from_str("beancount").unwrap());
+        assert!(ExportFormat::from_str("xml").is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_exporter {
+    use super::{Exporter, ExportFormat};
+    use crate::wallet::FilterResult;
+    use crate::entry::Entry;
+
+    fn result() -> FilterResult {
+        let mut e1 = Entry::from("Salary/2001-01-01/30/0");
+        e1.set_id("id1".to_string());
+        e1.set_category("work".to_string());
+
+        let mut e2 = Entry::from("Pizza, large/2001-01-02/0/10");
+        e2.set_id("id2".to_string());
+        e2.set_comment("with \"extra\" cheese".to_string());
+
+        let mut r1 = FilterResult::new();
+        r1.add(e1);
+        r1.add(e2);
+        r1
+    }
+
+    #[test]
+    fn test_exporter_csv1() {
+        let mut out: Vec<u8> = vec![];
+        Exporter::new(ExportFormat::Csv).export(&result(), &mut out).unwrap();
+
+        let s = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(3, lines.len());
+        assert_eq!("id,title,date,revenue,expense,balance,category,comment,epic", lines[0]);
+        assert_eq!("id1,Salary,2001-01-01,30.00,0.00,30.00,work,,default", lines[1]);
+        assert_eq!("id2,\"Pizza, large\",2001-01-02,0.00,-10.00,-10.00,default,\"with \"\"extra\"\" cheese\",default", lines[2]);
+    }
+
+    #[test]
+    fn test_exporter_tsv1() {
+        let mut out: Vec<u8> = vec![];
+        Exporter::new(ExportFormat::Tsv).export(&result(), &mut out).unwrap();
+
+        let s = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!("id\ttitle\tdate\trevenue\texpense\tbalance\tcategory\tcomment\tepic", lines[0]);
+        assert_eq!("id1\tSalary\t2001-01-01\t30.00\t0.00\t30.00\twork\t\tdefault", lines[1]);
+    }
+
+    #[test]
+    fn test_exporter_json1() {
+        let mut out: Vec<u8> = vec![];
+        Exporter::new(ExportFormat::Json).export(&result(), &mut out).unwrap();
+
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let entries = v.as_array().unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("id1", entries[0]["id"]);
+        assert_eq!("2001-01-01", entries[0]["date"]);
+        assert_eq!(30.0, entries[0]["revenue"]);
+        assert_eq!(-10.0, entries[1]["balance"]);
+    }
+
+    #[test]
+    fn test_exporter_ledger1() {
+        let mut out: Vec<u8> = vec![];
+        Exporter::new(ExportFormat::Hledger).export(&result(), &mut out).unwrap();
+
+        let s = String::from_utf8(out).unwrap();
+        assert!(s.starts_with("2001-01-01 Salary\n    ; uuid: id1\n"));
+        assert!(s.contains("2001-01-02 Pizza, large  ; with \"extra\" cheese\n    ; uuid: id2\n"));
+    }
+
+    #[test]
+    fn test_exporter_json_aggregates1() {
+        let mut exporter = Exporter::new(ExportFormat::Json);
+        exporter.set_aggregates(true);
+
+        let mut out: Vec<u8> = vec![];
+        exporter.export(&result(), &mut out).unwrap();
+
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(2, v["entries"].as_array().unwrap().len());
+        assert_eq!(20.0, v["balance"]);
+        assert_eq!(20.0, v["years"]["2001"]["balance"]);
+        assert_eq!(30.0, v["categories"]["work"]["revenue"]);
+        assert_eq!(-10.0, v["epics"]["default"]["expense"]);
+    }
+}
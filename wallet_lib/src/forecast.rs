@@ -0,0 +1,215 @@
+//! Cash-flow forecast.
+//!
+//! Each category is projected with its average of the last
+//! `TRAILING_MONTHS` months. Entries already booked for a future month,
+//! like scheduled payments or recurring ones entered in advance, are
+//! known items: when a category's known revenue or expense is higher
+//! than its average, the known value is used instead.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::date::Date;
+use crate::number::NumberType;
+use crate::wallet::{FilterResult, Year, Month, Categories};
+
+/// Months used for the average.
+pub const TRAILING_MONTHS: u32 = 6;
+
+/// Months to forecast.
+pub const FORECAST_MONTHS: u32 = 6;
+
+fn next_month(year: Year, month: Month) -> (Year, Month) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+fn prev_month(year: Year, month: Month) -> (Year, Month) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+fn month_categories(result: &FilterResult, year: Year, month: Month) -> Option<&Categories> {
+    result.years.get(&year)
+        .and_then(|year_sum| year_sum.months.get(&month))
+        .map(|month_sum| &month_sum.categories)
+}
+
+/// Revenue and expense per category.
+type Amounts = HashMap<String, (NumberType, NumberType)>;
+
+#[derive(Debug, Serialize)]
+pub struct ForecastMonth {
+    pub year: Year,
+    pub month: Month,
+    pub revenue: NumberType,
+    /// Negative, like in the summaries.
+    pub expense: NumberType,
+    pub balance: NumberType,
+    pub end_balance: NumberType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Forecast {
+    /// The current month. The forecast starts with the month after it.
+    pub year: Year,
+    pub month: Month,
+    /// Balance at the end of the current month.
+    pub start_balance: NumberType,
+    pub months: Vec<ForecastMonth>,
+}
+
+impl Forecast {
+    pub fn from_result(result: &FilterResult, today: Date, months: u32) -> Self {
+        println!("-> Forecast::from_result({}, {})", today, months);
+
+        let current = (today.year(), today.month());
+
+        let mut keys: Vec<(Year, Month)> = result.years.values()
+            .flat_map(|year_sum| year_sum.months.keys().map(move |month| (year_sum.year, *month)))
+            .collect();
+        keys.sort();
+
+        // Balance at the end of the current month.
+        let start_balance = keys.iter()
+            .filter(|key| **key <= current)
+            .map(|(year, month)| result.years[year].months[month].balance.unwrap())
+            .fold(0.0, |sum, balance| sum + balance);
+
+        // Trailing average, only since the first month with entries.
+        let mut totals: Amounts = HashMap::new();
+        let mut window: u32 = 0;
+        if let Some(first) = keys.first() {
+            let mut key = prev_month(current.0, current.1);
+            while window < TRAILING_MONTHS && key >= *first {
+                if let Some(categories) = month_categories(result, key.0, key.1) {
+                    for (name, category_sum) in categories {
+                        let total = totals.entry(name.clone()).or_insert((0.0, 0.0));
+                        total.0 += category_sum.revenue.unwrap();
+                        total.1 += category_sum.expense.unwrap();
+                    }
+                }
+
+                window += 1;
+                key = prev_month(key.0, key.1);
+            }
+        }
+        let averages: Amounts = totals.into_iter()
+            .map(|(name, (revenue, expense))| (name, (revenue / window as NumberType, expense / window as NumberType)))
+            .collect();
+
+        // Projection
+        let mut forecast_months: Vec<ForecastMonth> = vec![];
+        let mut end_balance = start_balance;
+        let mut key = current;
+        for _ in 0..months {
+            key = next_month(key.0, key.1);
+            let known = month_categories(result, key.0, key.1);
+
+            let mut names: Vec<&String> = averages.keys().collect();
+            if let Some(known) = known {
+                names.extend(known.keys());
+            }
+            names.sort();
+            names.dedup();
+
+            let mut revenue = 0.0;
+            let mut expense = 0.0;
+            for name in names {
+                let (avg_revenue, avg_expense) = averages.get(name).cloned().unwrap_or((0.0, 0.0));
+                let (known_revenue, known_expense) = known
+                    .and_then(|known| known.get(name))
+                    .map(|category_sum| (category_sum.revenue.unwrap(), category_sum.expense.unwrap()))
+                    .unwrap_or((0.0, 0.0));
+
+                revenue += avg_revenue.max(known_revenue);
+                expense += avg_expense.min(known_expense);
+            }
+
+            let balance = revenue + expense;
+            end_balance += balance;
+
+            forecast_months.push(ForecastMonth {
+                year: key.0,
+                month: key.1,
+                revenue,
+                expense,
+                balance,
+                end_balance,
+            });
+        }
+
+        Self {
+            year: current.0,
+            month: current.1,
+            start_balance,
+            months: forecast_months,
+        }
+    }
+
+    pub fn show(&self) {
+        println!("Start   {}-{:02} {:>35.2}", self.year, self.month, self.start_balance);
+        println!("Month         Revenue    Expense    Balance  End Balance");
+
+        for month in &self.months {
+            println!("{}-{:02} {:>12.2} {:>10.2} {:>10.2} {:>12.2}",
+                month.year,
+                month.month,
+                month.revenue,
+                month.expense,
+                month.balance,
+                month.end_balance,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_forecast {
+    use std::str::FromStr;
+    use super::Forecast;
+    use crate::wallet::FilterResult;
+    use crate::entry::Entry;
+    use crate::date::Date;
+
+    fn entry(s: &str, category: &str) -> Entry {
+        let mut entry = Entry::from(s);
+        entry.set_category(category.to_string());
+        entry
+    }
+
+    #[test]
+    fn test_forecast1() {
+        let mut r1 = FilterResult::new();
+        r1.add(entry("Salary/2020-01-01/1000/0", "job"));
+        r1.add(entry("Food/2020-01-05/0/100", "food"));
+        r1.add(entry("Salary/2020-02-01/1000/0", "job"));
+        r1.add(entry("Food/2020-02-05/0/300", "food"));
+        r1.add(entry("Food/2020-03-05/0/50", "food"));
+
+        // Known items
+        r1.add(entry("Car/2020-04-10/0/500", "car"));
+        r1.add(entry("Food/2020-05-10/0/100", "food"));
+
+        let f1 = Forecast::from_result(&r1, Date::from_str("2020-03-15").unwrap(), 3);
+
+        assert_eq!(1550.0, f1.start_balance);
+        assert_eq!(3, f1.months.len());
+
+        // Average of 2020-01 and 2020-02: 1000 revenue, 200 food.
+        assert_eq!((2020, 4), (f1.months[0].year, f1.months[0].month));
+        assert_eq!(1000.0, f1.months[0].revenue);
+        assert_eq!(-700.0, f1.months[0].expense);
+        assert_eq!(1850.0, f1.months[0].end_balance);
+
+        // The known 100 food is less than the average.
+        assert_eq!(-200.0, f1.months[1].expense);
+        assert_eq!(2650.0, f1.months[1].end_balance);
+        assert_eq!(3450.0, f1.months[2].end_balance);
+    }
+}
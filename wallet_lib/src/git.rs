@@ -0,0 +1,187 @@
+//! Git repository in the wallet directory.
+//!
+//! Opt-in with `wallet init --git`, which sets `wallet.autocommit` in
+//! the git config. Every change of the wallet is then committed, with
+//! the name of the operation as message. A repository created by hand
+//! is left alone. Runs the `git` binary.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::fs::write;
+use std::fmt::{Display, Formatter, Result as FmtRes};
+
+/// Git config key set by `wallet init --git`.
+const AUTOCOMMIT_KEY: &str = "wallet.autocommit";
+
+/// Generated files which are not committed.
+const GITIGNORE: &str = "/html/\n/tmp/\n/data/history.json\n/data/sync.json\n/data/*.tmp\n";
+
+#[derive(Debug, PartialEq)]
+pub enum GitError {
+    /// The git binary cannot be run.
+    NotFound,
+    /// Command and error output.
+    Failed(String, String),
+    /// Revisions must not look like an option.
+    InvalidRev(String),
+}
+
+impl Display for GitError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            GitError::NotFound => write!(f, "Cannot run git"),
+            GitError::Failed(command, output) => write!(f, "git {} failed: {}", command, output.trim()),
+            GitError::InvalidRev(rev) => write!(f, "Invalid revision '{}'", rev),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Commit {
+    pub rev: String,
+    pub time: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct Git {
+    path: PathBuf,
+}
+
+impl Git {
+    /// None when the directory is no git repository or it was not
+    /// created by `wallet init --git`.
+    pub fn open(path: PathBuf) -> Option<Self> {
+        if !path.join(".git").exists() {
+            return None;
+        }
+
+        let git = Self { path };
+        match git.run(&["config", "--get", AUTOCOMMIT_KEY]) {
+            Ok(value) if value.trim() == "true" => Some(git),
+            _ => None,
+        }
+    }
+
+    /// Create the repository. An existing one is kept.
+    pub fn init(path: PathBuf) -> Result<Self, GitError> {
+        println!("-> Git::init({:?})", path);
+
+        let git = Self { path };
+        git.run(&["init", "-q"])?;
+
+        // Commits need an identity.
+        if git.run(&["config", "user.email"]).is_err() {
+            git.run(&["config", "user.name", "Wallet"])?;
+            git.run(&["config", "user.email", "wallet@localhost"])?;
+        }
+        git.run(&["config", AUTOCOMMIT_KEY, "true"])?;
+
+        let gitignore = git.path.join(".gitignore");
+        if !gitignore.exists() {
+            write(gitignore, GITIGNORE).expect("Cannot write .gitignore");
+        }
+
+        git.commit("init")?;
+
+        Ok(git)
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, GitError> {
+        println!("-> Git::run({:?})", args);
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(args)
+            .output()
+            .map_err(|_| GitError::NotFound)?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(GitError::Failed(args.join(" "), String::from_utf8_lossy(&output.stderr).to_string()))
+        }
+    }
+
+    /// Commit all changes. Returns false when there was nothing to commit.
+    pub fn commit(&self, message: &str) -> Result<bool, GitError> {
+        println!("-> Git::commit({})", message);
+
+        self.run(&["add", "-A"])?;
+        if self.run(&["status", "--porcelain"])?.trim().is_empty() {
+            return Ok(false);
+        }
+
+        self.run(&["commit", "-q", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Newest commits first.
+    pub fn log(&self, limit: usize) -> Result<Vec<Commit>, GitError> {
+        let limit = format!("-{}", limit);
+        let raw = self.run(&["log", &limit, "--date=format:%Y-%m-%d %H:%M:%S", "--format=%h%x09%ad%x09%s"])?;
+
+        let commits = raw.lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(Commit {
+                    rev: fields.next()?.to_string(),
+                    time: fields.next()?.to_string(),
+                    message: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(commits)
+    }
+
+    /// Message and diff of a commit.
+    pub fn show(&self, rev: &str) -> Result<String, GitError> {
+        if rev.starts_with('-') {
+            return Err(GitError::InvalidRev(rev.to_string()));
+        }
+        self.run(&["show", "--date=format:%Y-%m-%d %H:%M:%S", rev, "--"])
+    }
+}
+
+#[cfg(test)]
+mod tests_git {
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use std::path::PathBuf;
+    use std::process::Command;
+    use super::{Git, GitError};
+
+    #[test]
+    fn test_git1() {
+        let dir = PathBuf::from("../tmp/tests/git1");
+        remove_dir_all(&dir).ok();
+        create_dir_all(dir.join("data")).unwrap();
+
+        assert!(Git::open(dir.clone()).is_none());
+
+        // Not created by the wallet.
+        Command::new("git").arg("-C").arg(&dir).args(["init", "-q"]).output().unwrap();
+        assert!(Git::open(dir.clone()).is_none());
+
+        let g1 = Git::init(dir.clone()).unwrap();
+        assert!(Git::open(dir.clone()).is_some());
+
+        write(dir.join("data/index.yml"), "a").unwrap();
+        assert_eq!(Ok(true), g1.commit("add: a"));
+        assert_eq!(Ok(false), g1.commit("add: a"));
+
+        // Ignored
+        write(dir.join("data/history.json"), "{}").unwrap();
+        assert_eq!(Ok(false), g1.commit("x"));
+
+        let log = g1.log(10).unwrap();
+        assert_eq!(2, log.len());
+        assert_eq!("add: a", log[0].message);
+        assert_eq!("init", log[1].message);
+
+        assert!(g1.show(&log[0].rev).unwrap().contains("+a"));
+        assert!(g1.show("nope").is_err());
+        assert_eq!(Err(GitError::InvalidRev("--output=x".to_string())), g1.show("--output=x"));
+    }
+}
@@ -0,0 +1,350 @@
+//! Undo and redo of wallet mutations.
+//!
+//! Every operation stores the content of the data files it changed,
+//! before and after. Undo writes the old contents back, redo the new
+//! ones. The history is kept in `data/history.json`.
+//!
+//! While an operation is recorded, YamlFile reports every file before
+//! it is written, so only the touched files are read.
+
+use std::path::{Path, PathBuf};
+use std::fs::{read_to_string, read_dir, rename, remove_file, File};
+use std::io::Write;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use chrono::Local;
+use serde::{Serialize, Deserialize};
+use crate::chain::SealError;
+
+/// Oldest operations are dropped beyond this.
+pub const MAX_OPERATIONS: usize = 100;
+
+const HISTORY_FILE: &str = "history.json";
+
+/// File name and content of all data files.
+pub type Snapshot = BTreeMap<String, String>;
+
+#[derive(Debug, PartialEq)]
+pub enum HistoryError {
+    NothingToUndo,
+    NothingToRedo,
+    /// The file was changed after the operation.
+    Modified(String),
+    /// The operation changed a sealed or closed month.
+    ReadOnly(SealError),
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            HistoryError::NothingToUndo => write!(f, "Nothing to undo"),
+            HistoryError::NothingToRedo => write!(f, "Nothing to redo"),
+            HistoryError::Modified(file) => write!(f, "File '{}' was changed outside of the history", file),
+            HistoryError::ReadOnly(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Content of a data file. None when the file does not exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub file: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub time: String,
+    /// Like `add: Groceries 2020-01-05 -23.40`.
+    pub name: String,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryData {
+    operations: Vec<Operation>,
+    /// Number of applied operations. Operations after it can be redone.
+    position: usize,
+}
+
+/// File name and content before the first write during a recording.
+/// None when the file did not exist.
+type Touched = BTreeMap<String, Option<String>>;
+
+fn recordings() -> &'static Mutex<HashMap<PathBuf, Touched>> {
+    static RECORDINGS: OnceLock<Mutex<HashMap<PathBuf, Touched>>> = OnceLock::new();
+    RECORDINGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Same key for different spellings of a directory.
+fn dir_key(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Collect the files written to the data directory from now on.
+pub fn start_recording(data_dir: &Path) {
+    recordings().lock().unwrap().insert(dir_key(data_dir), Touched::new());
+}
+
+/// Keep the content of a data file before it is written the first time
+/// during a recording.
+pub fn before_write(path: &Path) {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    if let Some(touched) = recordings().lock().unwrap().get_mut(&dir_key(dir)) {
+        touched.entry(file).or_insert_with(|| read_to_string(path).ok());
+    }
+}
+
+/// Stop collecting. Returns the written files before and after.
+pub fn stop_recording(data_dir: &Path) -> (Snapshot, Snapshot) {
+    let touched = recordings().lock().unwrap().remove(&dir_key(data_dir)).unwrap_or_default();
+
+    let mut before = Snapshot::new();
+    let mut after = Snapshot::new();
+    for (file, content) in touched {
+        if let Some(content) = content {
+            before.insert(file.clone(), content);
+        }
+        if let Ok(content) = read_to_string(data_dir.join(&file)) {
+            after.insert(file, content);
+        }
+    }
+
+    (before, after)
+}
+
+/// Read all YAML files of the data directory.
+pub fn snapshot(data_dir: &Path) -> Snapshot {
+    let mut files = Snapshot::new();
+
+    let dir = match read_dir(data_dir) {
+        Ok(dir) => dir,
+        Err(_) => return files,
+    };
+
+    for path in dir.filter_map(|item| item.ok()).map(|item| item.path()) {
+        if path.extension().is_none_or(|ext| ext != "yml") {
+            continue;
+        }
+
+        let file = path.file_name().unwrap().to_string_lossy().to_string();
+        let content = read_to_string(&path).expect("Cannot read data file");
+        files.insert(file, content);
+    }
+
+    files
+}
+
+pub struct History {
+    data_dir: PathBuf,
+    data: HistoryData,
+}
+
+impl History {
+    pub fn open(data_dir: PathBuf) -> Self {
+        println!("-> History::open({:?})", data_dir);
+
+        let path = data_dir.join(HISTORY_FILE);
+        let data = match read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).expect("Cannot parse history file"),
+            Err(_) => HistoryData::default(),
+        };
+
+        Self {
+            data_dir,
+            data,
+        }
+    }
+
+    fn save(&self) {
+        println!("-> History::save()");
+
+        let path = self.data_dir.join(HISTORY_FILE);
+        let tmp_path = self.data_dir.join(format!("{}.tmp", HISTORY_FILE));
+
+        let raw = serde_json::to_string(&self.data).expect("Cannot serialize history");
+        let mut file = File::create(&tmp_path).expect("Cannot open history file for writing");
+        file.write_all(raw.as_bytes()).expect("Cannot write history file");
+        rename(tmp_path, path).expect("Cannot move history file into place");
+    }
+
+    /// Record the files which differ between both snapshots.
+    /// Operations which were undone before cannot be redone anymore.
+    /// Returns false when nothing has changed.
+    pub fn record(&mut self, name: String, before: &Snapshot, after: &Snapshot) -> bool {
+        println!("-> History::record({})", name);
+
+        let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let files: Vec<FileChange> = names.into_iter()
+            .filter(|file| before.get(*file) != after.get(*file))
+            .map(|file| FileChange {
+                file: file.clone(),
+                before: before.get(file).cloned(),
+                after: after.get(file).cloned(),
+            })
+            .collect();
+
+        if files.is_empty() {
+            return false;
+        }
+
+        self.data.operations.truncate(self.data.position);
+        self.data.operations.push(Operation {
+            time: Local::now().format("%F %T").to_string(),
+            name,
+            files,
+        });
+
+        if self.data.operations.len() > MAX_OPERATIONS {
+            let n = self.data.operations.len() - MAX_OPERATIONS;
+            self.data.operations.drain(..n);
+        }
+        self.data.position = self.data.operations.len();
+
+        self.save();
+        true
+    }
+
+    /// Write `to` for every file, when it still has the content of `from`.
+    fn restore(&self, operation: &Operation, undo: bool) -> Result<(), HistoryError> {
+        let states = |change: &FileChange| if undo {
+            (change.after.clone(), change.before.clone())
+        } else {
+            (change.before.clone(), change.after.clone())
+        };
+
+        // Check all files first.
+        for change in &operation.files {
+            let (from, _) = states(change);
+            let current = read_to_string(self.data_dir.join(&change.file)).ok();
+            if current != from {
+                return Err(HistoryError::Modified(change.file.clone()));
+            }
+        }
+
+        for change in &operation.files {
+            let path = self.data_dir.join(&change.file);
+            match states(change) {
+                (_, Some(content)) => {
+                    let mut file = File::create(&path).expect("Cannot open data file for writing");
+                    file.write_all(content.as_bytes()).expect("Cannot write data file");
+                },
+                (_, None) => {
+                    remove_file(&path).expect("Cannot remove data file");
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The operation `undo()` would undo.
+    pub fn next_undo(&self) -> Option<&Operation> {
+        self.data.position.checked_sub(1).map(|i| &self.data.operations[i])
+    }
+
+    /// The operation `redo()` would redo.
+    pub fn next_redo(&self) -> Option<&Operation> {
+        self.data.operations.get(self.data.position)
+    }
+
+    pub fn undo(&mut self) -> Result<Operation, HistoryError> {
+        println!("-> History::undo()");
+
+        if self.data.position == 0 {
+            return Err(HistoryError::NothingToUndo);
+        }
+
+        let operation = self.data.operations[self.data.position - 1].clone();
+        self.restore(&operation, true)?;
+
+        self.data.position -= 1;
+        self.save();
+
+        Ok(operation)
+    }
+
+    pub fn redo(&mut self) -> Result<Operation, HistoryError> {
+        println!("-> History::redo()");
+
+        if self.data.position >= self.data.operations.len() {
+            return Err(HistoryError::NothingToRedo);
+        }
+
+        let operation = self.data.operations[self.data.position].clone();
+        self.restore(&operation, false)?;
+
+        self.data.position += 1;
+        self.save();
+
+        Ok(operation)
+    }
+
+    /// Drop all operations.
+    pub fn clear(&mut self) {
+        println!("-> History::clear()");
+
+        self.data = HistoryData::default();
+        self.save();
+    }
+
+    /// All operations, oldest first. The flag is false for undone ones.
+    pub fn operations(&self) -> Vec<(&Operation, bool)> {
+        self.data.operations.iter()
+            .enumerate()
+            .map(|(i, operation)| (operation, i < self.data.position))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests_history {
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+    use std::path::{Path, PathBuf};
+    use super::{History, HistoryError, snapshot};
+
+    fn content(dir: &Path, file: &str) -> Option<String> {
+        read_to_string(dir.join(file)).ok()
+    }
+
+    #[test]
+    fn test_history1() {
+        let dir = PathBuf::from("../tmp/tests/history1");
+        remove_dir_all(&dir).ok();
+        create_dir_all(&dir).unwrap();
+        write(dir.join("index.yml"), "a").unwrap();
+
+        // Record
+        let mut h1 = History::open(dir.clone());
+        let before = snapshot(&dir);
+        write(dir.join("index.yml"), "b").unwrap();
+        write(dir.join("month_2020_01.yml"), "c").unwrap();
+        assert!(h1.record("add: x".to_string(), &before, &snapshot(&dir)));
+        assert!(!h1.record("add: y".to_string(), &snapshot(&dir), &snapshot(&dir)));
+
+        // Undo
+        let mut h2 = History::open(dir.clone());
+        assert_eq!(1, h2.operations().len());
+        assert_eq!("add: x", h2.undo().unwrap().name);
+        assert_eq!(Some("a".to_string()), content(&dir, "index.yml"));
+        assert_eq!(None, content(&dir, "month_2020_01.yml"));
+        assert_eq!(Err(HistoryError::NothingToUndo), h2.undo().map(|operation| operation.name));
+        assert!(!h2.operations()[0].1);
+
+        // Redo
+        assert_eq!("add: x", h2.redo().unwrap().name);
+        assert_eq!(Some("c".to_string()), content(&dir, "month_2020_01.yml"));
+        assert_eq!(Err(HistoryError::NothingToRedo), h2.redo().map(|operation| operation.name));
+
+        // Modified
+        write(dir.join("index.yml"), "d").unwrap();
+        assert_eq!(Err(HistoryError::Modified("index.yml".to_string())), h2.undo().map(|operation| operation.name));
+    }
+}
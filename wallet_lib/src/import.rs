@@ -0,0 +1,178 @@
+//! Import entries from other formats.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use std::str::FromStr;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::number::NumberType;
+use crate::journal::read_ledger;
+use crate::ofx::read_ofx;
+use crate::qif::read_qif;
+use crate::camt::read_camt;
+use crate::mt940::read_mt940;
+
+#[derive(Debug, PartialEq)]
+pub enum ImportError {
+    InvalidFormat,
+    /// Line number of the invalid date.
+    InvalidDate(usize),
+    /// Line number of the invalid amount.
+    InvalidAmount(usize),
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            ImportError::InvalidFormat => write!(f, "Invalid format"),
+            ImportError::InvalidDate(line) => write!(f, "Invalid date on line {}", line),
+            ImportError::InvalidAmount(line) => write!(f, "Invalid amount on line {}", line),
+        }
+    }
+}
+
+/// All supported import formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    /// Also used for hledger journals.
+    Ledger,
+    Ofx,
+    Qif,
+    /// ISO 20022 camt.053 and camt.054.
+    Camt,
+    /// SWIFT MT940.
+    Mt940,
+}
+
+impl FromStr for ImportFormat {
+    type Err = ImportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ledger" | "hledger" => Ok(ImportFormat::Ledger),
+            "ofx" => Ok(ImportFormat::Ofx),
+            "qif" => Ok(ImportFormat::Qif),
+            "camt" | "camt053" | "camt054" => Ok(ImportFormat::Camt),
+            "mt940" => Ok(ImportFormat::Mt940),
+            _ => Err(ImportError::InvalidFormat),
+        }
+    }
+}
+
+/// Parsed entries, not yet added to a wallet.
+#[derive(Debug, Default)]
+pub struct ImportResult {
+    pub entries: Vec<Entry>,
+
+    /// Number of records which could not be mapped to an Entry.
+    pub skipped: usize,
+
+    /// Period covered by the statement.
+    pub start: Option<Date>,
+    pub end: Option<Date>,
+}
+
+impl ImportResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the first and the last entry date when the
+    /// file does not provide the period itself.
+    fn fill_period(&mut self) {
+        for entry in &self.entries {
+            let date = entry.date();
+
+            if self.start.is_none() || Some(date) < self.start {
+                self.start = Some(date);
+            }
+            if self.end.is_none() || Some(date) > self.end {
+                self.end = Some(date);
+            }
+        }
+    }
+}
+
+/// Parse the raw content of a file.
+pub fn parse(format: ImportFormat, raw: &str) -> Result<ImportResult, ImportError> {
+    let mut result = match format {
+        ImportFormat::Ledger => read_ledger(raw)?,
+        ImportFormat::Ofx => read_ofx(raw)?,
+        ImportFormat::Qif => read_qif(raw)?,
+        ImportFormat::Camt => read_camt(raw)?,
+        ImportFormat::Mt940 => read_mt940(raw)?,
+    };
+
+    if result.start.is_none() || result.end.is_none() {
+        result.fill_period();
+    }
+
+    Ok(result)
+}
+
+/// Parse an amount as found in bank statements and journals.
+///
+/// Currency symbols and codes are removed. Both `1,234.56` and
+/// `1.234,56` are understood; the last separator is the decimal one.
+pub fn parse_amount(s: &str) -> Option<NumberType> {
+    let mut raw: String = s.chars()
+        .filter(|c| c.is_ascii_digit() || *c == '-' || *c == '+' || *c == '.' || *c == ',')
+        .collect();
+
+    let dot = raw.rfind('.');
+    let comma = raw.rfind(',');
+
+    raw = match (dot, comma) {
+        (Some(d), Some(c)) if d > c => raw.replace(',', ""),
+        (Some(_), Some(_)) => raw.replace('.', "").replace(',', "."),
+        (None, Some(_)) if raw.matches(',').count() == 1 => raw.replace(',', "."),
+        (None, Some(_)) => raw.replace(',', ""),
+        _ => raw,
+    };
+
+    raw.parse().ok()
+}
+
+#[cfg(test)]
+mod tests_parse_amount {
+    use super::parse_amount;
+
+    #[test]
+    fn test_parse_amount1() {
+        assert_eq!(Some(23.4), parse_amount("23.40"));
+        assert_eq!(Some(-23.4), parse_amount("-23,40"));
+        assert_eq!(Some(1234.56), parse_amount("$1,234.56"));
+        assert_eq!(Some(-1234.56), parse_amount("-1.234,56 EUR"));
+        assert_eq!(Some(1000000.0), parse_amount("EUR 1,000,000"));
+        assert_eq!(None, parse_amount("EUR"));
+    }
+}
+
+#[cfg(test)]
+mod tests_format {
+    use std::str::FromStr;
+    use super::ImportFormat;
+
+    #[test]
+    fn test_import_format_from_str1() {
+        assert_eq!(ImportFormat::Ledger, ImportFormat::from_str("ledger").unwrap());
+        assert_eq!(ImportFormat::Ledger, ImportFormat::from_str("hledger").unwrap());
+        assert_eq!(ImportFormat::Ofx, ImportFormat::from_str("OFX").unwrap());
+        assert_eq!(ImportFormat::Qif, ImportFormat::from_str("qif").unwrap());
+        assert_eq!(ImportFormat::Camt, ImportFormat::from_str("camt").unwrap());
+        assert_eq!(ImportFormat::Mt940, ImportFormat::from_str("MT940").unwrap());
+        assert!(ImportFormat::from_str("xls").is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use super::{parse, ImportFormat};
+
+    #[test]
+    fn test_parse_period1() {
+        let raw = "2020-01-05 A\n    Expenses:x  1\n    Assets:Bank\n\n2020-01-01 B\n    Expenses:x  1\n    Assets:Bank\n";
+        let r1 = parse(ImportFormat::Ledger, raw).unwrap();
+        assert_eq!("2020-01-01", r1.start.unwrap().to_string());
+        assert_eq!("2020-01-05", r1.end.unwrap().to_string());
+    }
+}
@@ -0,0 +1,640 @@
+//! Plain-text accounting journals (ledger, hledger, beancount).
+
+use std::io::{Write, Result as IoResult};
+use std::str::FromStr;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::entry::Entry;
+use crate::number::{Number, NumberType};
+use crate::date::Date;
+use crate::import::{ImportResult, ImportError, parse_amount};
+
+/// The account which holds the money of the wallet.
+pub const ASSETS_ACCOUNT: &str = "Assets:Wallet";
+
+/// Parent account of all revenue categories.
+pub const INCOME_ACCOUNT: &str = "Income";
+
+/// Parent account of all expense categories.
+pub const EXPENSES_ACCOUNT: &str = "Expenses";
+
+/// Default commodity for beancount, which requires one on every amount.
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+/// Width of the account column in postings.
+const ACCOUNT_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalFormat {
+    /// Also read by hledger.
+    Ledger,
+    Beancount,
+}
+
+pub struct JournalWriter {
+    format: JournalFormat,
+    currency: Option<String>,
+}
+
+impl JournalWriter {
+    pub fn new(format: JournalFormat) -> Self {
+        Self {
+            format,
+            currency: None,
+        }
+    }
+
+    /// Commodity appended to every amount.
+    pub fn set_currency(&mut self, currency: Option<String>) {
+        self.currency = currency;
+    }
+
+    /// Write all entries as transactions, sorted by date and ID.
+    pub fn write<W: Write>(&self, entries: &[&Entry], mut writer: W) -> IoResult<()> {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|entry| (entry.date().ymd(), entry.id()));
+
+        if self.format == JournalFormat::Beancount {
+            self.write_beancount_open(&entries, &mut writer)?;
+        }
+
+        for entry in entries {
+            match self.format {
+                JournalFormat::Ledger => self.write_ledger_entry(entry, &mut writer)?,
+                JournalFormat::Beancount => self.write_beancount_entry(entry, &mut writer)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Beancount needs an `open` directive for every account before it is used.
+    fn write_beancount_open<W: Write>(&self, entries: &[&Entry], writer: &mut W) -> IoResult<()> {
+        let first = match entries.first() {
+            Some(entry) => entry.date().ymd(),
+            None => return Ok(()),
+        };
+
+        let mut accounts: Vec<String> = vec![ASSETS_ACCOUNT.to_string()];
+        for entry in entries {
+            for (account, _) in self.postings(entry) {
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+        }
+        accounts.sort();
+
+        for account in accounts {
+            writeln!(writer, "{} open {}", first, account)?;
+        }
+        writeln!(writer)
+    }
+
+    fn write_ledger_entry<W: Write>(&self, entry: &Entry, writer: &mut W) -> IoResult<()> {
+        let comment = entry.comment();
+        if comment.is_empty() {
+            writeln!(writer, "{} {}", entry.date().ymd(), entry.title())?;
+        } else {
+            writeln!(writer, "{} {}  ; {}", entry.date().ymd(), entry.title(), comment)?;
+        }
+
+        writeln!(writer, "    ; uuid: {}", entry.id())?;
+        if entry.epic() != "default" {
+            writeln!(writer, "    ; epic: {}", entry.epic())?;
+        }
+
+        for (account, amount) in self.postings(entry) {
+            writeln!(writer, "    {:<width$}  {}", account, self.amount(amount), width = ACCOUNT_WIDTH)?;
+        }
+        writeln!(writer, "    {}", ASSETS_ACCOUNT)?;
+        writeln!(writer)
+    }
+
+    fn write_beancount_entry<W: Write>(&self, entry: &Entry, writer: &mut W) -> IoResult<()> {
+        let mut header = format!("{} * {} {}",
+            entry.date().ymd(),
+            quote(&entry.title()),
+            quote(&entry.comment()));
+
+        if entry.epic() != "default" {
+            header.push_str(" #");
+            header.push_str(&beancount_component(&entry.epic()));
+        }
+        writeln!(writer, "{}", header)?;
+
+        writeln!(writer, "  uuid: {}", quote(&entry.id()))?;
+
+        for (account, amount) in self.postings(entry) {
+            writeln!(writer, "  {:<width$}  {}", account, self.amount(amount), width = ACCOUNT_WIDTH)?;
+        }
+        writeln!(writer, "  {}", ASSETS_ACCOUNT)?;
+        writeln!(writer)
+    }
+
+    /// Income and expense postings. The balancing posting to the
+    /// assets account is left without amount.
+    fn postings(&self, entry: &Entry) -> Vec<(String, Number)> {
+        let mut postings: Vec<(String, Number)> = vec![];
+
+        if entry.has_revenue() {
+            postings.push((self.account(INCOME_ACCOUNT, &entry.category()), Number::from(-entry.revenue().unwrap())));
+        }
+        if entry.has_expense() {
+            postings.push((self.account(EXPENSES_ACCOUNT, &entry.category()), Number::from(-entry.expense().unwrap())));
+        }
+
+        postings
+    }
+
+    /// Build an account name from a parent account and a category.
+    /// A `:` inside the category creates sub-accounts.
+    fn account(&self, parent: &str, category: &str) -> String {
+        let path = match self.format {
+            JournalFormat::Ledger => category.replace("  ", " ").replace('\t', " "),
+            JournalFormat::Beancount => category.split(':')
+                .map(beancount_component)
+                .collect::<Vec<String>>()
+                .join(":"),
+        };
+
+        format!("{}:{}", parent, path)
+    }
+
+    fn amount(&self, n: Number) -> String {
+        let currency = match (&self.currency, self.format) {
+            (Some(currency), _) => Some(currency.clone()),
+            (None, JournalFormat::Beancount) => Some(DEFAULT_CURRENCY.to_string()),
+            (None, JournalFormat::Ledger) => None,
+        };
+
+        match currency {
+            Some(currency) => format!("{:.2} {}", n.unwrap(), currency),
+            None => format!("{:.2}", n.unwrap()),
+        }
+    }
+}
+
+/// A transaction as read from a ledger journal.
+struct LedgerTransaction {
+    line: usize,
+    date: Date,
+    title: String,
+    comment: String,
+    uuid: Option<String>,
+    epic: Option<String>,
+    postings: Vec<(String, Option<NumberType>)>,
+}
+
+impl LedgerTransaction {
+    /// Parse the first line of a transaction.
+    ///
+    /// `DATE[=DATE2] [*|!] [(CODE)] PAYEE[ | NOTE] [; COMMENT]`
+    fn from_header(line: usize, raw: &str) -> Result<Self, ImportError> {
+        let (raw, comment) = split_comment(raw);
+
+        let mut parts = raw.splitn(2, char::is_whitespace);
+        let date_str = parts.next().unwrap_or("");
+        let date_str = date_str.split('=').next().unwrap().replace('.', "-");
+
+        let date = match Date::from_str(&date_str) {
+            Ok(date) if date.has_year() && date.has_month() && date.has_day() => date,
+            _ => return Err(ImportError::InvalidDate(line)),
+        };
+
+        let mut rest = parts.next().unwrap_or("").trim();
+        rest = rest.trim_start_matches(['*', '!']).trim_start();
+        if rest.starts_with('(') {
+            if let Some(end) = rest.find(')') {
+                rest = rest[(end + 1)..].trim_start();
+            }
+        }
+
+        let mut transaction = Self {
+            line,
+            date,
+            title: rest.to_string(),
+            comment: String::new(),
+            uuid: None,
+            epic: None,
+            postings: vec![],
+        };
+
+        // hledger: payee | note
+        if let Some(pos) = rest.find('|') {
+            transaction.title = rest[..pos].trim().to_string();
+            transaction.comment = rest[(pos + 1)..].trim().to_string();
+        }
+
+        if let Some(comment) = comment {
+            transaction.add_comment(comment);
+        }
+
+        Ok(transaction)
+    }
+
+    /// Comments are either tags (`key: value`) or free text.
+    fn add_comment(&mut self, comment: &str) {
+        match parse_tag(comment) {
+            Some((key, value)) if key == "uuid" => self.uuid = Some(value),
+            Some((key, value)) if key == "epic" => self.epic = Some(value),
+            Some(_) => (),
+            None => {
+                if self.comment.is_empty() {
+                    self.comment = comment.to_string();
+                }
+            },
+        }
+    }
+
+    /// Parse a posting line: `ACCOUNT  [AMOUNT] [; COMMENT]`.
+    /// Account and amount are separated by at least two spaces or a tab.
+    fn add_posting(&mut self, line: usize, raw: &str) -> Result<(), ImportError> {
+        let (raw, _) = split_comment(raw);
+        let raw = raw.trim();
+
+        let (account, amount) = match raw.find("  ").into_iter().chain(raw.find('\t')).min() {
+            Some(pos) => (raw[..pos].trim(), raw[pos..].trim()),
+            None => (raw, ""),
+        };
+
+        let amount = if amount.is_empty() {
+            None
+        } else {
+            match parse_amount(amount) {
+                Some(n) => Some(n),
+                None => return Err(ImportError::InvalidAmount(line)),
+            }
+        };
+
+        self.postings.push((account.to_string(), amount));
+        Ok(())
+    }
+
+    /// Key for transactions without `uuid` tag, built from all fields,
+    /// so the same transaction always gets the same entry ID.
+    fn key(&self) -> String {
+        let postings: Vec<String> = self.postings.iter()
+            .map(|(account, amount)| format!("{}={}", account, amount.map(|n| n.to_string()).unwrap_or_default()))
+            .collect();
+
+        format!("ledger|{}|{}|{}|{}",
+            self.date.ymd(),
+            self.title,
+            self.comment,
+            postings.join(","))
+    }
+
+    /// Map the postings to an Entry. Income accounts become revenues,
+    /// expense accounts become expenses. The account path below the
+    /// root account is used as category.
+    fn to_entry(&self, occurrence: usize) -> Result<Option<Entry>, ImportError> {
+        let missing = self.postings.iter().filter(|(_, amount)| amount.is_none()).count();
+        if missing > 1 {
+            return Err(ImportError::InvalidAmount(self.line));
+        }
+
+        // The posting without amount balances the transaction.
+        let sum: NumberType = self.postings.iter().filter_map(|(_, amount)| *amount).sum();
+
+        let mut revenue: NumberType = 0.0;
+        let mut expense: NumberType = 0.0;
+        let mut category: Option<String> = None;
+
+        for (account, amount) in &self.postings {
+            let amount = amount.unwrap_or(-sum);
+
+            let mut path = account.splitn(2, ':');
+            let root = path.next().unwrap().to_lowercase();
+            let sub = path.next().unwrap_or("default");
+
+            match root.as_str() {
+                "income" | "revenue" | "revenues" => revenue -= amount,
+                "expenses" | "expense" => expense += amount,
+                _ => continue,
+            }
+
+            if category.is_none() {
+                category = Some(sub.to_string());
+            }
+        }
+
+        let category = match category {
+            Some(category) => category,
+            None => return Ok(None),
+        };
+
+        // Refunds and reversals.
+        if revenue < 0.0 {
+            expense -= revenue;
+            revenue = 0.0;
+        }
+        if expense < 0.0 {
+            revenue -= expense;
+            expense = 0.0;
+        }
+
+        let mut entry = Entry::new();
+        entry.set_date(self.date);
+        entry.set_title(self.title.clone());
+        entry.set_comment(self.comment.clone());
+        entry.set_category(category);
+        entry.set_revenue(revenue);
+        entry.set_expense(expense);
+
+        match &self.uuid {
+            Some(uuid) => entry.set_id(uuid.clone()),
+            None => {
+                // Identical transactions on the same day, e.g. two coffees.
+                let key = format!("{}|{}", self.key(), occurrence);
+                entry.set_id(Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes()).to_string());
+            },
+        }
+        if let Some(epic) = &self.epic {
+            entry.set_epic(epic.clone());
+        }
+
+        Ok(Some(entry))
+    }
+}
+
+/// Split `text ; comment` into its two parts.
+fn split_comment(raw: &str) -> (&str, Option<&str>) {
+    match raw.find(';') {
+        Some(pos) => (raw[..pos].trim_end(), Some(raw[(pos + 1)..].trim())),
+        None => (raw, None),
+    }
+}
+
+/// Parse a `key: value` tag.
+fn parse_tag(comment: &str) -> Option<(String, String)> {
+    let pos = comment.find(':')?;
+    let key = comment[..pos].trim();
+
+    if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+
+    Some((key.to_lowercase(), comment[(pos + 1)..].trim().to_string()))
+}
+
+/// Add the Entry of a finished transaction to the result.
+fn finish_transaction(transaction: LedgerTransaction, result: &mut ImportResult, occurrences: &mut HashMap<String, usize>) -> Result<(), ImportError> {
+    let occurrence = occurrences.entry(transaction.key()).or_insert(0);
+    *occurrence += 1;
+
+    match transaction.to_entry(*occurrence)? {
+        Some(entry) => result.entries.push(entry),
+        None => result.skipped += 1,
+    }
+    Ok(())
+}
+
+/// Read a ledger or hledger journal.
+///
+/// Directives, periodic and automated transactions are ignored.
+/// Transactions without an income or expense posting are skipped.
+pub fn read_ledger(raw: &str) -> Result<ImportResult, ImportError> {
+    let mut result = ImportResult::new();
+    let mut current: Option<LedgerTransaction> = None;
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    for (index, line) in raw.lines().enumerate() {
+        let line_no = index + 1;
+
+        if line.trim().is_empty() || !line.starts_with(char::is_whitespace) {
+            // Finish the current transaction.
+            if let Some(transaction) = current.take() {
+                finish_transaction(transaction, &mut result, &mut occurrences)?;
+            }
+
+            if line.starts_with(|c: char| c.is_ascii_digit()) {
+                current = Some(LedgerTransaction::from_header(line_no, line)?);
+            }
+            continue;
+        }
+
+        if let Some(transaction) = current.as_mut() {
+            let trimmed = line.trim();
+            if let Some(comment) = trimmed.strip_prefix(';') {
+                transaction.add_comment(comment.trim());
+            } else {
+                transaction.add_posting(line_no, trimmed)?;
+            }
+        }
+    }
+
+    if let Some(transaction) = current.take() {
+        finish_transaction(transaction, &mut result, &mut occurrences)?;
+    }
+
+    Ok(result)
+}
+
+/// Beancount account components and tags must start with a capital
+/// letter or a digit and may only contain letters, digits and dashes.
+fn beancount_component(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.trim().chars() {
+        if out.is_empty() {
+            if c.is_alphanumeric() {
+                out.extend(c.to_uppercase());
+            }
+        } else if c.is_alphanumeric() || c == '-' {
+            out.push(c);
+        } else {
+            out.push('-');
+        }
+    }
+
+    if out.is_empty() {
+        out.push_str("Default");
+    }
+
+    out
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests_beancount_component {
+    use super::beancount_component;
+
+    #[test]
+    fn test_beancount_component1() {
+        assert_eq!("Food", beancount_component("food"));
+        assert_eq!("Eating-out", beancount_component("eating out"));
+        assert_eq!("Trip2020", beancount_component("_trip2020"));
+        assert_eq!("Default", beancount_component("!!"));
+    }
+}
+
+#[cfg(test)]
+mod tests_writer {
+    use super::{JournalWriter, JournalFormat};
+    use crate::entry::Entry;
+
+    fn entries() -> Vec<Entry> {
+        let mut e1 = Entry::from("Groceries/2020-01-05/0/23.4");
+        e1.set_id("id1".to_string());
+        e1.set_category("food".to_string());
+        e1.set_comment("Weekly".to_string());
+        e1.set_epic("trip".to_string());
+
+        let mut e2 = Entry::from("Salary/2020-01-01/1000/0");
+        e2.set_id("id2".to_string());
+        e2.set_category("work".to_string());
+
+        vec![e1, e2]
+    }
+
+    fn write(writer: JournalWriter) -> String {
+        let entries = entries();
+        let refs: Vec<&Entry> = entries.iter().collect();
+
+        let mut out: Vec<u8> = vec![];
+        writer.write(&refs, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_writer_ledger1() {
+        let s = write(JournalWriter::new(JournalFormat::Ledger));
+        let lines: Vec<&str> = s.lines().collect();
+
+        assert_eq!("2020-01-01 Salary", lines[0]);
+        assert_eq!("    ; uuid: id2", lines[1]);
+        assert_eq!("    Income:work                               -1000.00", lines[2]);
+        assert_eq!("    Assets:Wallet", lines[3]);
+        assert_eq!("", lines[4]);
+        assert_eq!("2020-01-05 Groceries  ; Weekly", lines[5]);
+        assert_eq!("    ; uuid: id1", lines[6]);
+        assert_eq!("    ; epic: trip", lines[7]);
+        assert_eq!("    Expenses:food                             23.40", lines[8]);
+        assert_eq!("    Assets:Wallet", lines[9]);
+    }
+
+    #[test]
+    fn test_writer_ledger_currency1() {
+        let mut writer = JournalWriter::new(JournalFormat::Ledger);
+        writer.set_currency(Some("USD".to_string()));
+
+        let s = write(writer);
+        assert!(s.contains("Income:work                               -1000.00 USD"));
+    }
+
+    #[test]
+    fn test_writer_beancount1() {
+        let s = write(JournalWriter::new(JournalFormat::Beancount));
+        let lines: Vec<&str> = s.lines().collect();
+
+        assert_eq!("2020-01-01 open Assets:Wallet", lines[0]);
+        assert_eq!("2020-01-01 open Expenses:Food", lines[1]);
+        assert_eq!("2020-01-01 open Income:Work", lines[2]);
+        assert_eq!("", lines[3]);
+        assert_eq!("2020-01-01 * \"Salary\" \"\"", lines[4]);
+        assert_eq!("  uuid: \"id2\"", lines[5]);
+        assert_eq!("  Income:Work                               -1000.00 EUR", lines[6]);
+        assert_eq!("  Assets:Wallet", lines[7]);
+        assert_eq!("2020-01-05 * \"Groceries\" \"Weekly\" #Trip", lines[9]);
+    }
+}
+
+#[cfg(test)]
+mod tests_reader {
+    use super::{read_ledger, JournalWriter, JournalFormat};
+    use crate::entry::Entry;
+    use crate::import::ImportError;
+
+    #[test]
+    fn test_read_ledger1() {
+        let raw = "; A comment
+account Assets:Bank
+
+2020/01/05 * (42) Groceries  ; Weekly
+    ; uuid: id1
+    ; epic: trip
+    Expenses:food:fruits    $23.40
+    Assets:Bank
+
+2020-01-01 Salary | January
+    Assets:Bank    1,000.00 EUR
+    Income:work
+
+2020-01-02 Transfer
+    Assets:Savings    100
+    Assets:Bank
+";
+        let r1 = read_ledger(raw).unwrap();
+        assert_eq!(2, r1.entries.len());
+        assert_eq!(1, r1.skipped);
+
+        let e1 = &r1.entries[0];
+        assert_eq!("id1", e1.id());
+        assert_eq!("Groceries", e1.title());
+        assert_eq!("Weekly", e1.comment());
+        assert_eq!("2020-01-05", e1.date().to_string());
+        assert_eq!("food:fruits", e1.category());
+        assert_eq!("trip", e1.epic());
+        assert_eq!(-23.4, e1.expense().unwrap());
+        assert!(!e1.has_revenue());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("Salary", e2.title());
+        assert_eq!("January", e2.comment());
+        assert_eq!("work", e2.category());
+        assert_eq!(1000.0, e2.revenue().unwrap());
+        assert!(!e2.has_expense());
+    }
+
+    /// Transactions without `uuid` tag always get the same IDs.
+    #[test]
+    fn test_read_ledger_stable_ids1() {
+        let raw = "2020-01-05 Coffee\n    Expenses:food  3\n    Assets:Bank\n\n2020-01-05 Coffee\n    Expenses:food  3\n    Assets:Bank\n";
+        let r1 = read_ledger(raw).unwrap();
+        let r2 = read_ledger(raw).unwrap();
+
+        let ids1: Vec<String> = r1.entries.iter().map(|e| e.id()).collect();
+        let ids2: Vec<String> = r2.entries.iter().map(|e| e.id()).collect();
+        assert_eq!(ids1, ids2);
+        assert_ne!(ids1[0], ids1[1]);
+    }
+
+    #[test]
+    fn test_read_ledger_refund1() {
+        let raw = "2020-01-05 Refund\n    Expenses:food  -5\n    Assets:Bank\n";
+        let r1 = read_ledger(raw).unwrap();
+        assert_eq!(5.0, r1.entries[0].revenue().unwrap());
+        assert!(!r1.entries[0].has_expense());
+    }
+
+    #[test]
+    fn test_read_ledger_bad1() {
+        assert_eq!(ImportError::InvalidDate(1), read_ledger("20x0-01-01 Bad\n").unwrap_err());
+        assert_eq!(ImportError::InvalidAmount(2), read_ledger("2020-01-01 Bad\n    Expenses:x  abc\n    Assets:Bank\n").unwrap_err());
+    }
+
+    /// Exported journals can be read again.
+    #[test]
+    fn test_read_ledger_roundtrip1() {
+        let mut e1 = Entry::from("Mixed/2020-01-05/10/30");
+        e1.set_id("id1".to_string());
+        e1.set_category("misc".to_string());
+        e1.set_comment("Note".to_string());
+
+        let mut out: Vec<u8> = vec![];
+        JournalWriter::new(JournalFormat::Ledger).write(&[&e1], &mut out).unwrap();
+
+        let r1 = read_ledger(&String::from_utf8(out).unwrap()).unwrap();
+        let e2 = &r1.entries[0];
+        assert_eq!(e1.id(), e2.id());
+        assert_eq!(e1.title(), e2.title());
+        assert_eq!(e1.comment(), e2.comment());
+        assert_eq!(e1.category(), e2.category());
+        assert_eq!(e1.revenue(), e2.revenue());
+        assert_eq!(e1.expense(), e2.expense());
+    }
+}
@@ -12,3 +12,23 @@ pub mod yaml;
 pub mod string;
 pub mod number;
 pub mod mustache;
+pub mod export;
+pub mod journal;
+pub mod import;
+pub mod ofx;
+pub mod qif;
+pub mod camt;
+pub mod mt940;
+pub mod prompt;
+pub mod tui;
+pub mod search;
+pub mod query;
+pub mod sort;
+pub mod stats;
+pub mod forecast;
+pub mod history;
+pub mod git;
+pub mod merge;
+pub mod sync;
+pub mod crypt;
+pub mod chain;
@@ -0,0 +1,157 @@
+//! Merge another wallet into this one.
+//!
+//! Entries are matched by ID. Missing Entries are added, Entries with
+//! the same ID but different content are conflicts.
+
+use std::str::FromStr;
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use crate::entry::Entry;
+use crate::chain::SealError;
+
+#[derive(Debug, PartialEq)]
+pub enum MergeError {
+    InvalidStrategy,
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            MergeError::InvalidStrategy => write!(f, "Invalid strategy, use newest, ours, theirs or interactive"),
+        }
+    }
+}
+
+/// How conflicts are resolved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeStrategy {
+    /// The Entry which was changed last. Entries written before the
+    /// change time was kept fall back to the update time of their month file.
+    Newest,
+    Ours,
+    Theirs,
+    Interactive,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = MergeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "newest" => Ok(MergeStrategy::Newest),
+            "ours" => Ok(MergeStrategy::Ours),
+            "theirs" => Ok(MergeStrategy::Theirs),
+            "interactive" => Ok(MergeStrategy::Interactive),
+            _ => Err(MergeError::InvalidStrategy),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+}
+
+impl MergeStrategy {
+    /// Resolve without asking. None for Interactive.
+    /// `ours_time` and `theirs_time` are the change times of the Entries.
+    pub fn resolve(self, ours_time: &Option<String>, theirs_time: &Option<String>) -> Option<Resolution> {
+        match self {
+            MergeStrategy::Ours => Some(Resolution::Ours),
+            MergeStrategy::Theirs => Some(Resolution::Theirs),
+            MergeStrategy::Newest => if theirs_time > ours_time {
+                Some(Resolution::Theirs)
+            } else {
+                Some(Resolution::Ours)
+            },
+            MergeStrategy::Interactive => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub ours: Entry,
+    pub theirs: Entry,
+    pub resolution: Resolution,
+}
+
+/// A conflict resolved as theirs which could not be applied,
+/// like in a sealed month.
+#[derive(Debug)]
+pub struct MergeFailure {
+    pub entry: Entry,
+    pub error: SealError,
+}
+
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    pub added: Vec<Entry>,
+    /// Same ID and content.
+    pub unchanged: usize,
+    /// Not in our month files but in our index, like removed Entries,
+    /// or in a sealed month.
+    pub skipped: usize,
+    pub conflicts: Vec<Conflict>,
+    pub failed: Vec<MergeFailure>,
+    /// Handles of added Epics.
+    pub epics: Vec<String>,
+}
+
+impl MergeReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&self) {
+        println!("Added:     {}", self.added.len());
+        for entry in &self.added {
+            println!("  {} {:>10.2}  {}", entry.date().ymd(), entry.balance().unwrap(), entry.title());
+        }
+
+        println!("Unchanged: {}", self.unchanged);
+        println!("Skipped:   {}", self.skipped);
+
+        println!("Conflicts: {}", self.conflicts.len());
+        for conflict in &self.conflicts {
+            let (kept, name) = match conflict.resolution {
+                Resolution::Ours => (&conflict.ours, "ours"),
+                Resolution::Theirs => (&conflict.theirs, "theirs"),
+            };
+            println!("  {} {} {:>10.2}  {} ({})", kept.id(), kept.date().ymd(), kept.balance().unwrap(), kept.title(), name);
+        }
+
+        if !self.failed.is_empty() {
+            println!("Failed:    {}", self.failed.len());
+            for failure in &self.failed {
+                let entry = &failure.entry;
+                println!("  {} {} {:>10.2}  {}", entry.id(), entry.date().ymd(), entry.balance().unwrap(), entry.title());
+                println!("    {}", failure.error);
+            }
+        }
+
+        println!("Epics:     {}", self.epics.len());
+        for handle in &self.epics {
+            println!("  {}", handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_merge {
+    use std::str::FromStr;
+    use super::{MergeStrategy, MergeError, Resolution};
+
+    #[test]
+    fn test_strategy1() {
+        assert_eq!(MergeStrategy::Theirs, MergeStrategy::from_str("Theirs").unwrap());
+        assert_eq!(MergeError::InvalidStrategy, MergeStrategy::from_str("x").unwrap_err());
+
+        let older = Some("2020-01-05T10:00:00+00:00".to_string());
+        let newer = Some("2020-02-01T10:00:00+00:00".to_string());
+        assert_eq!(Some(Resolution::Theirs), MergeStrategy::Newest.resolve(&older, &newer));
+        assert_eq!(Some(Resolution::Ours), MergeStrategy::Newest.resolve(&newer, &older));
+        assert_eq!(Some(Resolution::Ours), MergeStrategy::Newest.resolve(&newer, &newer));
+        assert_eq!(None, MergeStrategy::Interactive.resolve(&older, &newer));
+    }
+}
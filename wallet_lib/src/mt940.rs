@@ -0,0 +1,290 @@
+//! SWIFT MT940 customer statements.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use regex::Regex;
+use chrono::NaiveDate;
+use uuid::Uuid;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::import::{ImportResult, ImportError, parse_amount};
+
+/// A tag like `:61:` with its value. Continuation lines are joined with `\n`.
+struct Field {
+    line: usize,
+    tag: String,
+    value: String,
+}
+
+/// Split the file into fields. SWIFT block headers (`{1:...}{4:`)
+/// and trailers (`-}`) are ignored.
+fn fields(raw: &str) -> Vec<Field> {
+    let re = Regex::new(r"^:(?P<tag>\d{2}[A-Z]?):(?P<value>.*)$").unwrap();
+    let mut fields: Vec<Field> = vec![];
+
+    for (index, line) in raw.lines().enumerate() {
+        let line = line.trim_end();
+
+        if let Some(captures) = re.captures(line) {
+            fields.push(Field {
+                line: index + 1,
+                tag: captures["tag"].to_string(),
+                value: captures["value"].to_string(),
+            });
+        } else if line.starts_with('{') || line.starts_with('-') || line.is_empty() {
+            continue;
+        } else if let Some(field) = fields.last_mut() {
+            field.value.push('\n');
+            field.value.push_str(line);
+        }
+    }
+
+    fields
+}
+
+/// `YYMMDD` to Date.
+fn parse_date(s: &str) -> Option<Date> {
+    if s.len() != 6 || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let y: i32 = 2000 + s[..2].parse::<i32>().ok()?;
+    let m: u32 = s[2..4].parse().ok()?;
+    let d: u32 = s[4..6].parse().ok()?;
+
+    // Date::from_str panics on invalid values.
+    NaiveDate::from_ymd_opt(y, m, d)?;
+
+    Date::from_str(&format!("{}-{}-{}", y, m, d)).ok()
+}
+
+/// The `:86:` information field. Structured (German `?NN` subfields) or free text.
+struct Information {
+    name: Option<String>,
+    purpose: Option<String>,
+}
+
+impl Information {
+    fn from(value: &str) -> Self {
+        // Structured: `166?00GUTSCHRIFT?20Invoice 42?32ACME Corp`
+        // Subfields wrap in the middle of the text.
+        // Not structured when the first bytes are no ASCII digits.
+        let joined = value.replace('\n', "");
+        let structured = joined.get(..3).is_some_and(|code| code.chars().all(|c| c.is_ascii_digit()))
+            && joined.get(3..).is_some_and(|rest| rest.starts_with('?'));
+        if structured {
+            let mut subfields: Vec<(String, String)> = vec![];
+            for part in joined[4..].split('?') {
+                if let (Some(code), Some(text)) = (part.get(..2), part.get(2..)) {
+                    subfields.push((code.to_string(), text.trim().to_string()));
+                }
+            }
+
+            let join = |codes: &[&str]| -> Option<String> {
+                let parts: Vec<&str> = subfields.iter()
+                    .filter(|(code, text)| codes.contains(&code.as_str()) && !text.is_empty())
+                    .map(|(_, text)| text.as_str())
+                    .collect();
+                if parts.is_empty() {
+                    None
+                } else {
+                    Some(parts.join(" "))
+                }
+            };
+
+            let purpose = join(&["20", "21", "22", "23", "24", "25", "26", "27", "28", "29", "60", "61", "62", "63"])
+                .or_else(|| join(&["00"]));
+
+            return Self {
+                name: join(&["32", "33"]),
+                purpose,
+            };
+        }
+
+        // Free text continues on separate lines.
+        let lines: Vec<&str> = value.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        Self {
+            name: None,
+            purpose: if lines.is_empty() { None } else { Some(lines.join(" ")) },
+        }
+    }
+}
+
+/// Read an MT940 statement.
+///
+/// Every `:61:` statement line becomes an entry, using the value date.
+/// The following `:86:` field provides title and comment. MT940 has no
+/// unique transaction IDs, so the ID is derived from the content of the
+/// statement line to block double imports.
+pub fn read_mt940(raw: &str) -> Result<ImportResult, ImportError> {
+    let fields = fields(raw);
+    if !fields.iter().any(|field| field.tag == "20") {
+        return Err(ImportError::InvalidFormat);
+    }
+
+    // :61: value date, optional entry date, D/C mark, funds code, amount,
+    // transaction type, customer reference, bank reference.
+    let re = Regex::new(r"^(?P<vdate>\d{6})(?P<edate>\d{4})?(?P<dc>R?[CD])(?P<funds>[A-Z])?(?P<amount>[\d,]+)(?P<type>[A-Z][A-Z0-9]{3})(?P<cref>[^/\n]*)(//(?P<bref>[^\n]*))?").unwrap();
+
+    let mut result = ImportResult::new();
+    let mut account = String::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        match field.tag.as_str() {
+            "25" => account = field.value.trim().to_string(),
+            "60F" | "60M" => {
+                let date = field.value.get(1..7).and_then(parse_date);
+                if result.start.is_none() {
+                    result.start = date;
+                }
+            },
+            "62F" | "62M" => {
+                let date = field.value.get(1..7).and_then(parse_date);
+                if date.is_some() {
+                    result.end = date;
+                }
+            },
+            "61" => {
+                let captures = match re.captures(&field.value) {
+                    Some(captures) => captures,
+                    None => return Err(ImportError::InvalidAmount(field.line)),
+                };
+
+                let date = parse_date(&captures["vdate"]).ok_or(ImportError::InvalidDate(field.line))?;
+                let amount = parse_amount(&captures["amount"]).ok_or(ImportError::InvalidAmount(field.line))?;
+
+                // A reversal of a debit is a credit and the other way round.
+                let credit = matches!(&captures["dc"], "C" | "RD");
+
+                let information = match fields.get(index + 1) {
+                    Some(next) if next.tag == "86" => Information::from(&next.value),
+                    _ => Information {
+                        name: None,
+                        purpose: None,
+                    },
+                };
+
+                let customer_reference = captures["cref"].trim().to_string();
+                let bank_reference = captures.name("bref").map(|m| m.as_str().trim()).unwrap_or("");
+
+                let key = format!("mt940|{}|{}|{}|{}|{}|{}|{}",
+                    account, &captures["vdate"], &captures["dc"], &captures["amount"],
+                    customer_reference, bank_reference,
+                    information.purpose.as_deref().unwrap_or(""));
+                let occurrence = occurrences.entry(key.clone()).or_insert(0);
+                *occurrence += 1;
+                let id = Uuid::new_v5(&Uuid::NAMESPACE_OID, format!("{}|{}", key, occurrence).as_bytes());
+
+                let mut entry = Entry::new();
+                entry.set_id(id.to_string());
+                entry.set_date(date);
+
+                match (information.name, information.purpose) {
+                    (Some(name), Some(purpose)) => {
+                        entry.set_title(name);
+                        entry.set_comment(purpose);
+                    },
+                    (Some(title), None) | (None, Some(title)) => entry.set_title(title),
+                    (None, None) => entry.set_title(customer_reference),
+                }
+
+                if credit {
+                    entry.set_revenue(amount);
+                } else {
+                    entry.set_expense(amount);
+                }
+
+                result.entries.push(entry);
+            },
+            _ => (),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests_read {
+    use super::read_mt940;
+    use crate::import::ImportError;
+
+    const MT940: &str = "{1:F01BANKATWWAXXX0000000000}{2:I940BANKATWWXXXXN}{4:
+:20:STMT2001
+:25:AT611904300234573201
+:28C:1/1
+:60F:C200101EUR1000,00
+:61:2001050105D23,40NMSCNONREF//B200105001
+:86:166?00KARTENZAHLUNG?20Card 1234?21Vienna?32Supermarket
+:61:200102C1000,00NTRFNONREF
+:86:Salary January
+ACME Corp
+:61:200106RC5,00NMSCNONREF
+:61:200107CR2,50NMSCREF42
+:62F:C200131EUR1981,10
+-}
+";
+
+    #[test]
+    fn test_read_mt940_1() {
+        let r1 = read_mt940(MT940).unwrap();
+        assert_eq!(4, r1.entries.len());
+        assert_eq!("2020-01-01", r1.start.unwrap().to_string());
+        assert_eq!("2020-01-31", r1.end.unwrap().to_string());
+
+        let e1 = &r1.entries[0];
+        assert_eq!("2020-01-05", e1.date().to_string());
+        assert_eq!("Supermarket", e1.title());
+        assert_eq!("Card 1234 Vienna", e1.comment());
+        assert_eq!(-23.4, e1.expense().unwrap());
+        assert!(!e1.has_revenue());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("2020-01-02", e2.date().to_string());
+        assert_eq!("Salary January ACME Corp", e2.title());
+        assert_eq!(1000.0, e2.revenue().unwrap());
+
+        // Reversal of a credit.
+        let e3 = &r1.entries[2];
+        assert_eq!(-5.0, e3.expense().unwrap());
+        assert_eq!("NONREF", e3.title());
+
+        // Credit with funds code.
+        let e4 = &r1.entries[3];
+        assert_eq!(2.5, e4.revenue().unwrap());
+        assert_eq!("REF42", e4.title());
+    }
+
+    /// The same statement always produces the same IDs.
+    #[test]
+    fn test_read_mt940_stable_ids1() {
+        let r1 = read_mt940(MT940).unwrap();
+        let r2 = read_mt940(MT940).unwrap();
+
+        let ids1: Vec<String> = r1.entries.iter().map(|e| e.id()).collect();
+        let ids2: Vec<String> = r2.entries.iter().map(|e| e.id()).collect();
+        assert_eq!(ids1, ids2);
+        assert_ne!(ids1[0], ids1[1]);
+    }
+
+    #[test]
+    fn test_read_mt940_bad1() {
+        assert_eq!(ImportError::InvalidFormat, read_mt940("hello").unwrap_err());
+        assert_eq!(ImportError::InvalidDate(2), read_mt940(":20:X\n:61:201350D1,00NMSCNONREF\n").unwrap_err());
+        assert_eq!(ImportError::InvalidAmount(2), read_mt940(":20:X\n:61:200101X1,00\n").unwrap_err());
+    }
+
+    #[test]
+    fn test_read_mt940_multibyte1() {
+        let r1 = read_mt940(":20:X\n:61:200101D1,00NMSCNONREF\n:86:€uro\n").unwrap();
+        assert_eq!(1, r1.entries.len());
+        assert_eq!("€uro", r1.entries[0].title());
+
+        let r2 = read_mt940(":20:X\n:61:200101D1,00NMSCNONREF\n:86:166?2€?32Café\n").unwrap();
+        assert_eq!(1, r2.entries.len());
+        assert_eq!("Café", r2.entries[0].title());
+    }
+}
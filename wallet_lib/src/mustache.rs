@@ -14,7 +14,10 @@ use serde::Serialize;
 use crate::wallet::FilterResult;
 use crate::wallet::YearSummary;
 use crate::wallet::Year;
-use crate::number::Number;
+use crate::wallet::{Month, MonthSummary};
+use crate::entry::{Entry, running_balances};
+use crate::number::{Number, NumberType};
+use crate::forecast::Forecast;
 use crate::number::ToDisplay;
 
 const APP_NAME: &'static str = "WalletRust";
@@ -57,6 +60,85 @@ impl MustacheYear {
 //     }
 // }
 
+/// SVG polylines of the cumulative balance per month.
+/// The forecast continues the last balance.
+#[derive(Debug, Serialize)]
+struct BalanceChart {
+    width: u32,
+    height: u32,
+    zero_y: String,
+    points: String,
+    forecast_points: String,
+}
+
+impl BalanceChart {
+    const WIDTH: u32 = 600;
+    const HEIGHT: u32 = 200;
+
+    fn new(history: &[NumberType], forecast: &[NumberType]) -> Option<Self> {
+        if history.is_empty() {
+            return None;
+        }
+
+        let all: Vec<NumberType> = history.iter().chain(forecast.iter()).cloned().collect();
+        let min = all.iter().cloned().fold(0.0, NumberType::min);
+        let max = all.iter().cloned().fold(0.0, NumberType::max);
+        let range = if max > min { max - min } else { 1.0 };
+
+        let step = Self::WIDTH as NumberType / (all.len().max(2) - 1) as NumberType;
+        let y = |v: NumberType| (max - v) / range * Self::HEIGHT as NumberType;
+        let point = |i: usize, v: NumberType| format!("{:.1},{:.1}", i as NumberType * step, y(v));
+
+        let points: Vec<String> = history.iter().enumerate()
+            .map(|(i, v)| point(i, *v))
+            .collect();
+
+        let mut forecast_points: Vec<String> = vec![];
+        if !forecast.is_empty() {
+            let last = history.len() - 1;
+            forecast_points.push(point(last, history[last]));
+            forecast_points.extend(forecast.iter().enumerate().map(|(i, v)| point(last + 1 + i, *v)));
+        }
+
+        Some(Self {
+            width: Self::WIDTH,
+            height: Self::HEIGHT,
+            zero_y: format!("{:.1}", y(0.0)),
+            points: points.join(" "),
+            forecast_points: forecast_points.join(" "),
+        })
+    }
+}
+
+/// Balance at the end of each month up to the forecast start.
+fn balance_history(result: &FilterResult, forecast: &Forecast) -> Vec<NumberType> {
+    let mut keys: Vec<(Year, Month)> = result.years.values()
+        .flat_map(|year_sum| year_sum.months.keys().map(move |month| (year_sum.year, *month)))
+        .filter(|key| *key <= (forecast.year, forecast.month))
+        .collect();
+    keys.sort();
+
+    let mut history: Vec<NumberType> = vec![];
+    let mut balance = 0.0;
+    if let Some(first) = keys.first() {
+        let (mut year, mut month) = *first;
+        while (year, month) <= (forecast.year, forecast.month) {
+            if let Some(month_sum) = result.years.get(&year).and_then(|year_sum| year_sum.months.get(&month)) {
+                balance += month_sum.balance.unwrap();
+            }
+            history.push(balance);
+
+            month += 1;
+            if month > 12 {
+                month = 1;
+                year += 1;
+            }
+        }
+    }
+
+    history
+}
+
 pub struct IndexMustacheFile {
     path: String,
 }
@@ -69,8 +151,9 @@ impl IndexMustacheFile {
         }
     }
 
-    /// Render file.
-    pub fn render(&self, _result: &FilterResult) {
+    /// Render file. The balance chart is drawn up to the forecast start,
+    /// the forecast continues it dashed.
+    pub fn render(&self, _result: &FilterResult, forecast: &Forecast) {
         println!("-> MustacheFile::render()");
 
         // Now
@@ -96,7 +179,10 @@ impl IndexMustacheFile {
         let mut balance_sum = Number::new();
 
         // let _i: Vec<MustacheYear> = _result.years.values().map(|year_sum| MustacheYear::from(year_sum)).collect();
-        let _myears: Vec<MustacheYear> = _result.years.values()
+        let mut year_sums: Vec<&YearSummary> = _result.years.values().collect();
+        year_sums.sort_by_key(|year_sum| year_sum.year);
+
+        let _myears: Vec<MustacheYear> = year_sums.into_iter()
             .map(|year_sum| {
                 index += 1;
                 println!("-> index: {:?}", index);
@@ -132,7 +218,11 @@ impl IndexMustacheFile {
         //     builder
         // };
 
-        let data = MapBuilder::new()
+        // Chart
+        let forecast_balances: Vec<NumberType> = forecast.months.iter().map(|month| month.end_balance).collect();
+        let chart = BalanceChart::new(&balance_history(_result, forecast), &forecast_balances);
+
+        let mut builder = MapBuilder::new()
             .insert_str("PROJECT_NAME", APP_NAME)
             .insert_str("PROJECT_VERSION_FULL", APP_VERSION)
             .insert_str("PROJECT_HOMEPAGE_URL", APP_HOMEPAGE)
@@ -141,9 +231,13 @@ impl IndexMustacheFile {
             .insert_str("css_relative_path", ".")
             .insert_str("relative_path", ".")
 
-            .insert_vec("years", f_years)
+            .insert_vec("years", f_years);
+
+        if let Some(chart) = chart {
+            builder = builder.insert("chart", &chart).expect("Cannot insert chart.");
+        }
 
-            .build();
+        let data = builder.build();
 
         println!("-> render_data");
         template.render_data(&mut _file, &data)
@@ -151,10 +245,101 @@ impl IndexMustacheFile {
     }
 }
 
+#[derive(Debug, Serialize)]
+struct MustacheEntry {
+    index: usize,
+    date: String,
+    title: String,
+    balance: String,
+    balance_sum: String,
+}
+
+pub struct MonthMustacheFile {
+    path: String,
+}
+
+impl MonthMustacheFile {
+    /// New Month Mustache file.
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+        }
+    }
+
+    /// Render file. The running balance starts at `carried_over`
+    /// when given, otherwise at zero. Closed months get a badge.
+    pub fn render(&self, year: Year, month: Month, month_sum: &MonthSummary, carried_over: Option<Number>, closed: bool) {
+        println!("-> MonthMustacheFile::render({}, {})", year, month);
+
+        // Now
+        let now: DateTime<Local> = Local::now();
+
+        // Template Source
+        let bytes = include_bytes!("../../resources/views/month.mustache");
+        let raw = String::from_utf8_lossy(bytes);
+
+        // Compile Template
+        let template = compile_str(&raw).unwrap();
+
+        let mut file = match File::create(&self.path) {
+            Ok(file) => file,
+            Err(why) => panic!("Cannot create {}: {}", self.path, why),
+        };
+
+        let entries: Vec<Entry> = month_sum.entries.iter().map(|entry| (**entry).clone()).collect();
+        let running = running_balances(&entries, carried_over.unwrap_or_else(Number::new));
+
+        let mentries: Vec<MustacheEntry> = entries.iter().zip(running)
+            .enumerate()
+            .map(|(index, (entry, balance_sum))| {
+                MustacheEntry {
+                    index: index + 1,
+                    date: entry.date().ymd(),
+                    title: entry.title(),
+                    balance: format!("{}", entry.balance().to_display()),
+                    balance_sum: format!("{:.2}", balance_sum.unwrap()),
+                }
+            })
+            .collect();
+
+        let f_entries = move |mut builder: VecBuilder| {
+            for e in &mentries {
+                builder = builder.push(&e).unwrap();
+            }
+            builder
+        };
+
+        let mut builder = MapBuilder::new()
+            .insert_str("PROJECT_NAME", APP_NAME)
+            .insert_str("PROJECT_VERSION_FULL", APP_VERSION)
+            .insert_str("PROJECT_HOMEPAGE_URL", APP_HOMEPAGE)
+
+            .insert_str("generated_at", now.format("%F %T %z").to_string())
+            .insert_str("css_relative_path", "../..")
+            .insert_str("relative_path", "../..")
+
+            .insert_str("year", year.to_string())
+            .insert_str("month", format!("{:02}", month))
+            .insert_bool("closed", closed)
+            .insert_vec("entries", f_entries);
+
+        if let Some(carried_over) = carried_over {
+            builder = builder.insert_str("carried_over", format!("{:.2}", carried_over.unwrap()));
+        }
+
+        let data = builder.build();
+
+        template.render_data(&mut file, &data)
+            .expect("Failed to render");
+    }
+}
+
 #[cfg(test)]
 mod tests_index_mustache_file {
-    use super::IndexMustacheFile;
+    use super::{IndexMustacheFile, BalanceChart};
     use crate::wallet::FilterResult;
+    use crate::forecast::Forecast;
+    use crate::date::Date;
     use std::fs::create_dir_all;
 
     fn setup() {
@@ -167,8 +352,50 @@ mod tests_index_mustache_file {
         setup();
 
         let r1 = FilterResult::new();
+        let fc1 = Forecast::from_result(&r1, Date::new(), 0);
 
         let f1 = IndexMustacheFile::new("../tmp/tests/mustache/index.html".to_string());
-        f1.render(&r1);
+        f1.render(&r1, &fc1);
+    }
+
+    #[test]
+    fn test_balance_chart1() {
+        assert!(BalanceChart::new(&[], &[1.0]).is_none());
+
+        let c1 = BalanceChart::new(&[100.0, 50.0], &[0.0, -100.0]).unwrap();
+        assert_eq!("0.0,0.0 200.0,50.0", c1.points);
+        assert_eq!("200.0,50.0 400.0,100.0 600.0,200.0", c1.forecast_points);
+        assert_eq!("100.0", c1.zero_y);
+    }
+}
+
+#[cfg(test)]
+mod tests_month_mustache_file {
+    use super::MonthMustacheFile;
+    use crate::wallet::FilterResult;
+    use crate::entry::Entry;
+    use crate::number::Number;
+    use std::fs::{create_dir_all, read_to_string};
+
+    #[test]
+    fn test_month_mustache_file1() {
+        create_dir_all("../tmp/tests/mustache")
+            .expect("Cannot create mustache test directory.");
+
+        let mut r1 = FilterResult::new();
+        r1.add(Entry::from("Rent/2020-03-01/0/600"));
+        r1.add(Entry::from("Salary/2020-03-02/1000/0"));
+
+        let path = "../tmp/tests/mustache/month_03.html";
+        let f1 = MonthMustacheFile::new(path.to_string());
+        f1.render(2020, 3, &r1.years[&2020].months[&3], Some(Number::from(50.0)), false);
+
+        let html = read_to_string(path).unwrap();
+        assert!(html.contains("-550.00"));
+        assert!(html.contains("450.00"));
+        assert!(!html.contains("class=\"badge\""));
+
+        f1.render(2020, 3, &r1.years[&2020].months[&3], None, true);
+        assert!(read_to_string(path).unwrap().contains("<span class=\"badge\">closed</span>"));
     }
 }
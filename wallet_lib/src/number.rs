@@ -1,10 +1,12 @@
 
 use std::fmt::{Display, Formatter, Result as FmtRes};
 use std::ops::{Add, AddAssign};
+use serde::{Serialize, Deserialize};
 
 pub type NumberType = f64;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Number {
     n: NumberType,
 }
@@ -0,0 +1,227 @@
+//! Open Financial Exchange (OFX) bank statements.
+//!
+//! Reads both OFX 1.x (SGML, elements without end tags) and OFX 2.x (XML).
+
+use std::str::FromStr;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::import::{ImportResult, ImportError, parse_amount};
+
+/// All values of an element, in order of appearance.
+///
+/// The value ends at the next tag, so this works with and without end tags.
+fn tag_values(raw: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let mut values: Vec<String> = vec![];
+
+    let mut rest = raw;
+    while let Some(pos) = rest.find(&open) {
+        rest = &rest[(pos + open.len())..];
+
+        let end = rest.find('<').unwrap_or(rest.len());
+        values.push(decode_entities(rest[..end].trim()));
+    }
+
+    values
+}
+
+fn tag_value(raw: &str, tag: &str) -> Option<String> {
+    tag_values(raw, tag).into_iter().next()
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// OFX dates look like `YYYYMMDD[HHMMSS[.XXX]][[TZ]]`. Only the day is used.
+fn parse_date(s: &str) -> Option<Date> {
+    if s.len() < 8 || !s[..8].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let ymd = format!("{}-{}-{}", &s[..4], &s[4..6], &s[6..8]);
+    Date::from_str(&ymd).ok()
+}
+
+/// Split the file into `<STMTTRN>` aggregates.
+fn transactions(raw: &str) -> Vec<&str> {
+    let mut blocks: Vec<&str> = vec![];
+
+    let mut rest = raw;
+    while let Some(pos) = rest.find("<STMTTRN>") {
+        rest = &rest[(pos + "<STMTTRN>".len())..];
+
+        let end = rest.find("</STMTTRN>")
+            .or_else(|| rest.find("<STMTTRN>"))
+            .unwrap_or(rest.len());
+        blocks.push(&rest[..end]);
+    }
+
+    blocks
+}
+
+/// Line number of a slice of `raw`.
+fn line_of(raw: &str, block: &str) -> usize {
+    let offset = block.as_ptr() as usize - raw.as_ptr() as usize;
+    raw[..offset].matches('\n').count() + 1
+}
+
+/// Read an OFX statement. The FITID is used as entry ID, so importing
+/// the same statement twice is blocked by the wallet index.
+pub fn read_ofx(raw: &str) -> Result<ImportResult, ImportError> {
+    if !raw.contains("<OFX>") {
+        return Err(ImportError::InvalidFormat);
+    }
+
+    let mut result = ImportResult::new();
+
+    for block in transactions(raw) {
+        let fitid = match tag_value(block, "FITID") {
+            Some(fitid) if !fitid.is_empty() => fitid,
+            _ => {
+                result.skipped += 1;
+                continue;
+            },
+        };
+
+        let line = line_of(raw, block);
+        let date = tag_value(block, "DTPOSTED")
+            .and_then(|s| parse_date(&s))
+            .ok_or(ImportError::InvalidDate(line))?;
+        let amount = tag_value(block, "TRNAMT")
+            .and_then(|s| parse_amount(&s))
+            .ok_or(ImportError::InvalidAmount(line))?;
+
+        let name = tag_value(block, "NAME").unwrap_or_default();
+        let memo = tag_value(block, "MEMO").unwrap_or_default();
+
+        let mut entry = Entry::new();
+        entry.set_id(fitid);
+        entry.set_date(date);
+
+        if name.is_empty() {
+            entry.set_title(memo);
+        } else {
+            entry.set_title(name);
+            entry.set_comment(memo);
+        }
+
+        if amount >= 0.0 {
+            entry.set_revenue(amount);
+        } else {
+            entry.set_expense(amount);
+        }
+
+        result.entries.push(entry);
+    }
+
+    // Statement period over all <BANKTRANLIST> aggregates.
+    for date in tag_values(raw, "DTSTART").iter().filter_map(|s| parse_date(s)) {
+        if result.start.is_none() || Some(date) < result.start {
+            result.start = Some(date);
+        }
+    }
+    for date in tag_values(raw, "DTEND").iter().filter_map(|s| parse_date(s)) {
+        if result.end.is_none() || Some(date) > result.end {
+            result.end = Some(date);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests_read {
+    use super::read_ofx;
+    use crate::import::ImportError;
+
+    /// OFX 1.x, SGML without end tags on elements.
+    const SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>EUR
+<BANKTRANLIST>
+<DTSTART>20200101
+<DTEND>20200131120000.000[+1:CET]
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20200105120000
+<TRNAMT>-23.40
+<FITID>2020010501
+<NAME>Groceries &amp; more
+<MEMO>Card 1234
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20200102
+<TRNAMT>1000.00
+<FITID>2020010201
+<NAME>Salary
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>OTHER
+<DTPOSTED>20200103
+<TRNAMT>1.00
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+    /// OFX 2.x, XML.
+    const XML: &str = "<?xml version=\"1.0\"?>
+<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKTRANLIST>
+<DTSTART>20200201</DTSTART><DTEND>20200229</DTEND>
+<STMTTRN><TRNTYPE>DEBIT</TRNTYPE><DTPOSTED>20200210</DTPOSTED><TRNAMT>-5,50</TRNAMT><FITID>X1</FITID><MEMO>Coffee</MEMO></STMTTRN>
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>
+";
+
+    #[test]
+    fn test_read_ofx_sgml1() {
+        let r1 = read_ofx(SGML).unwrap();
+        assert_eq!(2, r1.entries.len());
+        assert_eq!(1, r1.skipped);
+        assert_eq!("2020-01-01", r1.start.unwrap().to_string());
+        assert_eq!("2020-01-31", r1.end.unwrap().to_string());
+
+        let e1 = &r1.entries[0];
+        assert_eq!("2020010501", e1.id());
+        assert_eq!("2020-01-05", e1.date().to_string());
+        assert_eq!("Groceries & more", e1.title());
+        assert_eq!("Card 1234", e1.comment());
+        assert_eq!(-23.4, e1.expense().unwrap());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("2020010201", e2.id());
+        assert_eq!(1000.0, e2.revenue().unwrap());
+    }
+
+    #[test]
+    fn test_read_ofx_xml1() {
+        let r1 = read_ofx(XML).unwrap();
+        assert_eq!(1, r1.entries.len());
+        assert_eq!("2020-02-01", r1.start.unwrap().to_string());
+        assert_eq!("2020-02-29", r1.end.unwrap().to_string());
+
+        let e1 = &r1.entries[0];
+        assert_eq!("X1", e1.id());
+        assert_eq!("Coffee", e1.title());
+        assert_eq!("", e1.comment());
+        assert_eq!(-5.5, e1.expense().unwrap());
+    }
+
+    #[test]
+    fn test_read_ofx_bad1() {
+        assert_eq!(ImportError::InvalidFormat, read_ofx("hello").unwrap_err());
+
+        let raw = "<OFX>\n<STMTTRN>\n<DTPOSTED>2020\n<FITID>1\n</STMTTRN>\n</OFX>\n";
+        assert_eq!(ImportError::InvalidDate(2), read_ofx(raw).unwrap_err());
+    }
+}
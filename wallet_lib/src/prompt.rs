@@ -0,0 +1,209 @@
+//! Interactive prompts on the terminal.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use std::str::FromStr;
+use rustyline::{Editor, Context, Helper};
+use rustyline::completion::Completer;
+use rustyline::hint::Hinter;
+use rustyline::highlight::Highlighter;
+use rustyline::validate::Validator;
+use crate::date::Date;
+
+#[derive(Debug, PartialEq)]
+pub enum PromptError {
+    /// Ctrl-C, Ctrl-D or closed input.
+    Aborted,
+}
+
+impl Display for PromptError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            PromptError::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
+
+/// Known values for the current question, for example all categories.
+struct ValueHelper {
+    values: Vec<String>,
+}
+
+impl Completer for ValueHelper {
+    type Candidate = String;
+
+    /// Tab completes the whole line.
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok((0, complete(&self.values, &line[..pos])))
+    }
+}
+
+impl Hinter for ValueHelper {
+    type Hint = String;
+
+    /// Show the rest of the first matching value. Right arrow accepts it.
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if line.is_empty() || pos < line.len() {
+            return None;
+        }
+
+        let first = complete(&self.values, line).into_iter().next()?;
+        let rest: String = first.chars().skip(line.chars().count()).collect();
+
+        if rest.is_empty() {
+            None
+        } else {
+            Some(rest)
+        }
+    }
+}
+
+impl Highlighter for ValueHelper {}
+impl Validator for ValueHelper {}
+impl Helper for ValueHelper {}
+
+/// All values starting with the given input, case-insensitive.
+pub fn complete(values: &[String], input: &str) -> Vec<String> {
+    let input = input.to_lowercase();
+
+    let mut found: Vec<String> = vec![];
+    for value in values {
+        if value.to_lowercase().starts_with(&input) && !found.contains(value) {
+            found.push(value.clone());
+        }
+    }
+
+    found
+}
+
+/// Empty input is the default answer.
+fn parse_yes_no(s: &str, default: bool) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "" => Some(default),
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a date like `wallet add -d` does. Missing parts are
+/// taken from `today`, so `15` is the 15th of the current month.
+pub fn parse_date(s: &str, today: Date) -> Option<Date> {
+    let date = Date::from_str(s.trim()).ok()?;
+
+    let y = if date.has_year() { date.year() } else { today.year() };
+    let m = if date.has_month() { date.month() } else { today.month() };
+    let d = if date.has_day() { date.day() } else { today.day() };
+
+    Date::from_str(&format!("{:04}-{:02}-{:02}", y, m, d)).ok()
+}
+
+/// Line editor with history and completion.
+pub struct Prompt {
+    editor: Editor<ValueHelper>,
+}
+
+impl Prompt {
+    pub fn new() -> Self {
+        let mut editor = Editor::new();
+        editor.set_helper(Some(ValueHelper {
+            values: vec![],
+        }));
+
+        Self {
+            editor,
+        }
+    }
+
+    /// Ask for a value. Empty input returns the default.
+    pub fn ask(&mut self, label: &str, default: &str) -> Result<String, PromptError> {
+        self.ask_complete(label, default, &[])
+    }
+
+    /// Ask for a value and complete from a list of known values.
+    pub fn ask_complete(&mut self, label: &str, default: &str, values: &[String]) -> Result<String, PromptError> {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.values = values.to_vec();
+        }
+
+        let prompt = if default.is_empty() {
+            format!("{}: ", label)
+        } else {
+            format!("{} [{}]: ", label, default)
+        };
+
+        match self.editor.readline(&prompt) {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    Ok(default.to_string())
+                } else {
+                    self.editor.add_history_entry(line);
+                    Ok(line.to_string())
+                }
+            },
+            Err(_) => Err(PromptError::Aborted),
+        }
+    }
+
+    /// Ask a yes/no question until the answer is valid.
+    pub fn confirm(&mut self, label: &str, default: bool) -> Result<bool, PromptError> {
+        let label = format!("{} ({})", label, if default { "Y/n" } else { "y/N" });
+
+        loop {
+            let answer = self.ask(&label, "")?;
+            match parse_yes_no(&answer, default) {
+                Some(yes) => return Ok(yes),
+                None => println!("Please answer y or n."),
+            }
+        }
+    }
+}
+
+impl Default for Prompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests_complete {
+    use super::complete;
+
+    #[test]
+    fn test_complete1() {
+        let values = vec!["food".to_string(), "Fuel".to_string(), "rent".to_string()];
+
+        assert_eq!(vec!["food", "Fuel"], complete(&values, "f"));
+        assert_eq!(vec!["Fuel"], complete(&values, "FU"));
+        assert_eq!(3, complete(&values, "").len());
+        assert!(complete(&values, "x").is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use std::str::FromStr;
+    use super::{parse_yes_no, parse_date};
+    use crate::date::Date;
+
+    #[test]
+    fn test_parse_yes_no1() {
+        assert_eq!(Some(true), parse_yes_no("", true));
+        assert_eq!(Some(false), parse_yes_no(" ", false));
+        assert_eq!(Some(true), parse_yes_no("Y", false));
+        assert_eq!(Some(false), parse_yes_no("no", true));
+        assert_eq!(None, parse_yes_no("maybe", true));
+    }
+
+    #[test]
+    fn test_parse_date1() {
+        let today = Date::from_str("2020-03-21").unwrap();
+
+        assert_eq!("2020-03-21", parse_date("2020-03-21", today).unwrap().to_string());
+        assert_eq!("2020-03-15", parse_date("15", today).unwrap().to_string());
+        assert_eq!("2020-01-05", parse_date("01-05", today).unwrap().to_string());
+        assert_eq!("2019-02-21", parse_date("2019-02", today).unwrap().to_string());
+        assert!(parse_date("2019-02-30", today).is_none());
+        assert!(parse_date("x", today).is_none());
+    }
+}
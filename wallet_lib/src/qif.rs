@@ -0,0 +1,277 @@
+//! Quicken Interchange Format (QIF) bank statements.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+use regex::Regex;
+use chrono::NaiveDate;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::import::{ImportResult, ImportError, parse_amount};
+
+/// A record between two `^` lines.
+struct QifRecord {
+    line: usize,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: String,
+    memo: String,
+    category: String,
+    number: String,
+}
+
+impl QifRecord {
+    fn new() -> Self {
+        Self {
+            line: 0,
+            date: None,
+            amount: None,
+            payee: String::new(),
+            memo: String::new(),
+            category: String::new(),
+            number: String::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.date.is_none() && self.amount.is_none()
+    }
+
+    /// QIF has no transaction IDs. The key is built from all fields,
+    /// so the same record always gets the same entry ID.
+    fn key(&self) -> String {
+        format!("qif|{}|{}|{}|{}|{}",
+            self.date.as_deref().unwrap_or(""),
+            self.amount.as_deref().unwrap_or(""),
+            self.payee,
+            self.memo,
+            self.number)
+    }
+
+    fn to_entry(&self, occurrence: usize) -> Result<Entry, ImportError> {
+        let date = self.date.as_deref()
+            .and_then(parse_date)
+            .ok_or(ImportError::InvalidDate(self.line))?;
+        let amount = self.amount.as_deref()
+            .and_then(parse_amount)
+            .ok_or(ImportError::InvalidAmount(self.line))?;
+
+        // Identical records on the same day, e.g. two coffees.
+        let key = format!("{}|{}", self.key(), occurrence);
+        let id = Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes());
+
+        let mut entry = Entry::new();
+        entry.set_id(id.to_string());
+        entry.set_date(date);
+
+        if self.payee.is_empty() {
+            entry.set_title(self.memo.clone());
+        } else {
+            entry.set_title(self.payee.clone());
+            entry.set_comment(self.memo.clone());
+        }
+
+        // `[Account]` is a transfer, not a category.
+        if !self.category.is_empty() && !self.category.starts_with('[') {
+            entry.set_category(self.category.clone());
+        }
+
+        if amount >= 0.0 {
+            entry.set_revenue(amount);
+        } else {
+            entry.set_expense(amount);
+        }
+
+        Ok(entry)
+    }
+}
+
+/// QIF dates come as `MM/DD/YYYY`, `M/D'YY`, `DD.MM.YYYY` or `YYYY-MM-DD`.
+///
+/// `Date::from_str` would read `01/05/20` as `YY/MM/DD`,
+/// so the US format is handled here.
+fn parse_date(s: &str) -> Option<Date> {
+    let s = s.replace(' ', "").replace('\'', "/");
+
+    let re = Regex::new(r"^(?P<m>\d{1,2})/(?P<d>\d{1,2})/(?P<y>\d{2,4})$").unwrap();
+    let date = if let Some(captures) = re.captures(&s) {
+        let mut y: i32 = captures["y"].parse().ok()?;
+        let m: u32 = captures["m"].parse().ok()?;
+        let d: u32 = captures["d"].parse().ok()?;
+        if y < 100 {
+            y += 2000;
+        }
+
+        // Date setters panic on invalid values.
+        NaiveDate::from_ymd_opt(y, m, d)?;
+
+        let mut date = Date::new();
+        date.set_year(y);
+        date.set_month(m);
+        date.set_day(d);
+        date
+    } else {
+        Date::from_str(&s).ok()?
+    };
+
+    if date.has_year() && date.has_month() && date.has_day() {
+        Some(date)
+    } else {
+        None
+    }
+}
+
+/// Read a QIF file. Only bank and cash transactions are read;
+/// investment and memorized records are skipped.
+pub fn read_qif(raw: &str) -> Result<ImportResult, ImportError> {
+    let mut result = ImportResult::new();
+    let mut records: Vec<QifRecord> = vec![];
+    let mut current = QifRecord::new();
+    let mut section = String::new();
+
+    // Exported with a byte order mark by some banks.
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+
+    for (index, line) in raw.lines().enumerate() {
+        let line = line.trim_end();
+        let mut chars = line.chars();
+        let code = match chars.next() {
+            Some(code) => code,
+            None => continue,
+        };
+        let value = chars.as_str();
+
+        if current.line == 0 && code != '!' && code != '^' {
+            current.line = index + 1;
+        }
+
+        match code {
+            '!' => section = value.to_lowercase(),
+            '^' => {
+                let record = std::mem::replace(&mut current, QifRecord::new());
+                if record.is_empty() {
+                    continue;
+                }
+                if section.starts_with("type:bank") || section.starts_with("type:cash") || section.starts_with("type:ccard") {
+                    records.push(record);
+                } else {
+                    result.skipped += 1;
+                }
+            },
+            'D' => current.date = Some(value.to_string()),
+            'T' | 'U' => current.amount = Some(value.to_string()),
+            'P' => current.payee = value.to_string(),
+            'M' => current.memo = value.to_string(),
+            'L' => current.category = value.to_string(),
+            'N' => current.number = value.to_string(),
+            _ => (),
+        }
+    }
+
+    if section.is_empty() && !records.is_empty() {
+        return Err(ImportError::InvalidFormat);
+    }
+
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        let occurrence = occurrences.entry(record.key()).or_insert(0);
+        *occurrence += 1;
+
+        result.entries.push(record.to_entry(*occurrence)?);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests_read {
+    use super::read_qif;
+    use crate::import::ImportError;
+
+    const QIF: &str = "!Type:Bank
+D01/05/2020
+T-23.40
+PGroceries
+MWeekly
+LFood:Groceries
+^
+D1/ 2'20
+T1,000.00
+PSalary
+LWork
+^
+D01/06/2020
+T-3.00
+PCoffee
+^
+D01/06/2020
+T-3.00
+PCoffee
+^
+D01/07/2020
+T-100.00
+PSavings
+L[Savings Account]
+^
+";
+
+    #[test]
+    fn test_read_qif1() {
+        let r1 = read_qif(QIF).unwrap();
+        assert_eq!(5, r1.entries.len());
+        assert_eq!(0, r1.skipped);
+
+        let e1 = &r1.entries[0];
+        assert_eq!("2020-01-05", e1.date().to_string());
+        assert_eq!("Groceries", e1.title());
+        assert_eq!("Weekly", e1.comment());
+        assert_eq!("Food:Groceries", e1.category());
+        assert_eq!(-23.4, e1.expense().unwrap());
+
+        let e2 = &r1.entries[1];
+        assert_eq!("2020-01-02", e2.date().to_string());
+        assert_eq!(1000.0, e2.revenue().unwrap());
+
+        // Same content, different IDs.
+        assert_ne!(r1.entries[2].id(), r1.entries[3].id());
+
+        // Transfers keep the default category.
+        assert_eq!("default", r1.entries[4].category());
+    }
+
+    /// The same file always produces the same IDs.
+    #[test]
+    fn test_read_qif_stable_ids1() {
+        let r1 = read_qif(QIF).unwrap();
+        let r2 = read_qif(QIF).unwrap();
+
+        let ids1: Vec<String> = r1.entries.iter().map(|e| e.id()).collect();
+        let ids2: Vec<String> = r2.entries.iter().map(|e| e.id()).collect();
+        assert_eq!(ids1, ids2);
+    }
+
+    #[test]
+    fn test_read_qif_skip1() {
+        let raw = "!Type:Invst\nD01/05/2020\nT-23.40\n^\n";
+        let r1 = read_qif(raw).unwrap();
+        assert_eq!(0, r1.entries.len());
+        assert_eq!(1, r1.skipped);
+    }
+
+    #[test]
+    fn test_read_qif_bom1() {
+        let raw = "\u{feff}!Type:Bank\nD01/05/2020\nT-23.40\nPCafé\n€ unknown\n^\n";
+        let r1 = read_qif(raw).unwrap();
+        assert_eq!(1, r1.entries.len());
+        assert_eq!("Café", r1.entries[0].title());
+    }
+
+    #[test]
+    fn test_read_qif_bad1() {
+        let raw = "!Type:Bank\nDxx\nT-23.40\n^\n";
+        assert_eq!(ImportError::InvalidDate(2), read_qif(raw).unwrap_err());
+
+        let raw = "!Type:Bank\nD02/30/2020\nT-23.40\n^\n";
+        assert_eq!(ImportError::InvalidDate(2), read_qif(raw).unwrap_err());
+    }
+}
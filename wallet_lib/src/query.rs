@@ -0,0 +1,713 @@
+//! Query language for filters.
+//!
+//! ```text
+//! category:food and (amount>50 or epic:trip) and not title~"refund"
+//! ```
+//!
+//! Terms are `field op value`. Terms are combined with `and`, `or`,
+//! `not` and parentheses. `not` binds stronger than `and`, `and`
+//! stronger than `or`. A word without a field searches in title and
+//! comment.
+//!
+//! | Field                                | Operators                      |
+//! |--------------------------------------|--------------------------------|
+//! | `title`, `comment`, `text`           | `:` `~` (contains), `=` `!=`   |
+//! | `category`, `epic`, `id`             | `:` `=` `!=`, `~` (contains)   |
+//! | `date`                               | `:` `=` `!=` `>` `>=` `<` `<=` |
+//! | `amount`, `revenue`, `expense`, `balance` | `:` `=` `!=` `>` `>=` `<` `<=` |
+//!
+//! Text compares are case-insensitive, except for the `-c` and `-x`
+//! options, which compare exactly. A value in slashes, like
+//! `title~/^rent/`, is a regular expression. `amount` is the absolute
+//! balance, `expense` is positive.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use std::str::FromStr;
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::number::NumberType;
+use crate::search::Search;
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnknownField(String),
+    InvalidOperator(String),
+    InvalidValue(String),
+    UnclosedQuote,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            QueryError::UnexpectedEnd => write!(f, "Unexpected end of query"),
+            QueryError::UnexpectedToken(token) => write!(f, "Unexpected '{}'", token),
+            QueryError::UnknownField(field) => write!(f, "Unknown field '{}'", field),
+            QueryError::InvalidOperator(op) => write!(f, "Invalid operator '{}'", op),
+            QueryError::InvalidValue(value) => write!(f, "Invalid value '{}'", value),
+            QueryError::UnclosedQuote => write!(f, "Unclosed quote"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Comment,
+    /// Title or comment.
+    Text,
+    Category,
+    Epic,
+    Id,
+    Date,
+    /// Absolute balance.
+    Amount,
+    Revenue,
+    /// Absolute expense.
+    Expense,
+    Balance,
+}
+
+impl Field {
+    fn is_number(self) -> bool {
+        matches!(self, Field::Amount | Field::Revenue | Field::Expense | Field::Balance)
+    }
+
+    /// Texts compared by string operators.
+    fn texts(self, entry: &Entry) -> Vec<String> {
+        match self {
+            Field::Title => vec![entry.title()],
+            Field::Comment => vec![entry.comment()],
+            Field::Text => vec![entry.title(), entry.comment()],
+            Field::Category => vec![entry.category()],
+            Field::Epic => vec![entry.epic()],
+            Field::Id => vec![entry.id()],
+            _ => vec![],
+        }
+    }
+
+    pub fn number(self, entry: &Entry) -> NumberType {
+        match self {
+            Field::Amount => entry.balance().unwrap().abs(),
+            Field::Revenue => entry.revenue().unwrap(),
+            Field::Expense => entry.expense().unwrap().abs(),
+            Field::Balance => entry.balance().unwrap(),
+            _ => 0.0,
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "title" => Ok(Field::Title),
+            "comment" => Ok(Field::Comment),
+            "text" => Ok(Field::Text),
+            "category" => Ok(Field::Category),
+            "epic" => Ok(Field::Epic),
+            "id" => Ok(Field::Id),
+            "date" => Ok(Field::Date),
+            "amount" => Ok(Field::Amount),
+            "revenue" => Ok(Field::Revenue),
+            "expense" => Ok(Field::Expense),
+            "balance" => Ok(Field::Balance),
+            _ => Err(QueryError::UnknownField(s.to_string())),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        let name = match self {
+            Field::Title => "title",
+            Field::Comment => "comment",
+            Field::Text => "text",
+            Field::Category => "category",
+            Field::Epic => "epic",
+            Field::Id => "id",
+            Field::Date => "date",
+            Field::Amount => "amount",
+            Field::Revenue => "revenue",
+            Field::Expense => "expense",
+            Field::Balance => "balance",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        let op = match self {
+            Op::Eq => ":",
+            Op::Ne => "!=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Contains => "~",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Text(String),
+    /// Compared case-sensitive, for the `-c` and `-x` options.
+    Exact(String),
+    Search(Search),
+    Date(Date),
+    Number(NumberType),
+}
+
+/// Quote a text when it would not be read back as one word.
+fn quote(s: &str) -> String {
+    if s.is_empty() || s.chars().any(|c| c.is_whitespace() || c == '(' || c == ')' || c == '"' || c == '/') {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        s.to_string()
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            Value::Text(text) | Value::Exact(text) => write!(f, "{}", quote(text)),
+            Value::Search(search) if search.is_regex() => write!(f, "/{}/", search.query()),
+            Value::Search(search) => write!(f, "{}", quote(&search.query())),
+            Value::Date(date) => write!(f, "{}", date.ymd()),
+            Value::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Like `wallet list -d`: a day, a month or a year.
+fn date_matches(date: Date, edate: Date) -> bool {
+    if date.has_day() {
+        date == edate
+    } else if date.has_month() {
+        date.year() == edate.year() && date.month() == edate.month()
+    } else if date.has_year() {
+        date.year() == edate.year()
+    } else {
+        true
+    }
+}
+
+/// Year, month and day of `edate`, cut to the parts used in `date`.
+/// So `date>2020-03` means after March.
+fn date_keys(date: Date, edate: Date) -> ((i32, u32, u32), (i32, u32, u32)) {
+    let month = |d: Date| if date.has_month() { d.month() } else { 0 };
+    let day = |d: Date| if date.has_day() { d.day() } else { 0 };
+
+    ((date.year(), month(date), day(date)), (edate.year(), month(edate), day(edate)))
+}
+
+/// One `field op value` term.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub field: Field,
+    pub op: Op,
+    pub value: Value,
+}
+
+impl Condition {
+    pub fn new(field: Field, op: Op, value: Value) -> Self {
+        Self {
+            field,
+            op,
+            value,
+        }
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match (&self.value, self.op) {
+            (Value::Search(search), _) => {
+                self.field.texts(entry).iter().any(|text| search.is_match_text(text))
+            },
+            (Value::Text(text), Op::Eq) | (Value::Text(text), Op::Ne) => {
+                let text = text.to_lowercase();
+                let eq = self.field.texts(entry).iter().any(|t| t.to_lowercase() == text);
+                eq == (self.op == Op::Eq)
+            },
+            (Value::Text(text), _) => {
+                let text = text.to_lowercase();
+                self.field.texts(entry).iter().any(|t| t.to_lowercase().contains(&text))
+            },
+            (Value::Exact(text), Op::Eq) | (Value::Exact(text), Op::Ne) => {
+                let eq = self.field.texts(entry).iter().any(|t| t == text);
+                eq == (self.op == Op::Eq)
+            },
+            (Value::Exact(text), _) => {
+                self.field.texts(entry).iter().any(|t| t.contains(text.as_str()))
+            },
+            (Value::Date(date), op) => {
+                let edate = entry.date();
+                let (date_key, edate_key) = date_keys(*date, edate);
+                match op {
+                    Op::Eq => date_matches(*date, edate),
+                    Op::Ne => !date_matches(*date, edate),
+                    Op::Gt => edate_key > date_key,
+                    Op::Ge => edate_key >= date_key,
+                    Op::Lt => edate_key < date_key,
+                    Op::Le => edate_key <= date_key,
+                    Op::Contains => false,
+                }
+            },
+            (Value::Number(n), op) => {
+                let v = self.field.number(entry);
+                match op {
+                    Op::Eq => (v - n).abs() < 0.005,
+                    Op::Ne => (v - n).abs() >= 0.005,
+                    Op::Gt => v > *n,
+                    Op::Ge => v >= *n,
+                    Op::Lt => v < *n,
+                    Op::Le => v <= *n,
+                    Op::Contains => false,
+                }
+            },
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        write!(f, "{}{}{}", self.field, self.op, self.value)
+    }
+}
+
+/// Expression tree.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Condition),
+}
+
+impl Expr {
+    pub fn term(field: Field, op: Op, value: Value) -> Self {
+        Expr::Term(Condition::new(field, op, value))
+    }
+
+    pub fn and(self, other: Expr) -> Self {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Self {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn negate(self) -> Self {
+        Expr::Not(Box::new(self))
+    }
+
+    /// Combine all expressions with `and`. None for an empty list.
+    pub fn all(exprs: Vec<Expr>) -> Option<Self> {
+        exprs.into_iter().fold(None, |acc, expr| match acc {
+            Some(acc) => Some(Expr::and(acc, expr)),
+            None => Some(expr),
+        })
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(entry) && b.matches(entry),
+            Expr::Or(a, b) => a.matches(entry) || b.matches(entry),
+            Expr::Not(a) => !a.matches(entry),
+            Expr::Term(condition) => condition.matches(entry),
+        }
+    }
+}
+
+/// Binary nodes are always put in parentheses.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            Expr::And(a, b) => write!(f, "({} and {})", a, b),
+            Expr::Or(a, b) => write!(f, "({} or {})", a, b),
+            Expr::Not(a) => write!(f, "not {}", a),
+            Expr::Term(condition) => write!(f, "{}", condition),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+        };
+
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+// Lexer
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// Field, operator and value. The value is a regex when `regex` is set.
+    Term(String, String, String, bool),
+    /// Word without a field.
+    Word(String, bool),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::And => write!(f, "and"),
+            Token::Or => write!(f, "or"),
+            Token::Not => write!(f, "not"),
+            Token::Term(field, op, value, _) => write!(f, "{}{}{}", field, op, value),
+            Token::Word(word, _) => write!(f, "{}", word),
+        }
+    }
+}
+
+const OPERATORS: [&str; 8] = [">=", "<=", "!=", ":", "~", "=", ">", "<"];
+
+/// Read a value: `"quoted"`, `/regex/` or a plain word.
+/// Returns the value, whether it is a regex and the new position.
+fn read_value(chars: &[char], mut pos: usize) -> Result<(String, bool, usize), QueryError> {
+    let mut value = String::new();
+
+    match chars.get(pos) {
+        Some(&quote) if quote == '"' || quote == '/' => {
+            pos += 1;
+            loop {
+                match chars.get(pos) {
+                    None => return Err(QueryError::UnclosedQuote),
+                    Some('\\') if quote == '"' && pos + 1 < chars.len() => {
+                        value.push(chars[pos + 1]);
+                        pos += 2;
+                    },
+                    Some(&c) if c == quote => {
+                        pos += 1;
+                        break;
+                    },
+                    Some(&c) => {
+                        value.push(c);
+                        pos += 1;
+                    },
+                }
+            }
+            Ok((value, quote == '/', pos))
+        },
+        _ => {
+            while let Some(&c) = chars.get(pos) {
+                if c.is_whitespace() || c == '(' || c == ')' {
+                    break;
+                }
+                value.push(c);
+                pos += 1;
+            }
+            Ok((value, false, pos))
+        },
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if c == '(' {
+            tokens.push(Token::LParen);
+            pos += 1;
+            continue;
+        }
+        if c == ')' {
+            tokens.push(Token::RParen);
+            pos += 1;
+            continue;
+        }
+
+        // Field name
+        let start = pos;
+        while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+            pos += 1;
+        }
+        let name: String = chars[start..pos].iter().collect();
+
+        // Operator
+        let rest: String = chars[pos..].iter().take(2).collect();
+        let op = if name.is_empty() {
+            None
+        } else {
+            OPERATORS.iter().find(|op| rest.starts_with(*op))
+        };
+
+        if let Some(op) = op {
+            pos += op.len();
+            let (value, regex, end) = read_value(&chars, pos)?;
+            pos = end;
+            tokens.push(Token::Term(name, op.to_string(), value, regex));
+            continue;
+        }
+
+        match name.to_lowercase().as_str() {
+            "and" => tokens.push(Token::And),
+            "or" => tokens.push(Token::Or),
+            "not" => tokens.push(Token::Not),
+            _ => {
+                // A word or a quoted text without a field.
+                let (value, regex, end) = read_value(&chars, start)?;
+                pos = end;
+                tokens.push(Token::Word(value, regex));
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Parser
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = expr.or(self.parse_and()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = expr.and(self.parse_not()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, QueryError> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(self.parse_not()?.negate());
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, QueryError> {
+        match self.next() {
+            None => Err(QueryError::UnexpectedEnd),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+                    None => Err(QueryError::UnexpectedEnd),
+                }
+            },
+            Some(Token::Term(field, op, value, regex)) => parse_term(&field, &op, &value, regex),
+            Some(Token::Word(value, regex)) => parse_term("text", "~", &value, regex),
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+fn parse_term(field: &str, op: &str, value: &str, regex: bool) -> Result<Expr, QueryError> {
+    let field = Field::from_str(field)?;
+    let invalid_op = || QueryError::InvalidOperator(op.to_string());
+
+    match field {
+        Field::Title | Field::Comment | Field::Text | Field::Category | Field::Epic | Field::Id => {
+            let contains_default = matches!(field, Field::Title | Field::Comment | Field::Text);
+            let op = match op {
+                ":" if contains_default => Op::Contains,
+                ":" | "=" => Op::Eq,
+                "!=" => Op::Ne,
+                "~" => Op::Contains,
+                _ => return Err(invalid_op()),
+            };
+
+            let value = if regex {
+                let search = Search::regex(value).map_err(|_| QueryError::InvalidValue(value.to_string()))?;
+                Value::Search(search)
+            } else if op == Op::Contains {
+                Value::Search(Search::new(value))
+            } else {
+                Value::Text(value.to_string())
+            };
+
+            let expr = match (op, &value) {
+                // A regex never tests for equality.
+                (Op::Ne, Value::Search(_)) => Expr::term(field, Op::Contains, value).negate(),
+                (_, Value::Search(_)) => Expr::term(field, Op::Contains, value),
+                _ => Expr::term(field, op, value),
+            };
+            Ok(expr)
+        },
+        _ => {
+            let op = match op {
+                ":" | "=" => Op::Eq,
+                "!=" => Op::Ne,
+                ">" => Op::Gt,
+                ">=" => Op::Ge,
+                "<" => Op::Lt,
+                "<=" => Op::Le,
+                _ => return Err(invalid_op()),
+            };
+
+            let invalid_value = || QueryError::InvalidValue(value.to_string());
+            let value = if field.is_number() {
+                Value::Number(value.replace(',', ".").parse().map_err(|_| invalid_value())?)
+            } else {
+                Value::Date(Date::from_str(value).map_err(|_| invalid_value())?)
+            };
+
+            Ok(Expr::term(field, op, value))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use std::str::FromStr;
+    use super::{Expr, QueryError};
+
+    fn parse(s: &str) -> String {
+        Expr::from_str(s).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse1() {
+        assert_eq!("category:food", parse("category:food"));
+        assert_eq!("title~refund", parse("title:refund"));
+        assert_eq!("(category:food and amount>50)", parse("category:food and amount>50"));
+        assert_eq!("(category:food or (amount>50 and epic:trip))", parse("category:food or amount>50 and epic:trip"));
+        assert_eq!("not not epic:trip", parse("not not epic:trip"));
+        assert_eq!("text~coffee", parse("coffee"));
+        assert_eq!("title~/^rent/", parse("title~/^rent/"));
+        assert_eq!("date>=2020-03", parse("date>=2020-03"));
+        assert_eq!("expense<=12.5", parse("expense<=12,5"));
+    }
+
+    #[test]
+    fn test_parse_example1() {
+        let s1 = r#"category:food and (amount>50 or epic:trip) and not title~"refund me""#;
+        assert_eq!(r#"((category:food and (amount>50 or epic:trip)) and not title~"refund me")"#, parse(s1));
+
+        // Display output is read back to the same tree.
+        assert_eq!(parse(s1), parse(&parse(s1)));
+    }
+
+    #[test]
+    fn test_parse_errors1() {
+        assert_eq!(QueryError::UnexpectedEnd, Expr::from_str("").unwrap_err());
+        assert_eq!(QueryError::UnexpectedEnd, Expr::from_str("(epic:x").unwrap_err());
+        assert_eq!(QueryError::UnexpectedEnd, Expr::from_str("epic:x and").unwrap_err());
+        assert_eq!(QueryError::UnexpectedToken(")".to_string()), Expr::from_str("epic:x)").unwrap_err());
+        assert_eq!(QueryError::UnknownField("color".to_string()), Expr::from_str("color:red").unwrap_err());
+        assert_eq!(QueryError::InvalidOperator(">".to_string()), Expr::from_str("epic>x").unwrap_err());
+        assert_eq!(QueryError::InvalidValue("x".to_string()), Expr::from_str("amount>x").unwrap_err());
+        assert_eq!(QueryError::UnclosedQuote, Expr::from_str("title:\"x").unwrap_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_matches {
+    use std::str::FromStr;
+    use super::{Expr, Field, Op, Value};
+    use crate::entry::Entry;
+
+    fn entry(s: &str, category: &str, epic: &str) -> Entry {
+        let mut entry = Entry::from(s);
+        entry.set_category(category.to_string());
+        entry.set_epic(epic.to_string());
+        entry
+    }
+
+    fn matches(query: &str, entry: &Entry) -> bool {
+        Expr::from_str(query).unwrap().matches(entry)
+    }
+
+    #[test]
+    fn test_matches1() {
+        let e1 = entry("Groceries/2020-03-05/0/60", "food", "default");
+        let e2 = entry("Pizza refund/2020-03-06/10/0", "food", "trip");
+        let e3 = entry("Hotel/2020-04-01/0/200", "travel", "trip");
+
+        let query = r#"category:food and (amount>50 or epic:trip) and not title~"refund""#;
+        assert!(matches(query, &e1));
+        assert!(!matches(query, &e2));
+        assert!(!matches(query, &e3));
+
+        assert!(matches("category:FOOD", &e1));
+        assert!(matches("category!=travel", &e1));
+        assert!(matches("category~oo", &e1));
+        assert!(matches("date:2020-03", &e2));
+        assert!(!matches("date:2020-03", &e3));
+        assert!(matches("date>2020-03-31", &e3));
+        assert!(matches("date>2020-03", &e3));
+        assert!(!matches("date>2020-03", &e2));
+        assert!(matches("date<=2020-03", &e2));
+        assert!(matches("expense>=200 and balance<0", &e3));
+        assert!(matches("revenue=10", &e2));
+        assert!(matches("refund", &e2));
+        assert!(matches("title!=/^hot/", &e1));
+        assert!(!matches("title!=/^hot/", &e3));
+    }
+
+    #[test]
+    fn test_matches_exact1() {
+        let e1 = entry("Groceries/2020-03-05/0/60", "Food", "default");
+
+        assert!(Expr::term(Field::Category, Op::Eq, Value::Exact("Food".to_string())).matches(&e1));
+        assert!(!Expr::term(Field::Category, Op::Eq, Value::Exact("food".to_string())).matches(&e1));
+        assert!(Expr::term(Field::Category, Op::Ne, Value::Exact("food".to_string())).matches(&e1));
+    }
+}
@@ -0,0 +1,155 @@
+//! Full-text search on titles and comments.
+
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use regex::{Regex, RegexBuilder};
+use crate::entry::Entry;
+
+#[derive(Debug, PartialEq)]
+pub enum SearchError {
+    InvalidRegex(String),
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            SearchError::InvalidRegex(error) => write!(f, "Invalid regex: {}", error),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SearchKind {
+    /// Lowercase needle.
+    Substring(String),
+    Regex(Regex),
+}
+
+/// Case-insensitive search on `title` and `comment` of an Entry.
+#[derive(Debug, Clone)]
+pub struct Search {
+    query: String,
+    kind: SearchKind,
+}
+
+impl Search {
+    /// Substring search.
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            kind: SearchKind::Substring(query.to_lowercase()),
+        }
+    }
+
+    /// Regex search.
+    pub fn regex(pattern: &str) -> Result<Self, SearchError> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|error| SearchError::InvalidRegex(error.to_string()))?;
+
+        Ok(Self {
+            query: pattern.to_string(),
+            kind: SearchKind::Regex(regex),
+        })
+    }
+
+    pub fn query(&self) -> String {
+        self.query.clone()
+    }
+
+    pub fn is_regex(&self) -> bool {
+        matches!(self.kind, SearchKind::Regex(_))
+    }
+
+    /// Number of matches in a text.
+    fn count(&self, text: &str) -> usize {
+        match &self.kind {
+            SearchKind::Substring(needle) if needle.is_empty() => 0,
+            SearchKind::Substring(needle) => text.to_lowercase().matches(needle.as_str()).count(),
+            SearchKind::Regex(regex) => regex.find_iter(text).count(),
+        }
+    }
+
+    /// Match a single text. An empty query matches everything.
+    pub fn is_match_text(&self, text: &str) -> bool {
+        match &self.kind {
+            SearchKind::Substring(needle) if needle.is_empty() => true,
+            _ => self.count(text) > 0,
+        }
+    }
+
+    pub fn is_match(&self, entry: &Entry) -> bool {
+        self.is_match_text(&entry.title()) || self.is_match_text(&entry.comment())
+    }
+
+    /// Relevance of a hit. A match in the title counts more than one in
+    /// the comment, a title equal to or starting with the query even more.
+    /// Zero means no match.
+    pub fn score(&self, entry: &Entry) -> usize {
+        let title = entry.title();
+        let title_count = self.count(&title);
+        let comment_count = self.count(&entry.comment());
+
+        let mut score = title_count * 10 + comment_count * 3;
+
+        if let SearchKind::Substring(needle) = &self.kind {
+            let title = title.to_lowercase();
+            if title_count > 0 && &title == needle {
+                score += 50;
+            } else if title_count > 0 && title.starts_with(needle.as_str()) {
+                score += 20;
+            }
+        }
+
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests_search {
+    use super::{Search, SearchError};
+    use crate::entry::Entry;
+
+    fn entry(title: &str, comment: &str) -> Entry {
+        let mut entry = Entry::new();
+        entry.set_title(title.to_string());
+        entry.set_comment(comment.to_string());
+        entry
+    }
+
+    #[test]
+    fn test_search_substring1() {
+        let s1 = Search::new("REFUND");
+
+        assert!(s1.is_match(&entry("Refund shoes", "")));
+        assert!(s1.is_match(&entry("Shoes", "partial refund")));
+        assert!(!s1.is_match(&entry("Shoes", "")));
+        assert!(Search::new("").is_match(&entry("Shoes", "")));
+    }
+
+    #[test]
+    fn test_search_regex1() {
+        let s1 = Search::regex(r"^(rent|lease)\b").unwrap();
+
+        assert!(s1.is_match(&entry("Rent January", "")));
+        assert!(!s1.is_match(&entry("Parent", "")));
+
+        assert!(matches!(Search::regex("(").unwrap_err(), SearchError::InvalidRegex(_)));
+    }
+
+    #[test]
+    fn test_search_score1() {
+        let s1 = Search::new("coffee");
+
+        let exact = s1.score(&entry("Coffee", ""));
+        let prefix = s1.score(&entry("Coffee beans", ""));
+        let title = s1.score(&entry("Iced coffee", ""));
+        let comment = s1.score(&entry("Breakfast", "with coffee"));
+
+        assert!(exact > prefix);
+        assert!(prefix > title);
+        assert!(title > comment);
+        assert!(comment > 0);
+        assert_eq!(0, s1.score(&entry("Tea", "")));
+    }
+}
@@ -0,0 +1,185 @@
+//! Sorting and grouping of entries.
+
+use std::str::FromStr;
+use std::fmt::{Display, Formatter, Result as FmtRes};
+use std::cmp::Ordering;
+use crate::entry::Entry;
+
+#[derive(Debug, PartialEq)]
+pub enum SortError {
+    InvalidSort,
+    InvalidGroup,
+}
+
+impl Display for SortError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            SortError::InvalidSort => write!(f, "Invalid sort, use date, amount, category or title"),
+            SortError::InvalidGroup => write!(f, "Invalid group, use day, week, month, category or epic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    Date,
+    /// Absolute balance.
+    Amount,
+    Category,
+    Title,
+}
+
+impl FromStr for SortBy {
+    type Err = SortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "date" => Ok(SortBy::Date),
+            "amount" => Ok(SortBy::Amount),
+            "category" => Ok(SortBy::Category),
+            "title" => Ok(SortBy::Title),
+            _ => Err(SortError::InvalidSort),
+        }
+    }
+}
+
+impl SortBy {
+    /// Entries with the same value are ordered by date.
+    pub fn cmp(self, a: &Entry, b: &Entry) -> Ordering {
+        let ordering = match self {
+            SortBy::Date => Ordering::Equal,
+            SortBy::Amount => {
+                let a = a.balance().unwrap().abs();
+                let b = b.balance().unwrap().abs();
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            },
+            SortBy::Category => a.category().to_lowercase().cmp(&b.category().to_lowercase()),
+            SortBy::Title => a.title().to_lowercase().cmp(&b.title().to_lowercase()),
+        };
+
+        ordering.then_with(|| a.date().partial_cmp(&b.date()).unwrap_or(Ordering::Equal))
+    }
+}
+
+/// Stable sort. Equal entries keep the order of the month files.
+pub fn sort(entries: &mut [Entry], sort_by: SortBy, reverse: bool) {
+    entries.sort_by(|a, b| sort_by.cmp(a, b));
+
+    if reverse {
+        entries.reverse();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+    Category,
+    Epic,
+}
+
+impl FromStr for GroupBy {
+    type Err = SortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(GroupBy::Day),
+            "week" => Ok(GroupBy::Week),
+            "month" => Ok(GroupBy::Month),
+            "category" => Ok(GroupBy::Category),
+            "epic" => Ok(GroupBy::Epic),
+            _ => Err(SortError::InvalidGroup),
+        }
+    }
+}
+
+impl GroupBy {
+    /// Name of the group an Entry belongs to.
+    pub fn key(self, entry: &Entry) -> String {
+        match self {
+            GroupBy::Day => entry.date().ymd(),
+            GroupBy::Week => entry.date().yw(),
+            GroupBy::Month => entry.date().fym("-"),
+            GroupBy::Category => entry.category(),
+            GroupBy::Epic => entry.epic(),
+        }
+    }
+}
+
+/// Groups with the indices of their Entries, ordered by name, descending
+/// with `reverse`. Entries keep their order within a group.
+pub fn group_indices(entries: &[Entry], group_by: GroupBy, reverse: bool) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = vec![];
+
+    for (i, entry) in entries.iter().enumerate() {
+        let key = group_by.key(entry);
+
+        match groups.iter_mut().find(|(name, _)| name == &key) {
+            Some((_, group)) => group.push(i),
+            None => groups.push((key, vec![i])),
+        }
+    }
+
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    if reverse {
+        groups.reverse();
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests_sort {
+    use std::str::FromStr;
+    use super::{sort, group_indices, SortBy, GroupBy, SortError};
+    use crate::entry::Entry;
+
+    fn titles(entries: &[Entry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.title()).collect()
+    }
+
+    #[test]
+    fn test_sort1() {
+        let mut entries = vec![
+            Entry::from("b/2020-01-03/0/5"),
+            Entry::from("c/2020-01-01/0/50"),
+            Entry::from("a/2020-01-02/20/0"),
+        ];
+
+        sort(&mut entries, SortBy::Date, false);
+        assert_eq!(vec!["c", "a", "b"], titles(&entries));
+
+        sort(&mut entries, SortBy::Amount, false);
+        assert_eq!(vec!["b", "a", "c"], titles(&entries));
+
+        sort(&mut entries, SortBy::Title, true);
+        assert_eq!(vec!["c", "b", "a"], titles(&entries));
+    }
+
+    #[test]
+    fn test_group1() {
+        let entries = vec![
+            Entry::from("a/2020-02-03/0/5"),
+            Entry::from("b/2020-01-01/0/50"),
+            Entry::from("c/2020-02-02/20/0"),
+        ];
+
+        let groups = group_indices(&entries, GroupBy::Month, false);
+        assert_eq!(2, groups.len());
+        assert_eq!("2020-01", groups[0].0);
+        assert_eq!("2020-02", groups[1].0);
+        assert_eq!(vec![0, 2], groups[1].1);
+
+        let groups = group_indices(&entries, GroupBy::Week, true);
+        assert_eq!(vec!["2020-W06", "2020-W05", "2020-W01"], groups.iter().map(|g| g.0.clone()).collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn test_from_str1() {
+        assert_eq!(SortBy::Amount, SortBy::from_str("Amount").unwrap());
+        assert_eq!(GroupBy::Epic, GroupBy::from_str("epic").unwrap());
+        assert_eq!(SortError::InvalidSort, SortBy::from_str("x").unwrap_err());
+        assert_eq!(SortError::InvalidGroup, GroupBy::from_str("year").unwrap_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Statistics over a FilterResult.
+
+use std::cmp::Ordering;
+use chrono::Weekday;
+use serde::Serialize;
+use crate::entry::Entry;
+use crate::number::NumberType;
+use crate::wallet::{FilterResult, Year, Month, Categories};
+
+/// Number of largest entries.
+const LARGEST_N: usize = 5;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+    Weekday::Fri, Weekday::Sat, Weekday::Sun,
+];
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AmountStats {
+    pub total: NumberType,
+    pub average: NumberType,
+    pub median: NumberType,
+}
+
+impl AmountStats {
+    /// Monthly values, without gaps.
+    fn from_values(values: &[NumberType]) -> Self {
+        let total: NumberType = values.iter().fold(0.0, |sum, v| sum + v);
+
+        Self {
+            total,
+            average: if values.is_empty() { 0.0 } else { total / values.len() as NumberType },
+            median: median(values),
+        }
+    }
+}
+
+fn median(values: &[NumberType]) -> NumberType {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let mut values = values.to_vec();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    // Same index for an odd length.
+    let lower = values[(values.len() - 1) / 2];
+    let upper = values[values.len() / 2];
+
+    (lower + upper) / 2.0
+}
+
+/// Change from `previous` to `current`. The percent is relative
+/// to the absolute previous value, None when there was nothing before.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Change {
+    pub current: NumberType,
+    pub previous: NumberType,
+    pub change: NumberType,
+    pub percent: Option<NumberType>,
+}
+
+impl Change {
+    fn new(current: NumberType, previous: NumberType) -> Self {
+        let change = current - previous;
+
+        Self {
+            current,
+            previous,
+            change,
+            percent: if previous == 0.0 { None } else { Some(change / previous.abs() * 100.0) },
+        }
+    }
+}
+
+/// Balance of a category in the last month, compared with the month
+/// before and the same month one year before.
+#[derive(Debug, Serialize)]
+pub struct CategoryTrend {
+    pub category: String,
+    pub month_over_month: Change,
+    pub year_over_year: Change,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekdayStats {
+    pub weekday: String,
+    pub count: u64,
+    /// Positive.
+    pub expense: NumberType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// Number of months from the first to the last entry.
+    pub months: usize,
+    pub revenue: AmountStats,
+    /// Positive.
+    pub expense: AmountStats,
+    /// Percent of the revenue not spent. None without revenue.
+    pub savings_rate: Option<NumberType>,
+    pub largest_revenues: Vec<Entry>,
+    pub largest_expenses: Vec<Entry>,
+    pub weekdays: Vec<WeekdayStats>,
+    /// The month the category trends refer to, like `2020-03`.
+    pub month: Option<String>,
+    pub categories: Vec<CategoryTrend>,
+}
+
+/// Category summaries of one month. None when there is no such month.
+fn month_categories(result: &FilterResult, year: Year, month: Month) -> Option<&Categories> {
+    result.years.get(&year)
+        .and_then(|year_sum| year_sum.months.get(&month))
+        .map(|month_sum| &month_sum.categories)
+}
+
+fn category_balance(categories: Option<&Categories>, category: &str) -> NumberType {
+    categories
+        .and_then(|categories| categories.get(category))
+        .map(|category_sum| category_sum.balance.unwrap())
+        .unwrap_or(0.0)
+}
+
+impl Stats {
+    pub fn from_result(result: &FilterResult) -> Self {
+        println!("-> Stats::from_result()");
+
+        // Months
+        let mut keys: Vec<(Year, Month)> = result.years.values()
+            .flat_map(|year_sum| year_sum.months.keys().map(move |month| (year_sum.year, *month)))
+            .collect();
+        keys.sort();
+
+        let mut revenues: Vec<NumberType> = vec![];
+        let mut expenses: Vec<NumberType> = vec![];
+
+        if let (Some(first), Some(last)) = (keys.first(), keys.last()) {
+            let (mut year, mut month) = *first;
+            while (year, month) <= *last {
+                match result.years.get(&year).and_then(|year_sum| year_sum.months.get(&month)) {
+                    Some(month_sum) => {
+                        revenues.push(month_sum.revenue.unwrap());
+                        expenses.push(month_sum.expense.unwrap().abs());
+                    },
+                    None => {
+                        revenues.push(0.0);
+                        expenses.push(0.0);
+                    },
+                }
+
+                month += 1;
+                if month > 12 {
+                    month = 1;
+                    year += 1;
+                }
+            }
+        }
+
+        let revenue = AmountStats::from_values(&revenues);
+        let expense = AmountStats::from_values(&expenses);
+
+        // Savings Rate
+        let savings_rate = if revenue.total > 0.0 {
+            Some((revenue.total - expense.total) / revenue.total * 100.0)
+        } else {
+            None
+        };
+
+        // Largest Entries
+        let largest = |f: &dyn Fn(&Entry) -> NumberType| -> Vec<Entry> {
+            let mut entries: Vec<Entry> = result.entries.iter()
+                .filter(|entry| f(entry) > 0.0)
+                .map(|entry| (**entry).clone())
+                .collect();
+            entries.sort_by(|a, b| f(b).partial_cmp(&f(a)).unwrap_or(Ordering::Equal));
+            entries.truncate(LARGEST_N);
+            entries
+        };
+        let largest_revenues = largest(&|entry| entry.revenue().unwrap());
+        let largest_expenses = largest(&|entry| entry.expense().unwrap().abs());
+
+        // Weekdays
+        let weekdays = WEEKDAYS.iter()
+            .map(|weekday| {
+                let entries: Vec<_> = result.entries.iter()
+                    .filter(|entry| entry.has_expense() && entry.date().weekday() == *weekday)
+                    .collect();
+
+                WeekdayStats {
+                    weekday: weekday.to_string(),
+                    count: entries.len() as u64,
+                    expense: entries.iter().fold(0.0, |sum, entry| sum + entry.expense().unwrap().abs()),
+                }
+            })
+            .collect();
+
+        // Category Trends
+        let mut month: Option<String> = None;
+        let mut categories: Vec<CategoryTrend> = vec![];
+
+        if let Some((year, last_month)) = keys.last() {
+            let (year, last_month) = (*year, *last_month);
+            let (prev_year, prev_month) = if last_month == 1 { (year - 1, 12) } else { (year, last_month - 1) };
+
+            let current = month_categories(result, year, last_month);
+            let previous = month_categories(result, prev_year, prev_month);
+            let year_before = month_categories(result, year - 1, last_month);
+
+            let mut names: Vec<String> = vec![current, previous, year_before].into_iter()
+                .flatten()
+                .flat_map(|categories| categories.keys().cloned())
+                .collect();
+            names.sort();
+            names.dedup();
+
+            categories = names.into_iter()
+                .map(|name| {
+                    let balance = category_balance(current, &name);
+
+                    CategoryTrend {
+                        month_over_month: Change::new(balance, category_balance(previous, &name)),
+                        year_over_year: Change::new(balance, category_balance(year_before, &name)),
+                        category: name,
+                    }
+                })
+                .collect();
+
+            month = Some(format!("{}-{:02}", year, last_month));
+        }
+
+        Self {
+            months: revenues.len(),
+            revenue,
+            expense,
+            savings_rate,
+            largest_revenues,
+            largest_expenses,
+            weekdays,
+            month,
+            categories,
+        }
+    }
+
+    pub fn show(&self) {
+        if self.months == 0 {
+            println!("No entries found.");
+            return;
+        }
+
+        println!("Months: {}", self.months);
+        println!();
+
+        println!("Monthly            Total    Average     Median");
+        println!("Revenue     {:>12.2} {:>10.2} {:>10.2}", self.revenue.total, self.revenue.average, self.revenue.median);
+        println!("Expense     {:>12.2} {:>10.2} {:>10.2}", self.expense.total, self.expense.average, self.expense.median);
+        println!();
+
+        match self.savings_rate {
+            Some(rate) => println!("Savings Rate: {:.1} %", rate),
+            None => println!("Savings Rate: -"),
+        }
+        println!();
+
+        println!("Largest Revenues");
+        for entry in &self.largest_revenues {
+            println!("  {} {:>10.2}  {}", entry.date().ymd(), entry.revenue().unwrap(), entry.title());
+        }
+        println!();
+
+        println!("Largest Expenses");
+        for entry in &self.largest_expenses {
+            println!("  {} {:>10.2}  {}", entry.date().ymd(), entry.expense().unwrap().abs(), entry.title());
+        }
+        println!();
+
+        println!("Weekday  Count    Expense");
+        for weekday in &self.weekdays {
+            println!("{:<7} {:>6} {:>10.2}", weekday.weekday, weekday.count, weekday.expense);
+        }
+        println!();
+
+        if let Some(month) = &self.month {
+            println!("Category {:<15} {:>10} {:>10} {:>8} {:>10} {:>8}", month, "Balance", "MoM", "%", "YoY", "%");
+            for trend in &self.categories {
+                println!("{:<24} {:>10.2} {:>10.2} {:>8} {:>10.2} {:>8}",
+                    trend.category,
+                    trend.month_over_month.current,
+                    trend.month_over_month.change,
+                    format_percent(trend.month_over_month.percent),
+                    trend.year_over_year.change,
+                    format_percent(trend.year_over_year.percent),
+                );
+            }
+        }
+    }
+}
+
+fn format_percent(percent: Option<NumberType>) -> String {
+    match percent {
+        Some(percent) => format!("{:+.1}", percent),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests_stats {
+    use super::{Stats, Change, median};
+    use crate::wallet::FilterResult;
+    use crate::entry::Entry;
+
+    fn entry(s: &str, category: &str) -> Entry {
+        let mut entry = Entry::from(s);
+        entry.set_category(category.to_string());
+        entry
+    }
+
+    #[test]
+    fn test_median1() {
+        assert_eq!(0.0, median(&[]));
+        assert_eq!(2.0, median(&[3.0, 1.0, 2.0]));
+        assert_eq!(2.5, median(&[4.0, 1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn test_change1() {
+        assert_eq!(Some(-50.0), Change::new(-30.0, -20.0).percent);
+        assert_eq!(None, Change::new(10.0, 0.0).percent);
+    }
+
+    #[test]
+    fn test_stats1() {
+        let mut r1 = FilterResult::new();
+        r1.add(entry("Salary/2019-03-01/1000/0", "job"));
+        r1.add(entry("Food/2019-03-06/0/100", "food"));
+        r1.add(entry("Salary/2020-02-01/1000/0", "job"));
+        r1.add(entry("Food/2020-02-03/0/200", "food"));
+        r1.add(entry("Food/2020-03-02/0/300", "food"));
+        r1.add(entry("Car/2020-03-10/0/400", "car"));
+
+        let s1 = Stats::from_result(&r1);
+
+        // 2019-03 to 2020-03
+        assert_eq!(13, s1.months);
+        assert_eq!(2000.0, s1.revenue.total);
+        assert_eq!(1000.0, s1.expense.total);
+        assert_eq!(0.0, s1.expense.median);
+        assert_eq!(Some(50.0), s1.savings_rate);
+
+        assert_eq!("Car", s1.largest_expenses[0].title());
+        assert_eq!(4, s1.largest_expenses.len());
+
+        // 2020-03-02 and 2020-02-03 are Mondays.
+        assert_eq!("Mon", s1.weekdays[0].weekday);
+        assert_eq!(2, s1.weekdays[0].count);
+        assert_eq!(500.0, s1.weekdays[0].expense);
+
+        assert_eq!(Some("2020-03".to_string()), s1.month);
+        let food = s1.categories.iter().find(|trend| trend.category == "food").unwrap();
+        assert_eq!(-100.0, food.month_over_month.change);
+        assert_eq!(-200.0, food.year_over_year.change);
+        assert_eq!(Some(-200.0), food.year_over_year.percent);
+    }
+}
@@ -0,0 +1,298 @@
+//! Two-way sync between two wallet directories.
+//!
+//! Each wallet keeps a sync state per peer in `data/sync.json`: the
+//! version of every Entry at the last sync. An Entry which was changed
+//! on one side only is copied to the other side. An Entry which is
+//! missing on one side but has a version is a tombstone: it was removed
+//! there and is removed on the other side too. When both sides changed
+//! an Entry differently it is a conflict and neither side is changed.
+//! A change which cannot be applied keeps the version of the last sync,
+//! so it is tried again with the next sync.
+
+use std::path::PathBuf;
+use std::fs::{read_to_string, rename, File};
+use std::io::Write;
+use std::collections::{BTreeMap, HashMap};
+use chrono::Local;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use crate::entry::Entry;
+use crate::chain::SealError;
+
+const SYNC_FILE: &str = "sync.json";
+
+/// Entry ID and version.
+pub type Versions = BTreeMap<String, String>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PeerState {
+    pub synced_at: String,
+    pub entries: Versions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncData {
+    /// ID of this wallet.
+    id: String,
+    peers: BTreeMap<String, PeerState>,
+}
+
+pub struct SyncState {
+    path: PathBuf,
+    data: SyncData,
+}
+
+impl SyncState {
+    /// A new wallet ID is created with the first sync.
+    pub fn open(data_dir: PathBuf) -> Self {
+        println!("-> SyncState::open({:?})", data_dir);
+
+        let path = data_dir.join(SYNC_FILE);
+        let data = match read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).expect("Cannot parse sync file"),
+            Err(_) => SyncData {
+                id: Uuid::new_v4().to_string(),
+                peers: BTreeMap::new(),
+            },
+        };
+
+        Self {
+            path,
+            data,
+        }
+    }
+
+    pub fn id(&self) -> String {
+        self.data.id.clone()
+    }
+
+    /// Versions at the last sync with a peer. Empty for a new peer.
+    pub fn versions(&self, peer: &str) -> Versions {
+        self.data.peers.get(peer)
+            .map(|state| state.entries.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_versions(&mut self, peer: &str, entries: Versions) {
+        self.data.peers.insert(peer.to_string(), PeerState {
+            synced_at: Local::now().format("%F %T").to_string(),
+            entries,
+        });
+    }
+
+    pub fn save(&self) {
+        println!("-> SyncState::save()");
+
+        let mut tmp_path = self.path.clone();
+        tmp_path.set_extension("json.tmp");
+
+        let raw = serde_json::to_string(&self.data).expect("Cannot serialize sync state");
+        let mut file = File::create(&tmp_path).expect("Cannot open sync file for writing");
+        file.write_all(raw.as_bytes()).expect("Cannot write sync file");
+        rename(tmp_path, &self.path).expect("Cannot move sync file into place");
+    }
+}
+
+#[derive(Debug)]
+pub enum Change {
+    Add(Entry),
+    Update(Entry),
+    Remove(String),
+}
+
+impl Change {
+    /// ID of the changed Entry.
+    pub fn id(&self) -> String {
+        match self {
+            Change::Add(entry) | Change::Update(entry) => entry.id(),
+            Change::Remove(id) => id.clone(),
+        }
+    }
+}
+
+/// A change which was not applied, like in a sealed month.
+#[derive(Debug)]
+pub struct SyncFailure {
+    pub change: Change,
+    pub error: SealError,
+}
+
+/// Both sides changed an Entry since the last sync. None when it was removed.
+#[derive(Debug)]
+pub struct SyncConflict {
+    pub id: String,
+    pub ours: Option<Entry>,
+    pub theirs: Option<Entry>,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Changes for our wallet.
+    pub pull: Vec<Change>,
+    /// Changes for their wallet.
+    pub push: Vec<Change>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Versions after the sync. Conflicts keep their old version.
+    pub versions: Versions,
+}
+
+/// The change which makes `to` out of `from`. None when both are equal.
+fn change(id: &str, from: Option<&Entry>, to: Option<&Entry>) -> Option<Change> {
+    match (from, to) {
+        (None, Some(to)) => Some(Change::Add(to.clone())),
+        (Some(_), Some(to)) => Some(Change::Update(to.clone())),
+        (Some(_), None) => Some(Change::Remove(id.to_string())),
+        (None, None) => None,
+    }
+}
+
+/// Compare both sides with the versions of the last sync.
+pub fn plan(base: &Versions, ours: &HashMap<String, Entry>, theirs: &HashMap<String, Entry>) -> SyncPlan {
+    let mut ids: Vec<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut plan = SyncPlan::default();
+
+    for id in ids {
+        let base_version = base.get(id);
+        let our_entry = ours.get(id);
+        let their_entry = theirs.get(id);
+        let our_version = our_entry.map(|entry| entry.version());
+        let their_version = their_entry.map(|entry| entry.version());
+
+        // Version of both sides after the sync.
+        let version = if our_version == their_version {
+            our_version
+        } else if our_version.as_ref() == base_version {
+            // Changed on their side only.
+            plan.pull.extend(change(id, our_entry, their_entry));
+            their_version
+        } else if their_version.as_ref() == base_version {
+            // Changed on our side only.
+            plan.push.extend(change(id, their_entry, our_entry));
+            our_version
+        } else {
+            plan.conflicts.push(SyncConflict {
+                id: id.clone(),
+                ours: our_entry.cloned(),
+                theirs: their_entry.cloned(),
+            });
+            base_version.cloned()
+        };
+
+        if let Some(version) = version {
+            plan.versions.insert(id.clone(), version);
+        }
+    }
+
+    plan
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub pulled: Vec<Change>,
+    pub pushed: Vec<Change>,
+    pub conflicts: Vec<SyncConflict>,
+    pub failed: Vec<SyncFailure>,
+}
+
+impl SyncReport {
+    pub fn show(&self) {
+        show_changes("Pulled", &self.pulled);
+        show_changes("Pushed", &self.pushed);
+
+        println!("Conflicts: {}", self.conflicts.len());
+        for conflict in &self.conflicts {
+            println!("  {}", conflict.id);
+            show_side("ours", &conflict.ours);
+            show_side("theirs", &conflict.theirs);
+        }
+
+        if !self.failed.is_empty() {
+            println!("Failed: {}", self.failed.len());
+            for failure in &self.failed {
+                show_change(&failure.change);
+                println!("    {}", failure.error);
+            }
+        }
+    }
+}
+
+fn show_changes(label: &str, changes: &[Change]) {
+    println!("{}: {}", label, changes.len());
+    for change in changes {
+        show_change(change);
+    }
+}
+
+fn show_change(change: &Change) {
+    match change {
+        Change::Add(entry) => println!("  add     {} {:>10.2}  {}", entry.date().ymd(), entry.balance().unwrap(), entry.title()),
+        Change::Update(entry) => println!("  update  {} {:>10.2}  {}", entry.date().ymd(), entry.balance().unwrap(), entry.title()),
+        Change::Remove(id) => println!("  remove  {}", id),
+    }
+}
+
+fn show_side(label: &str, entry: &Option<Entry>) {
+    match entry {
+        Some(entry) => println!("    {:<7} {} {:>10.2}  {}", label, entry.date().ymd(), entry.balance().unwrap(), entry.title()),
+        None => println!("    {:<7} removed", label),
+    }
+}
+
+#[cfg(test)]
+mod tests_sync {
+    use std::collections::HashMap;
+    use super::{plan, Versions, Change};
+    use crate::entry::Entry;
+
+    fn entry(id: &str, s: &str) -> Entry {
+        let mut entry = Entry::from(s);
+        entry.set_id(id.to_string());
+        entry
+    }
+
+    fn entries(entries: Vec<Entry>) -> HashMap<String, Entry> {
+        entries.into_iter().map(|entry| (entry.id(), entry)).collect()
+    }
+
+    #[test]
+    fn test_plan1() {
+        let a = entry("a", "A/2020-01-01/0/1");
+        let b = entry("b", "B/2020-01-02/0/2");
+        let c = entry("c", "C/2020-01-03/0/3");
+        let d = entry("d", "D/2020-01-04/0/4");
+
+        let mut base = Versions::new();
+        for e in &[&a, &b, &c, &d] {
+            base.insert(e.id(), e.version());
+        }
+
+        // a: edited by us, b: removed by them, c: edited on both sides,
+        // d: unchanged, e: added by them.
+        let ours = entries(vec![entry("a", "A2/2020-01-01/0/1"), b.clone(), entry("c", "C1/2020-01-03/0/3"), d.clone()]);
+        let theirs = entries(vec![a.clone(), entry("c", "C2/2020-01-03/0/3"), d.clone(), entry("e", "E/2020-01-05/0/5")]);
+
+        let p1 = plan(&base, &ours, &theirs);
+
+        assert_eq!(2, p1.pull.len());
+        assert!(matches!(&p1.pull[0], Change::Remove(id) if id == "b"));
+        assert!(matches!(&p1.pull[1], Change::Add(entry) if entry.title() == "E"));
+
+        assert_eq!(1, p1.push.len());
+        assert!(matches!(&p1.push[0], Change::Update(entry) if entry.title() == "A2"));
+
+        assert_eq!(1, p1.conflicts.len());
+        assert_eq!("c", p1.conflicts[0].id);
+
+        // The conflict keeps its old version, b is gone.
+        assert_eq!(vec!["a", "c", "d", "e"], p1.versions.keys().collect::<Vec<&String>>());
+        assert_eq!(&c.version(), &p1.versions["c"]);
+
+        // Added on both sides with different content.
+        let p2 = plan(&Versions::new(), &entries(vec![a.clone()]), &entries(vec![entry("a", "X/2020-01-01/0/1")]));
+        assert_eq!(1, p2.conflicts.len());
+        assert!(p2.versions.is_empty());
+    }
+}
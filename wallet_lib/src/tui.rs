@@ -0,0 +1,709 @@
+//! Full-screen terminal UI.
+//!
+//! Browse the wallet month by month, filter the entries and edit or
+//! delete them. Only needs a terminal, so it also works over SSH.
+
+use std::io::{stdout, Stdout, Result as IoResult};
+use std::rc::Rc;
+use std::str::FromStr;
+use crossterm::execute;
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::Terminal;
+use ratatui::Frame;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Layout, Direction, Constraint, Rect};
+use ratatui::style::{Style, Modifier, Color};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState};
+use chrono::{Local, Datelike};
+use crate::wallet::{Wallet, FilterOptions, FilterResult, MonthSummary, Year, Month};
+use crate::entry::Entry;
+use crate::date::Date;
+use crate::import::parse_amount;
+use crate::prompt::parse_date;
+
+/// Live filter for the entry table. All values are case-insensitive substrings.
+#[derive(Debug, Default)]
+struct Filter {
+    text: String,
+    category: String,
+    epic: String,
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.category.is_empty() && self.epic.is_empty()
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let contains = |haystack: String, needle: &str| -> bool {
+            needle.is_empty() || haystack.to_lowercase().contains(&needle.to_lowercase())
+        };
+
+        contains(entry.category(), &self.category)
+            && contains(entry.epic(), &self.epic)
+            && (contains(entry.title(), &self.text) || contains(entry.comment(), &self.text))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterField {
+    Text,
+    Category,
+    Epic,
+}
+
+const FORM_LABELS: [&str; 7] = ["Title", "Date", "Revenue", "Expense", "Category", "Epic", "Comment"];
+
+/// Edit form for one Entry.
+#[derive(Debug)]
+struct EditForm {
+    entry: Entry,
+    values: Vec<String>,
+    focus: usize,
+}
+
+impl EditForm {
+    fn new(entry: &Entry) -> Self {
+        let values = vec![
+            entry.title(),
+            entry.date().ymd(),
+            format!("{:.2}", entry.revenue().unwrap()),
+            format!("{:.2}", -entry.expense().unwrap() + 0.0),
+            entry.category(),
+            entry.epic(),
+            entry.comment(),
+        ];
+
+        Self {
+            entry: entry.clone(),
+            values,
+            focus: 0,
+        }
+    }
+
+    fn next(&mut self) {
+        self.focus = (self.focus + 1) % self.values.len();
+    }
+
+    fn previous(&mut self) {
+        self.focus = (self.focus + self.values.len() - 1) % self.values.len();
+    }
+
+    fn push(&mut self, c: char) {
+        self.values[self.focus].push(c);
+    }
+
+    fn pop(&mut self) {
+        self.values[self.focus].pop();
+    }
+
+    /// The edited Entry, or the label of the first invalid field.
+    fn to_entry(&self) -> Result<Entry, &'static str> {
+        let mut entry = self.entry.clone();
+
+        let title = self.values[0].trim();
+        if title.is_empty() {
+            return Err("Title");
+        }
+        entry.set_title(title.to_string());
+
+        let date = parse_date(&self.values[1], self.entry.date()).ok_or("Date")?;
+        entry.set_date(date);
+
+        let amount = |s: &str| -> Option<f64> {
+            if s.trim().is_empty() {
+                Some(0.0)
+            } else {
+                parse_amount(s)
+            }
+        };
+        entry.set_revenue(amount(&self.values[2]).ok_or("Revenue")?);
+        entry.set_expense(amount(&self.values[3]).ok_or("Expense")?);
+
+        let category = self.values[4].trim();
+        entry.set_category(if category.is_empty() { "default" } else { category }.to_string());
+
+        let epic = self.values[5].trim();
+        entry.set_epic(if epic.is_empty() { "default" } else { epic }.to_string());
+
+        entry.set_comment(self.values[6].trim().to_string());
+
+        Ok(entry)
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    Browse,
+    Filter(FilterField),
+    Edit(Box<EditForm>),
+    Delete,
+}
+
+/// All months with entries, sorted. Contains at least the current month.
+fn months(result: &FilterResult) -> Vec<(Year, Month)> {
+    let mut months: Vec<(Year, Month)> = vec![];
+    for (year, year_summary) in &result.years {
+        for month in year_summary.months.keys() {
+            months.push((*year, *month));
+        }
+    }
+
+    if months.is_empty() {
+        let now = Local::now();
+        months.push((now.year(), now.month()));
+    }
+
+    months.sort_unstable();
+    months
+}
+
+pub struct Tui {
+    wallet: Wallet,
+    months: Vec<(Year, Month)>,
+    month: usize,
+    result: FilterResult,
+    filter: Filter,
+    visible: Vec<Rc<Entry>>,
+    table: TableState,
+    mode: Mode,
+    status: String,
+    quit: bool,
+}
+
+impl Tui {
+    pub fn new(wallet: Wallet) -> Self {
+        let mut tui = Self {
+            wallet,
+            months: vec![],
+            month: 0,
+            result: FilterResult::new(),
+            filter: Filter::default(),
+            visible: vec![],
+            table: TableState::default(),
+            mode: Mode::Browse,
+            status: String::new(),
+            quit: false,
+        };
+
+        tui.load_months();
+        tui.month = tui.months.len() - 1;
+        tui.load_month();
+        tui
+    }
+
+    /// Run until the user quits. The terminal is restored on return.
+    pub fn run(&mut self) -> IoResult<()> {
+        enable_raw_mode()?;
+        let mut out = stdout();
+        execute!(out, EnterAlternateScreen)?;
+
+        let mut terminal = Terminal::new(CrosstermBackend::new(out))?;
+        let result = self.event_loop(&mut terminal);
+
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> IoResult<()> {
+        // The wallet prints debug output while loading. Redraw everything.
+        terminal.clear()?;
+
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                let reload = self.handle_key(key);
+                if reload {
+                    terminal.clear()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Data
+
+    fn load_months(&mut self) {
+        let result = self.wallet.filter(FilterOptions::new());
+        self.months = months(&result);
+    }
+
+    /// Load the current month and apply the filter.
+    fn load_month(&mut self) {
+        let (year, month) = self.months[self.month];
+
+        let mut options = FilterOptions::new();
+        options.date = Date::from_str(&format!("{}-{}", year, month)).ok();
+        self.result = self.wallet.filter(options);
+
+        self.apply_filter();
+    }
+
+    fn apply_filter(&mut self) {
+        let selected_id = self.selected().map(|entry| entry.id());
+
+        let mut visible: Vec<Rc<Entry>> = self.result.entries.iter()
+            .filter(|entry| self.filter.matches(entry))
+            .cloned()
+            .collect();
+        visible.sort_by_key(|entry| entry.date().ymd());
+        self.visible = visible;
+
+        // Keep the selection on the same Entry when possible.
+        let index = selected_id
+            .and_then(|id| self.visible.iter().position(|entry| entry.id() == id))
+            .unwrap_or(0);
+        self.select(index);
+    }
+
+    fn month_summary(&self) -> Option<&MonthSummary> {
+        let (year, month) = self.months[self.month];
+        self.result.years.get(&year)?.months.get(&month)
+    }
+
+    fn selected(&self) -> Option<Rc<Entry>> {
+        self.table.selected().and_then(|index| self.visible.get(index)).cloned()
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.visible.is_empty() {
+            self.table.select(None);
+        } else {
+            self.table.select(Some(index.min(self.visible.len() - 1)));
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let current = self.table.selected().unwrap_or(0) as isize;
+        self.select((current + delta).max(0) as usize);
+    }
+
+    fn move_month(&mut self, delta: isize) {
+        let month = self.month as isize + delta;
+        if month >= 0 && (month as usize) < self.months.len() {
+            self.month = month as usize;
+            self.table.select(None);
+            self.load_month();
+        }
+    }
+
+    // Keys
+
+    /// Returns true when the wallet has been read again.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return false;
+        }
+
+        let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+        match mode {
+            Mode::Browse => self.handle_browse_key(key),
+            Mode::Filter(field) => {
+                self.handle_filter_key(field, key);
+                false
+            },
+            Mode::Edit(form) => self.handle_edit_key(form, key),
+            Mode::Delete => self.handle_delete_key(key),
+        }
+    }
+
+    fn handle_browse_key(&mut self, key: KeyEvent) -> bool {
+        self.status.clear();
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('n') => {
+                self.move_month(1);
+                return true;
+            },
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('p') => {
+                self.move_month(-1);
+                return true;
+            },
+            KeyCode::Char('/') => self.mode = Mode::Filter(FilterField::Text),
+            KeyCode::Char('c') => self.mode = Mode::Filter(FilterField::Category),
+            KeyCode::Char('x') => self.mode = Mode::Filter(FilterField::Epic),
+            KeyCode::Esc => {
+                self.filter = Filter::default();
+                self.apply_filter();
+            },
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(entry) = self.selected() {
+                    self.mode = Mode::Edit(Box::new(EditForm::new(&entry)));
+                }
+            },
+            KeyCode::Delete | KeyCode::Char('d') if self.selected().is_some() => {
+                self.mode = Mode::Delete;
+            },
+            KeyCode::Char('r') => {
+                self.reload();
+                return true;
+            },
+            _ => (),
+        }
+
+        false
+    }
+
+    fn handle_filter_key(&mut self, field: FilterField, key: KeyEvent) {
+        let value = match field {
+            FilterField::Text => &mut self.filter.text,
+            FilterField::Category => &mut self.filter.category,
+            FilterField::Epic => &mut self.filter.epic,
+        };
+
+        match key.code {
+            KeyCode::Enter => return,
+            KeyCode::Esc => value.clear(),
+            KeyCode::Backspace => {
+                value.pop();
+                self.mode = Mode::Filter(field);
+            },
+            KeyCode::Char(c) => {
+                value.push(c);
+                self.mode = Mode::Filter(field);
+            },
+            _ => self.mode = Mode::Filter(field),
+        }
+
+        self.apply_filter();
+    }
+
+    fn handle_edit_key(&mut self, mut form: Box<EditForm>, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => {
+                self.status = "Edit cancelled.".to_string();
+                return false;
+            },
+            KeyCode::Enter => {
+                match form.to_entry() {
+                    Ok(entry) => {
+                        let title = entry.title();
+                        self.status = match self.wallet.update(entry) {
+                            Ok(true) => format!("Saved '{}'.", title),
+                            Ok(false) => format!("Entry '{}' not found.", title),
+                            Err(error) => format!("{}.", error),
+                        };
+                        self.reload();
+                        return true;
+                    },
+                    Err(label) => {
+                        self.status = format!("Invalid {}.", label);
+                    },
+                }
+            },
+            KeyCode::Tab | KeyCode::Down => form.next(),
+            KeyCode::BackTab | KeyCode::Up => form.previous(),
+            KeyCode::Backspace => form.pop(),
+            KeyCode::Char(c) => form.push(c),
+            _ => (),
+        }
+
+        self.mode = Mode::Edit(form);
+        false
+    }
+
+    fn handle_delete_key(&mut self, key: KeyEvent) -> bool {
+        if let (KeyCode::Char('y'), Some(entry)) = (key.code, self.selected()) {
+            match self.wallet.remove(&entry.id()) {
+                Ok(true) => self.status = format!("Deleted '{}'.", entry.title()),
+                Ok(false) => (),
+                Err(error) => self.status = format!("{}.", error),
+            }
+            self.reload();
+            return true;
+        }
+
+        self.status = "Delete cancelled.".to_string();
+        false
+    }
+
+    /// Read the wallet again and stay on the current month.
+    fn reload(&mut self) {
+        let current = self.months[self.month];
+
+        self.load_months();
+        self.month = match self.months.binary_search(&current) {
+            Ok(index) => index,
+            Err(index) => index.min(self.months.len() - 1),
+        };
+        self.load_month();
+    }
+
+    // Drawing
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3), Constraint::Length(1)])
+            .split(frame.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(40), Constraint::Length(32)])
+            .split(rows[1]);
+
+        self.draw_header(frame, rows[0]);
+        self.draw_table(frame, columns[0]);
+        self.draw_sidebar(frame, columns[1]);
+        self.draw_footer(frame, rows[2]);
+
+        if let Mode::Edit(form) = &self.mode {
+            draw_form(frame, form);
+        }
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let (year, month) = self.months[self.month];
+
+        let mut spans = vec![
+            Span::styled(format!(" Wallet {}-{:02} ", year, month), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(" ({}/{})", self.month + 1, self.months.len())),
+        ];
+
+        if !self.filter.is_empty() {
+            spans.push(Span::raw(format!("  Filter: text='{}' category='{}' epic='{}'",
+                self.filter.text, self.filter.category, self.filter.epic)));
+        }
+
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let header = Row::new(vec!["Date", "Title", "Revenue", "Expense", "Balance", "Category", "Epic"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+
+        let rows: Vec<Row> = self.visible.iter().map(|entry| {
+            Row::new(vec![
+                entry.date().ymd(),
+                entry.title(),
+                format!("{:>10.2}", entry.revenue().unwrap()),
+                format!("{:>10.2}", entry.expense().unwrap() + 0.0),
+                format!("{:>10.2}", entry.balance().unwrap() + 0.0),
+                entry.category(),
+                entry.epic(),
+            ])
+        }).collect();
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Min(12),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(10),
+        ];
+
+        let title = format!(" Entries {}/{} ", self.visible.len(), self.result.entries.len());
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_sidebar(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = vec![];
+
+        if let Some(summary) = self.month_summary() {
+            lines.push(Line::from(format!("Revenue  {:>12.2}", summary.revenue.unwrap())));
+            lines.push(Line::from(format!("Expense  {:>12.2}", summary.expense.unwrap() + 0.0)));
+
+            let balance = summary.balance.unwrap() + 0.0;
+            let color = if balance < 0.0 { Color::Red } else { Color::Green };
+            lines.push(Line::from(Span::styled(format!("Balance  {:>12.2}", balance), Style::default().fg(color))));
+
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Categories", Style::default().add_modifier(Modifier::BOLD))));
+
+            let mut categories: Vec<(&String, f64)> = summary.categories.iter()
+                .map(|(name, category)| (name, category.balance.unwrap() + 0.0))
+                .collect();
+            categories.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (name, balance) in categories {
+                lines.push(Line::from(format!("{:<16} {:>10.2}", name, balance)));
+            }
+        } else {
+            lines.push(Line::from("No entries."));
+        }
+
+        let sidebar = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(" Month "));
+        frame.render_widget(sidebar, area);
+    }
+
+    fn draw_footer(&self, frame: &mut Frame, area: Rect) {
+        let text = match &self.mode {
+            Mode::Filter(field) => {
+                let (label, value) = match field {
+                    FilterField::Text => ("Text", &self.filter.text),
+                    FilterField::Category => ("Category", &self.filter.category),
+                    FilterField::Epic => ("Epic", &self.filter.epic),
+                };
+                format!("{}: {}_  (Enter: keep, Esc: clear)", label, value)
+            },
+            Mode::Delete => {
+                let title = self.selected().map(|entry| entry.title()).unwrap_or_default();
+                format!("Delete '{}'? (y/n)", title)
+            },
+            Mode::Edit(_) => "Tab: next field  Enter: save  Esc: cancel".to_string(),
+            Mode::Browse if !self.status.is_empty() => self.status.clone(),
+            Mode::Browse => "q: quit  ←/→: month  ↑/↓: entry  /: text  c: category  x: epic  Esc: clear  e: edit  d: delete  r: reload".to_string(),
+        };
+
+        frame.render_widget(Paragraph::new(text), area);
+    }
+}
+
+fn draw_form(frame: &mut Frame, form: &EditForm) {
+    let area = centered(frame.size(), 60, FORM_LABELS.len() as u16 + 2);
+
+    let lines: Vec<Line> = FORM_LABELS.iter().zip(form.values.iter()).enumerate()
+        .map(|(index, (label, value))| {
+            let style = if index == form.focus {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::raw(format!("{:<9}", label)),
+                Span::styled(value.clone(), style),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(" Edit "));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+/// Rectangle of the given size in the middle of `area`.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests_filter {
+    use super::Filter;
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_filter_matches1() {
+        let mut e1 = Entry::new();
+        e1.set_title("Groceries".to_string());
+        e1.set_comment("Supermarket".to_string());
+        e1.set_category("food".to_string());
+        e1.set_epic("home".to_string());
+
+        let mut f1 = Filter::default();
+        assert!(f1.matches(&e1));
+
+        f1.text = "market".to_string();
+        assert!(f1.matches(&e1));
+
+        f1.category = "FO".to_string();
+        assert!(f1.matches(&e1));
+
+        f1.epic = "trip".to_string();
+        assert!(!f1.matches(&e1));
+    }
+}
+
+#[cfg(test)]
+mod tests_edit_form {
+    use std::str::FromStr;
+    use super::EditForm;
+    use crate::entry::Entry;
+    use crate::date::Date;
+
+    #[test]
+    fn test_edit_form1() {
+        let mut e1 = Entry::new();
+        e1.set_title("t1".to_string());
+        e1.set_date(Date::from_str("2020-01-05").unwrap());
+        e1.set_expense(23.4);
+
+        let mut f1 = EditForm::new(&e1);
+        assert_eq!("23.40", f1.values[3]);
+
+        // Title
+        f1.push('x');
+
+        // Date
+        f1.next();
+        f1.values[1] = "07".to_string();
+
+        // Revenue
+        f1.next();
+        f1.values[2] = "1,5".to_string();
+
+        let e2 = f1.to_entry().unwrap();
+        assert_eq!(e1.id(), e2.id());
+        assert_eq!("t1x", e2.title());
+        assert_eq!("2020-01-07", e2.date().to_string());
+        assert_eq!(1.5, e2.revenue().unwrap());
+        assert_eq!(-23.4, e2.expense().unwrap());
+
+        f1.values[1] = "2020-02-30".to_string();
+        assert_eq!(Err("Date"), f1.to_entry().map(|_| ()));
+
+        f1.previous();
+        f1.previous();
+        assert_eq!(0, f1.focus);
+        f1.values[0].clear();
+        assert_eq!(Err("Title"), f1.to_entry().map(|_| ()));
+    }
+}
+
+#[cfg(test)]
+mod tests_months {
+    use std::str::FromStr;
+    use super::months;
+    use crate::wallet::FilterResult;
+    use crate::entry::Entry;
+    use crate::date::Date;
+
+    #[test]
+    fn test_months1() {
+        let mut r1 = FilterResult::new();
+        for date in &["2020-02-01", "2019-12-24", "2020-02-03"] {
+            let mut entry = Entry::new();
+            entry.set_date(Date::from_str(date).unwrap());
+            r1.add(entry);
+        }
+
+        assert_eq!(vec![(2019, 12), (2020, 2)], months(&r1));
+        assert_eq!(1, months(&FilterResult::new()).len());
+    }
+}
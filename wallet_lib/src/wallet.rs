@@ -1,24 +1,35 @@
 
 // use std::convert::From;
 // use std::env::current_dir;
-use std::path::PathBuf;
-use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::fs::{create_dir_all, rename, write};
 use std::fs::File;
 use std::io::Write;
 use std::include_bytes;
 use glob::glob;
 use std::fmt::{Display, Formatter, Result as FmtRes};
 use std::vec::Vec;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::rc::Rc;
-// use serde::Serialize;
-use crate::entry::Entry;
-use crate::epic::Epic;
+use std::cell::Cell;
+use serde::Serialize;
+use crate::entry::{Entry, EntryChanges, FieldChange};
+use crate::epic::{Epic, EpicError};
 use crate::yaml::YamlFile;
 use crate::date::Date;
 use crate::command::CommandOptions;
-use crate::mustache::{IndexMustacheFile};
-use crate::number::Number;
+use crate::mustache::{IndexMustacheFile, MonthMustacheFile};
+use crate::number::{Number, NumberType};
+use crate::search::Search;
+use crate::query::{Expr, Field, Op, Value};
+use crate::sort::{sort, SortBy};
+use crate::forecast::{Forecast, FORECAST_MONTHS};
+use crate::history::{self, History, HistoryError, Operation, snapshot};
+use crate::git::{Git, GitError};
+use crate::merge::{MergeStrategy, MergeReport, MergeFailure, Conflict, Resolution};
+use crate::crypt::{self, CryptError};
+use crate::chain::{self, Link, MonthStatus, SealError};
+use crate::sync::{SyncState, SyncPlan, SyncReport, SyncFailure, Change, plan as sync_plan};
 
 pub type Year = i32;
 pub type Month = u32;
@@ -46,14 +57,42 @@ impl AddedResult {
 pub enum AddResult {
     ExistsInIndex,
     Added(AddedResult),
+    /// The month is sealed or closed.
+    ReadOnly(SealError),
 }
 
 impl Display for AddResult {
     fn fmt(&self, f: &mut Formatter) -> FmtRes {
-        write!(f, "{}", match self {
-            AddResult::Added(_) => "Yes",
-            _ => "No",
-        })
+        match self {
+            AddResult::Added(_) => write!(f, "Yes"),
+            AddResult::ReadOnly(error) => write!(f, "No, {}", error),
+            _ => write!(f, "No"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CategoryError {
+    NotFound(String),
+    Exists(String),
+    Same,
+    Sealed(SealError),
+}
+
+impl Display for CategoryError {
+    fn fmt(&self, f: &mut Formatter) -> FmtRes {
+        match self {
+            CategoryError::NotFound(name) => write!(f, "Category '{}' not found", name),
+            CategoryError::Exists(name) => write!(f, "Category '{}' already exists, use merge instead", name),
+            CategoryError::Same => write!(f, "Both categories are the same"),
+            CategoryError::Sealed(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<SealError> for CategoryError {
+    fn from(error: SealError) -> Self {
+        CategoryError::Sealed(error)
     }
 }
 
@@ -64,6 +103,12 @@ pub struct FilterOptions {
     pub filter_expense: Option<bool>,
     pub category: Option<String>,
     pub epic: Option<String>,
+    pub search: Option<Search>,
+    pub min: Option<NumberType>,
+    pub max: Option<NumberType>,
+    pub query: Option<Expr>,
+    pub sort: SortBy,
+    pub reverse: bool,
 }
 
 impl FilterOptions {
@@ -74,7 +119,69 @@ impl FilterOptions {
             filter_expense: None,
             category: None,
             epic: None,
+            search: None,
+            min: None,
+            max: None,
+            query: None,
+            sort: SortBy::Date,
+            reverse: false,
+        }
+    }
+}
+
+impl FilterOptions {
+    /// The field compared with `min` and `max`: the revenue when filtering
+    /// only revenues, the expense when filtering only expenses, otherwise
+    /// the absolute balance.
+    fn amount_field(&self) -> Field {
+        let revenue = self.filter_revenue.unwrap_or(false);
+        let expense = self.filter_expense.unwrap_or(false);
+
+        match (revenue, expense) {
+            (true, false) => Field::Revenue,
+            (false, true) => Field::Expense,
+            _ => Field::Amount,
+        }
+    }
+
+    /// The amount compared with `min` and `max`. Always positive.
+    pub fn amount(&self, entry: &Entry) -> NumberType {
+        self.amount_field().number(entry)
+    }
+
+    /// Compile all options into one expression. None matches everything.
+    pub fn to_expr(&self) -> Option<Expr> {
+        let mut exprs: Vec<Expr> = vec![];
+
+        if let Some(date) = self.date {
+            exprs.push(Expr::term(Field::Date, Op::Eq, Value::Date(date)));
+        }
+        if let Some(true) = self.filter_revenue {
+            exprs.push(Expr::term(Field::Revenue, Op::Gt, Value::Number(0.0)));
         }
+        if let Some(true) = self.filter_expense {
+            exprs.push(Expr::term(Field::Expense, Op::Gt, Value::Number(0.0)));
+        }
+        if let Some(category) = &self.category {
+            exprs.push(Expr::term(Field::Category, Op::Eq, Value::Exact(category.clone())));
+        }
+        if let Some(epic) = &self.epic {
+            exprs.push(Expr::term(Field::Epic, Op::Eq, Value::Exact(epic.clone())));
+        }
+        if let Some(min) = self.min {
+            exprs.push(Expr::term(self.amount_field(), Op::Ge, Value::Number(min)));
+        }
+        if let Some(max) = self.max {
+            exprs.push(Expr::term(self.amount_field(), Op::Le, Value::Number(max)));
+        }
+        if let Some(search) = &self.search {
+            exprs.push(Expr::term(Field::Text, Op::Contains, Value::Search(search.clone())));
+        }
+        if let Some(query) = &self.query {
+            exprs.push(query.clone());
+        }
+
+        Expr::all(exprs)
     }
 }
 
@@ -87,6 +194,14 @@ impl From<CommandOptions> for FilterOptions {
         foptions.filter_expense = options.filter_expense;
         foptions.category = options.category;
         foptions.epic = options.epic;
+        foptions.search = options.search;
+        foptions.min = options.min;
+        foptions.max = options.max;
+        foptions.query = options.query;
+        if let Some(sort) = options.sort {
+            foptions.sort = sort;
+        }
+        foptions.reverse = options.reverse;
 
         foptions
     }
@@ -96,7 +211,7 @@ trait AddEntry {
     fn add(&mut self, entry_ref: EntryRc);
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct CategorySummary {
     pub revenue: Number,
     pub expense: Number,
@@ -123,7 +238,7 @@ impl AddEntry for CategorySummary {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct EpicSummary {
     pub revenue: Number,
     pub expense: Number,
@@ -151,7 +266,7 @@ impl AddEntry for EpicSummary {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct DaySummary {
     pub revenue: Number,
     pub expense: Number,
@@ -179,8 +294,9 @@ impl AddEntry for DaySummary {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MonthSummary {
+    #[serde(skip)]
     pub entries: Entries,
     pub days: Days,
     pub categories: Categories,
@@ -277,10 +393,11 @@ impl AddEntry for MonthSummary {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct YearSummary {
     pub year: Year,
 
+    #[serde(skip)]
     pub entries: Entries,
     pub months: Months,
     pub categories: Categories,
@@ -380,7 +497,7 @@ impl AddEntry for YearSummary {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct FilterResult {
     pub entries: Entries,
     pub years: Years,
@@ -481,6 +598,14 @@ impl FilterResult {
     }
 }
 
+/// An Entry found by `Wallet::search()`.
+#[derive(Debug)]
+pub struct SearchHit {
+    pub entry: Entry,
+    pub month_file: String,
+    pub score: usize,
+}
+
 #[derive(Debug)]
 pub struct Wallet {
     path: PathBuf,
@@ -489,6 +614,24 @@ pub struct Wallet {
     tmp_dir: PathBuf,
     index_file: PathBuf,
     epics_file: PathBuf,
+    /// Set while an operation is recorded, so nested operations
+    /// are recorded as part of it.
+    recording: Cell<bool>,
+    /// Set when changes are committed to git.
+    git: Option<Git>,
+    /// Allow changes of sealed or closed months.
+    unseal: bool,
+}
+
+/// Month of a month file, like `2020-03`.
+fn month_name(path: &Path) -> String {
+    let file_name = path.file_stem().unwrap_or_default().to_string_lossy();
+    file_name.trim_start_matches("month_").replace('_', "-")
+}
+
+/// Name of an Entry in the history, like `Groceries 2020-01-05 -23.40`.
+fn entry_name(entry: &Entry) -> String {
+    format!("{} {} {:.2}", entry.title(), entry.date().ymd(), entry.balance().unwrap())
 }
 
 impl Wallet {
@@ -512,6 +655,8 @@ impl Wallet {
         println!("-> index_file {:?}", index_file);
         println!("-> epics_file {:?}", epics_file);
 
+        let git = Git::open(basedir.clone());
+
         let _w = Wallet {
             path: basedir,
             data_dir,
@@ -519,6 +664,9 @@ impl Wallet {
             tmp_dir,
             index_file,
             epics_file,
+            recording: Cell::new(false),
+            git,
+            unseal: false,
         };
         _w.init();
         _w
@@ -529,6 +677,11 @@ impl Wallet {
         self.html_dir = path.into();
     }
 
+    pub fn set_unseal(&mut self, unseal: bool) {
+        println!("-> Wallet::set_unseal({:?})", unseal);
+        self.unseal = unseal;
+    }
+
     pub fn init(&self) {
         println!("-> Wallet::init()");
         self.create_dirs();
@@ -548,305 +701,1180 @@ impl Wallet {
         println!("-> Wallet::add(f={:?})", force);
         println!("-> entry {:?}", entry);
 
-        // Index
-        let mut index_file = YamlFile::open_index(self.index_file.clone());
+        self.record(format!("add: {}", entry_name(&entry)), || {
+            if let Err(error) = self.check_writable(&entry.date()) {
+                return AddResult::ReadOnly(error);
+            }
+
+            // Index
+            let mut index_file = YamlFile::open_index(self.index_file.clone());
 
-        if index_file.exists(entry.id()) {
-            if ! force {
-                return AddResult::ExistsInIndex;
+            if index_file.exists(entry.id()) {
+                if ! force {
+                    return AddResult::ExistsInIndex;
+                }
+            } else {
+                index_file.add(entry.id());
             }
-        } else {
-            index_file.add(entry.id());
-        }
 
-        // Epics
-        let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
-        if epics_file.exists(entry.epic()) {
-            // println!("-> epic exist");
-        } else {
-            // println!("-> NO epic");
-            let mut epic = Epic::new();
-            epic.set_handle(entry.epic());
-            epics_file.add(epic);
-        }
+            // Epics
+            self.add_epic_handle(entry.epic());
+
+            // Month file
+            let month_file_name = format!("month_{}.yml", entry.date().fym("_"));
+            // println!("-> month_file_name: {:?}", month_file_name);
 
-        // Month file
-        let month_file_name = format!("month_{}.yml", entry.date().fym("_"));
-        // println!("-> month_file_name: {:?}", month_file_name);
+            let month_file_path = self.data_dir.join(month_file_name.clone());
+            // println!("-> month_file_path: {:?}", month_file_path);
 
-        let month_file_path = self.data_dir.join(month_file_name.clone());
-        // println!("-> month_file_path: {:?}", month_file_path);
+            // Copied Entries keep their time.
+            let mut entry = entry;
+            if entry.updated_at().is_none() {
+                entry.touch();
+            }
 
-        let mut month_file = YamlFile::open_month(month_file_path);
-        month_file.add(entry);
+            let mut month_file = YamlFile::open_month(month_file_path);
+            month_file.add(entry);
 
-        AddResult::Added(AddedResult {
-            month_file_name,
+            AddResult::Added(AddedResult {
+                month_file_name,
+            })
         })
     }
 
-    /// Add Epic
-    pub fn add_epic(&self, epic: Epic) -> bool {
-        let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
-
-        if epics_file.exists(epic.handle()) {
-            false
-        } else {
-            println!("-> NO epic");
-            epics_file.add(epic);
-            true
+    /// Run a mutation and record the changed data files in the history.
+    pub fn record<R, F: FnOnce() -> R>(&self, name: String, f: F) -> R {
+        if self.recording.get() {
+            return f();
         }
-    }
 
-    /// Retrieve Entries by a set of filters.
-    pub fn filter(&self, options: FilterOptions) -> FilterResult {
-        println!("-> Wallet::filter()");
-        // println!("-> options: {:?}", options);
+        println!("-> Wallet::record({})", name);
 
-        // Result
-        let mut all_items: Vec<Entry> = vec![];
+        self.recording.set(true);
+        history::start_recording(&self.data_dir);
+        let result = f();
+        let (before, after) = history::stop_recording(&self.data_dir);
+        self.recording.set(false);
 
-        let data_dir = self.data_dir.join("month_");
+        let mut history = History::open(self.data_dir.clone());
+        if history.record(name.clone(), &before, &after) {
+            self.commit(&name);
+        }
 
-        // Find files.
-        if let Some(path) = data_dir.to_str() {
-            let mut g = String::from(path);
+        result
+    }
 
-            // Filter Date
-            if let Some(date) = options.date {
-                if date.has_year() && date.has_month() {
-                    g.push_str(&date.rym());
-                } else if date.has_year() {
-                    g.push_str(&date.year().to_string());
-                    g.push_str("_*");
-                } else if date.has_month() {
-                    g.push_str(&date.rym());
-                } else if date.has_day() {
-                    g.push_str(&date.rym());
-                } else {
-                    g.push_str("*");
-                }
-            } else {
-                g.push_str("*");
+    /// Commit all changes, when git is enabled.
+    fn commit(&self, message: &str) {
+        if let Some(git) = &self.git {
+            if let Err(error) = git.commit(message) {
+                println!("Error: {}", error);
             }
+        }
+    }
 
-            g.push_str(".yml");
-
-            // Get files.
-            let entries = glob(&g).expect("Failed to read glob pattern");
-            for entry in entries {
-                match entry {
-                    Ok(path) => {
-                        // println!("-> path: {:?}", path.display());
-                        // println!("-> path: {:?}", path);
-
-                        let month_file = YamlFile::open_month(path);
-                        let mut month_items: Vec<Entry> = month_file.get();
-                        // println!("-> month_items: {:?}", month_items);
-
-                        all_items.append(&mut month_items);
-                    },
-                    _ => (),
-                }
+    /// Fails when the operation changed a month which may not be changed.
+    fn check_operation(&self, operation: &Operation) -> Result<(), HistoryError> {
+        for change in &operation.files {
+            if !change.file.starts_with("month_") {
+                continue;
+            }
+            if let Ok(date) = month_name(Path::new(&change.file)).parse::<Date>() {
+                self.check_writable(&date).map_err(HistoryError::ReadOnly)?;
             }
         }
 
-        // Filter
-        let filter = all_items.iter().filter(|entry| -> bool {
-            // println!("-> filter: {:?}", entry);
+        Ok(())
+    }
 
-            // Date
-            if let Some(odate) = options.date {
-                // println!("-> odate: {:?} -> {:?}", odate, odate.to_string());
-
-                let edate = entry.date();
-                // println!("-> edate: {:?} -> {:?}", edate, edate.to_string());
-                // println!("-> odate == edate: {:?}", odate == edate);
-
-                if odate.has_day() {
-                    // println!("-> filter year-month-day");
-                    if odate != edate {
-                        return false;
-                    }
-                } else if odate.has_month() && odate.year() == edate.year() && odate.month() == edate.month() {
-                    // println!("-> filter year-month");
-                } else if odate.has_year() && odate.year() == edate.year() {
-                    // println!("-> filter year");
-                } else {
-                    // println!("-> date filter failed");
-                    return false;
-                }
-            }
+    /// Revert the last operation.
+    pub fn undo(&self) -> Result<Operation, HistoryError> {
+        let mut history = History::open(self.data_dir.clone());
+        if let Some(operation) = history.next_undo() {
+            self.check_operation(operation)?;
+        }
 
-            // Revenue
-            if let Some(filter_revenue) = options.filter_revenue {
-                // println!("-> filter_revenue: {:?}", filter_revenue);
+        let operation = history.undo()?;
+        self.commit(&format!("undo: {}", operation.name));
 
-                if filter_revenue && !entry.has_revenue() {
-                    return false;
-                }
-            }
+        Ok(operation)
+    }
 
-            // Expense
-            if let Some(filter_expense) = options.filter_expense {
-                // println!("-> filter_expense: {:?}", filter_expense);
+    /// Apply the last undone operation again.
+    pub fn redo(&self) -> Result<Operation, HistoryError> {
+        let mut history = History::open(self.data_dir.clone());
+        if let Some(operation) = history.next_redo() {
+            self.check_operation(operation)?;
+        }
 
-                if filter_expense && !entry.has_expense() {
-                    return false;
-                }
-            }
+        let operation = history.redo()?;
+        self.commit(&format!("redo: {}", operation.name));
 
-            // Category
-            if let Some(category) = &options.category {
-                // println!("-> category: {:?}", category);
+        Ok(operation)
+    }
 
-                if &entry.category() != category {
-                    return false;
-                }
-            }
+    /// Commit every change to a git repository in the wallet directory.
+    pub fn init_git(&mut self) -> Result<(), GitError> {
+        println!("-> Wallet::init_git()");
 
-            // Epic
-            if let Some(epic) = &options.epic {
-                // println!("-> epic: {:?}", epic);
+        self.git = Some(Git::init(self.path.clone())?);
+        Ok(())
+    }
 
-                if &entry.epic() != epic {
-                    return false;
-                }
-            }
+    /// None when git is not enabled.
+    pub fn git(&self) -> Option<&Git> {
+        self.git.as_ref()
+    }
 
-            true
-        });
+    pub fn history(&self) -> History {
+        History::open(self.data_dir.clone())
+    }
 
-        // Result
-        let mut result = FilterResult::new();
+    pub fn is_encrypted(&self) -> bool {
+        crypt::is_encrypted(&self.data_dir)
+    }
 
-        // Apply filter.
-        let entries: Vec<&Entry> = filter.collect();
+    /// Encrypted and no passphrase given yet.
+    pub fn is_locked(&self) -> bool {
+        self.is_encrypted() && !crypt::is_unlocked(&self.data_dir)
+    }
 
-        // Iterate entries.
-        for entry in entries {
-            // println!("-> entry: {:?}", entry);
+    pub fn unlock(&self, passphrase: &str) -> Result<(), CryptError> {
+        crypt::unlock(&self.data_dir, passphrase)
+    }
 
-            result.add(entry.clone());
+    /// Encrypt all data files. Returns the number of files.
+    /// The history is cleared, because it holds the plain contents.
+    /// Refused with git, because the git history is not rewritten.
+    pub fn encrypt(&self, passphrase: &str) -> Result<usize, CryptError> {
+        println!("-> Wallet::encrypt()");
+
+        if self.git.is_some() {
+            return Err(CryptError::Git);
         }
 
-        // let mut filtered_items: Vec<&Entry> = filter.collect();
-        // filtered_items.sort_by(|a, b| a.date().to_string().cmp(&b.date().to_string()));
+        let key = crypt::new_key(&self.data_dir, passphrase)?;
+        let files = snapshot(&self.data_dir).into_iter()
+            .map(|(file, content)| {
+                let encoded = key.encode(&self.data_dir.join(&file), &content);
+                (file, encoded)
+            })
+            .collect();
+        let n = self.replace_files(files);
 
-        // let items = filtered_items.into_iter().map(|item| item.clone()).collect();
-        // items
+        // The crypt file comes last, so a failure before leaves a plain wallet.
+        crypt::setup(&self.data_dir, key);
 
-        result
+        Ok(n)
     }
 
-    /// HTML
-    pub fn html(&self, _options: FilterOptions) {
-        println!("-> Wallet::html()");
-
-        // Create html directory.
-        create_dir_all(&self.html_dir).expect("Cannot create html directory.");
+    /// Decrypt all data files. Returns the number of files.
+    pub fn decrypt(&self) -> Result<usize, CryptError> {
+        println!("-> Wallet::decrypt()");
 
-        // let cwd = current_dir().expect("Cannot get current dir");
-        // println!("-> cwd: {}", cwd.display());
+        if !self.is_encrypted() {
+            return Err(CryptError::NotEncrypted);
+        }
 
-        // let up = cwd.join("..");
-        // println!("-> up: {}", up.display());
+        // Decode all files before the key is gone.
+        let mut files = snapshot(&self.data_dir);
+        for (file, content) in files.iter_mut() {
+            *content = crypt::decode(&self.data_dir.join(file), content.clone())?;
+        }
 
-        let _result = self.filter(_options);
+        let n = self.replace_files(files);
+        crypt::remove(&self.data_dir)?;
+        self.commit("decrypt");
 
-        // CSS File
-        {
-            let css_file_path = self.html_dir.join("style.css");
-            println!("-> css_file: {}", css_file_path.display());
-            let mut css_file = match File::create(&css_file_path) {
-                Ok(file) => file,
-                Err(why) => panic!("Cannot create {}: {}", css_file_path.display(), why),
-            };
+        Ok(n)
+    }
 
-            let bytes = include_bytes!("../../resources/css/style.css");
-            css_file.write_all(bytes)
-                .expect("Cannot write style file.");
+    /// Write all files next to the old ones first, then move them into
+    /// place. Returns the number of files.
+    fn replace_files(&self, files: BTreeMap<String, String>) -> usize {
+        for (file, content) in &files {
+            write(self.data_dir.join(format!("{}.tmp", file)), content).expect("Cannot write data file");
+        }
+        for file in files.keys() {
+            rename(self.data_dir.join(format!("{}.tmp", file)), self.data_dir.join(file))
+                .expect("Cannot move data file into place");
         }
 
-        // Index File
-        {
-            // Path
-            let index_file_path = self.html_dir.join("index.html");
-            println!("-> index_file: {}", index_file_path.display());
+        History::open(self.data_dir.clone()).clear();
 
-            // File
-            let index_file = IndexMustacheFile::new(index_file_path.to_str().unwrap().to_string());
-            index_file.render(&_result);
-        }
+        files.len()
     }
-}
 
-#[cfg(test)]
-mod tests_addedresult {
-    use super::AddedResult;
+    /// Fails when a data file cannot be decoded.
+    pub fn check_files(&self) -> Result<(), CryptError> {
+        for file in snapshot(&self.data_dir).keys() {
+            YamlFile::check(&self.data_dir.join(file))?;
+        }
 
-    #[test]
-    fn test_addedresult_new() {
-        AddedResult::new();
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests_addresult {
-    use super::{AddedResult, AddResult};
+    /// Hash chain fields of all month files, sorted by month.
+    fn links(&self) -> Vec<(String, Link)> {
+        self.month_files().into_iter()
+            .map(|path| (month_name(&path), YamlFile::open_month(path).link().unwrap()))
+            .collect()
+    }
 
-    #[test]
-    fn test_addresult1() {
-        let r1 = AddResult::ExistsInIndex;
-        assert_eq!("No", format!("{}", r1));
+    /// Check the hash chain of all month files.
+    pub fn verify(&self) -> Vec<MonthStatus> {
+        println!("-> Wallet::verify()");
+        chain::verify(&self.links())
     }
 
-    #[test]
-    fn test_addresult2() {
-        let r1 = AddResult::Added(AddedResult::new());
-        assert_eq!("Yes", format!("{}", r1));
+    /// Last sealed month, like `2020-03`. All months before are sealed too.
+    pub fn sealed_until(&self) -> Option<String> {
+        self.links().into_iter()
+            .rev()
+            .find(|(_, link)| link.sealed_at.is_some())
+            .map(|(month, _)| month)
     }
-}
 
-#[cfg(test)]
-mod tests_filteroptions_basic {
-    use super::FilterOptions;
+    /// Closed months, like `2020-03`, from the index file and the month
+    /// files.
+    pub fn closed_months(&self) -> Vec<String> {
+        let mut closed = if self.index_file.exists() {
+            YamlFile::open_index(self.index_file.clone()).closed()
+        } else {
+            vec![]
+        };
 
-    #[test]
-    fn test_filteroptions1() {
-        FilterOptions::new();
+        for (month, link) in self.links() {
+            if link.closed_at.is_some() && !closed.contains(&month) {
+                closed.push(month);
+            }
+        }
+        closed.sort();
+        closed
     }
-}
 
-#[cfg(test)]
-mod tests_filteroptions_from {
-    use super::FilterOptions;
-    use crate::command::CommandOptions;
+    /// Close a month after it was reconciled. Returns false when it is
+    /// closed already.
+    pub fn close(&self, date: &Date) -> bool {
+        println!("-> Wallet::close({})", date.ym());
 
-    #[test]
-    fn test_filteroptions_from_commandoptions1() {
-        let mut copt1 = CommandOptions::new();
-        copt1.category = Some("test1".to_string());
+        self.record(format!("close: {}", date.ym()), || {
+            let month_file_path = self.data_dir.join(format!("month_{}.yml", date.fym("_")));
+            if month_file_path.exists() {
+                YamlFile::open_month(month_file_path).mark_closed();
+            }
 
-        let fopt1 = FilterOptions::from(copt1);
-        if let Some(category) = fopt1.category {
-            assert_eq!("test1", category);
+            let mut index_file = YamlFile::open_index(self.index_file.clone());
+            index_file.close_month(date.ym())
+        })
+    }
+
+    /// Fails when the month of the date is sealed, closed or was changed
+    /// outside of the wallet, unless unsealed.
+    fn check_writable(&self, date: &Date) -> Result<(), SealError> {
+        if self.unseal {
+            return Ok(());
         }
-        else {
-            assert!(false);
+
+        let month = date.ym();
+        let month_file_path = self.data_dir.join(format!("month_{}.yml", date.fym("_")));
+        if month_file_path.exists() && YamlFile::open_month(month_file_path).is_broken() {
+            return Err(SealError::Broken(month));
+        }
+        if self.sealed_until().is_some_and(|until| month <= until) {
+            return Err(SealError::Sealed(month));
+        }
+        if self.closed_months().contains(&month) {
+            return Err(SealError::Closed(month));
         }
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests_filterresult_basic {
-    use super::FilterResult;
-    use crate::entry::Entry;
+    /// Seal a month and all months before it. The chain must be intact
+    /// up to the month. Returns the newly sealed months.
+    pub fn seal(&self, date: &Date) -> Result<Vec<String>, SealError> {
+        println!("-> Wallet::seal({})", date.ym());
 
-    #[test]
-    fn test_filterresult1() {
-        FilterResult::new();
-    }
+        let month = date.ym();
+        let status = self.verify();
+        if !status.iter().any(|status| status.month == month) {
+            return Err(SealError::NotFound(month));
+        }
+        if let Some(broken) = status.iter().filter(|status| status.month <= month).find(|status| status.error.is_some()) {
+            return Err(SealError::Broken(broken.month.clone()));
+        }
+
+        self.record(format!("seal: {}", month), || {
+            let mut sealed = vec![];
+            for path in self.month_files() {
+                let name = month_name(&path);
+                if name > month {
+                    break;
+                }
+
+                let mut month_file = YamlFile::open_month(path);
+                if month_file.link().unwrap().sealed_at.is_none() {
+                    month_file.seal();
+                    sealed.push(name);
+                }
+            }
+
+            Ok(sealed)
+        })
+    }
+
+    /// Create an Epic for the handle if it does not exist yet.
+    fn add_epic_handle(&self, handle: String) {
+        let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+        if epics_file.exists(handle.clone()) {
+            // println!("-> epic exist");
+        } else {
+            // println!("-> NO epic");
+            let mut epic = Epic::new();
+            epic.set_handle(handle);
+            epics_file.add(epic);
+        }
+    }
+
+    /// All month files, sorted by name.
+    fn month_files(&self) -> Vec<PathBuf> {
+        let pattern = self.data_dir.join("month_*.yml");
+        let pattern = pattern.to_str().expect("Invalid data directory");
+
+        let mut paths: Vec<PathBuf> = glob(pattern).expect("Failed to read glob pattern")
+            .filter_map(|path| path.ok())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Find an Entry by ID. Returns the Entry and its month file.
+    fn find_with_path(&self, id: &str) -> Option<(Entry, PathBuf)> {
+        for path in self.month_files() {
+            let month_file = YamlFile::open_month(path.clone());
+            let entries: Vec<Entry> = month_file.get();
+
+            if let Some(entry) = entries.into_iter().find(|entry| entry.id() == id) {
+                return Some((entry, path));
+            }
+        }
+
+        None
+    }
+
+    /// Find an Entry by ID.
+    pub fn find(&self, id: &str) -> Option<Entry> {
+        self.find_with_path(id).map(|(entry, _)| entry)
+    }
+
+    /// Replace the Entry with the same ID. When the date has changed
+    /// the Entry is moved to another month file.
+    /// Returns false when the Entry does not exist.
+    pub fn update(&self, mut entry: Entry) -> Result<bool, SealError> {
+        println!("-> Wallet::update({})", entry.id());
+
+        entry.touch();
+
+        self.record(format!("edit: {}", entry_name(&entry)), || {
+            let (old, path) = match self.find_with_path(&entry.id()) {
+                Some(found) => found,
+                None => return Ok(false),
+            };
+            self.check_writable(&old.date())?;
+            self.check_writable(&entry.date())?;
+
+            {
+                let mut month_file = YamlFile::open_month(path);
+                month_file.remove(entry.id());
+            }
+
+            // Epics
+            self.add_epic_handle(entry.epic());
+
+            let month_file_path = self.data_dir.join(format!("month_{}.yml", entry.date().fym("_")));
+            let mut month_file = YamlFile::open_month(month_file_path);
+            month_file.add(entry);
+
+            Ok(true)
+        })
+    }
+
+    /// Remove an Entry from its month file and from the index.
+    /// Returns false when the Entry does not exist.
+    pub fn remove(&self, id: &str) -> Result<bool, SealError> {
+        println!("-> Wallet::remove({})", id);
+
+        let name = match self.find(id) {
+            Some(entry) => format!("remove: {}", entry_name(&entry)),
+            None => return Ok(false),
+        };
+
+        self.record(name, || {
+            let (old, path) = match self.find_with_path(id) {
+                Some(found) => found,
+                None => return Ok(false),
+            };
+            self.check_writable(&old.date())?;
+
+            let mut month_file = YamlFile::open_month(path);
+            month_file.remove(id.to_string());
+
+            let mut index_file = YamlFile::open_index(self.index_file.clone());
+            index_file.remove(id.to_string());
+
+            Ok(true)
+        })
+    }
+
+    /// Add Epic
+    pub fn add_epic(&self, epic: Epic) -> bool {
+        self.record(format!("epic add: {}", epic.handle()), || {
+            let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+
+            if epics_file.exists(epic.handle()) {
+                false
+            } else {
+                println!("-> NO epic");
+                epics_file.add(epic);
+                true
+            }
+        })
+    }
+
+    /// Apply `f` to all Entries. `f` returns true when it changed the
+    /// Entry. Returns the number of changed Entries.
+    fn rewrite<F: FnMut(&mut Entry) -> bool>(&self, mut f: F) -> Result<usize, SealError> {
+        println!("-> Wallet::rewrite()");
+
+        let (sealed_until, closed) = if self.unseal {
+            (None, vec![])
+        } else {
+            (self.sealed_until(), self.closed_months())
+        };
+
+        let mut n = 0;
+        let mut month_files: Vec<YamlFile> = vec![];
+        for path in self.month_files() {
+            let month = month_name(&path);
+            let mut month_file = YamlFile::open_month(path);
+            let entries: Vec<Entry> = month_file.get();
+
+            let mut changed = false;
+            for mut entry in entries {
+                if f(&mut entry) {
+                    entry.touch();
+                    month_file.update(entry);
+                    n += 1;
+                    changed = true;
+                }
+            }
+
+            let error = if !changed {
+                None
+            } else if sealed_until.as_ref().is_some_and(|until| &month <= until) {
+                Some(SealError::Sealed(month))
+            } else if closed.contains(&month) {
+                Some(SealError::Closed(month))
+            } else if !self.unseal && month_file.is_broken() {
+                Some(SealError::Broken(month))
+            } else {
+                None
+            };
+
+            if let Some(error) = error {
+                month_file.discard();
+                for month_file in &mut month_files {
+                    month_file.discard();
+                }
+                return Err(error);
+            }
+
+            if changed {
+                month_files.push(month_file);
+            }
+        }
+
+        // Write all files to temporary files first, so a failing
+        // write leaves every month file unchanged.
+        if let Err(error) = month_files.iter_mut().try_for_each(|month_file| month_file.stage()) {
+            for month_file in &mut month_files {
+                month_file.discard();
+            }
+            panic!("Cannot write month file: {}", error);
+        }
+
+        for month_file in &mut month_files {
+            month_file.commit().expect("Cannot move month file into place");
+        }
+
+        Ok(n)
+    }
+
+    /// Set the epic of all Entries from one handle to another.
+    fn move_epic_entries(&self, from: &str, to: &str) -> Result<usize, SealError> {
+        self.rewrite(|entry| {
+            if entry.epic() == from {
+                entry.set_epic(to.to_string());
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Find an Epic by handle.
+    pub fn find_epic(&self, handle: &str) -> Result<Epic, EpicError> {
+        self.epics().into_iter()
+            .find(|epic| epic.handle() == handle)
+            .ok_or_else(|| EpicError::NotFound(handle.to_string()))
+    }
+
+    /// Change title and background color of an Epic.
+    pub fn update_epic(&self, epic: Epic) -> Result<(), EpicError> {
+        println!("-> Wallet::update_epic({})", epic.handle());
+
+        self.record(format!("epic edit: {}", epic.handle()), || {
+            let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+            let handle = epic.handle();
+            if epics_file.update(epic) {
+                Ok(())
+            } else {
+                Err(EpicError::NotFound(handle))
+            }
+        })
+    }
+
+    /// Rename an Epic and all its Entries. Returns the number of changed Entries.
+    pub fn rename_epic(&self, from: &str, to: &str) -> Result<usize, EpicError> {
+        println!("-> Wallet::rename_epic({}, {})", from, to);
+
+        self.record(format!("epic rename: {} -> {}", from, to), || {
+            if from == "default" || to == "default" {
+                return Err(EpicError::Default);
+            }
+
+            let mut epic = self.find_epic(from)?;
+            if self.find_epic(to).is_ok() {
+                return Err(EpicError::Exists(to.to_string()));
+            }
+
+            let n = self.move_epic_entries(from, to)?;
+
+            let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+            epics_file.remove(from.to_string());
+            epic.set_handle(to.to_string());
+            epics_file.add(epic);
+
+            Ok(n)
+        })
+    }
+
+    /// Remove an Epic. Fails when it is still used by Entries, unless
+    /// `force` is set. Then the Entries are moved to the default Epic.
+    /// Returns the number of changed Entries.
+    pub fn remove_epic(&self, handle: &str, force: bool) -> Result<usize, EpicError> {
+        println!("-> Wallet::remove_epic({}, f={:?})", handle, force);
+
+        self.record(format!("epic remove: {}", handle), || {
+            if handle == "default" {
+                return Err(EpicError::Default);
+            }
+
+            self.find_epic(handle)?;
+
+            let mut options = FilterOptions::new();
+            options.epic = Some(handle.to_string());
+            let used = self.filter(options).entries.len();
+            if used > 0 && !force {
+                return Err(EpicError::InUse(handle.to_string(), used));
+            }
+
+            let n = self.move_epic_entries(handle, "default")?;
+
+            let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+            epics_file.remove(handle.to_string());
+
+            Ok(n)
+        })
+    }
+
+    /// Move all Entries of one Epic to another and remove the first one.
+    /// Returns the number of changed Entries.
+    pub fn merge_epics(&self, from: &str, into: &str) -> Result<usize, EpicError> {
+        println!("-> Wallet::merge_epics({}, {})", from, into);
+
+        self.record(format!("epic merge: {} -> {}", from, into), || {
+            if from == "default" {
+                return Err(EpicError::Default);
+            }
+
+            self.find_epic(from)?;
+            if into != "default" {
+                self.find_epic(into)?;
+            }
+
+            let n = self.move_epic_entries(from, into)?;
+
+            let mut epics_file = YamlFile::open_epics(self.epics_file.clone());
+            epics_file.remove(from.to_string());
+
+            Ok(n)
+        })
+    }
+
+    /// All Epics from the epics file.
+    pub fn epics(&self) -> Vec<Epic> {
+        let epics_file = YamlFile::open_epics(self.epics_file.clone());
+        epics_file.get()
+    }
+
+    /// All Categories used by Entries, sorted by name.
+    pub fn categories(&self) -> Vec<String> {
+        let result = self.filter(FilterOptions::new());
+
+        let mut categories: Vec<String> = result.categories.keys().cloned().collect();
+        categories.sort();
+        categories
+    }
+
+    /// Set the category of all Entries from one name to another.
+    fn move_category_entries(&self, from: &str, to: &str) -> Result<usize, SealError> {
+        self.rewrite(|entry| {
+            if entry.category() == from {
+                entry.set_category(to.to_string());
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Rename a Category. Fails when the new name is already used.
+    /// Returns the number of changed Entries.
+    pub fn rename_category(&self, from: &str, to: &str) -> Result<usize, CategoryError> {
+        println!("-> Wallet::rename_category({}, {})", from, to);
+
+        self.record(format!("category rename: {} -> {}", from, to), || {
+            if from == to {
+                return Err(CategoryError::Same);
+            }
+
+            let categories = self.categories();
+            if !categories.iter().any(|name| name == from) {
+                return Err(CategoryError::NotFound(from.to_string()));
+            }
+            if categories.iter().any(|name| name == to) {
+                return Err(CategoryError::Exists(to.to_string()));
+            }
+
+            Ok(self.move_category_entries(from, to)?)
+        })
+    }
+
+    /// Move all Entries of one Category into another existing one.
+    /// Returns the number of changed Entries.
+    pub fn merge_categories(&self, from: &str, into: &str) -> Result<usize, CategoryError> {
+        println!("-> Wallet::merge_categories({}, {})", from, into);
+
+        self.record(format!("category merge: {} -> {}", from, into), || {
+            if from == into {
+                return Err(CategoryError::Same);
+            }
+
+            let categories = self.categories();
+            for name in &[from, into] {
+                if !categories.iter().any(|category| category == name) {
+                    return Err(CategoryError::NotFound(name.to_string()));
+                }
+            }
+
+            Ok(self.move_category_entries(from, into)?)
+        })
+    }
+
+    /// Entries matching the filter with the fields which would change.
+    /// Entries without changes are left out.
+    pub fn bulk_edit_preview(&self, options: FilterOptions, changes: &EntryChanges) -> Vec<(Entry, Vec<FieldChange>)> {
+        println!("-> Wallet::bulk_edit_preview()");
+
+        self.filter(options).entries.iter()
+            .map(|entry| ((**entry).clone(), changes.diff(entry)))
+            .filter(|(_, diff)| !diff.is_empty())
+            .collect()
+    }
+
+    /// Apply the changes to all Entries matching the filter. All month
+    /// files are written in one transaction.
+    /// Returns the number of changed Entries.
+    pub fn bulk_edit(&self, options: FilterOptions, changes: &EntryChanges) -> Result<usize, SealError> {
+        println!("-> Wallet::bulk_edit()");
+
+        self.record(format!("bulk-edit: {}", changes), || {
+            let ids: HashSet<String> = self.filter(options).entries.iter()
+                .map(|entry| entry.id())
+                .collect();
+
+            self.rewrite(|entry| ids.contains(&entry.id()) && changes.apply(entry))
+        })
+    }
+
+    /// All Entries with the time of their last change. Entries without
+    /// one get the update time of their month file.
+    fn entries_with_time(&self) -> Vec<(Entry, Option<String>)> {
+        let mut entries = vec![];
+
+        for path in self.month_files() {
+            let month_file = YamlFile::open_month(path);
+            let file_updated_at = month_file.updated_at();
+
+            for entry in month_file.get::<Entry>() {
+                let updated_at = entry.updated_at().or_else(|| file_updated_at.clone());
+                entries.push((entry, updated_at));
+            }
+        }
+
+        entries
+    }
+
+    /// Add Epics and Entries of another Wallet which are missing here.
+    /// Entries with the same ID but different content are resolved by
+    /// the strategy. `ask` is only called for Interactive.
+    pub fn merge<F: FnMut(&Entry, &Entry) -> Resolution>(&self, other: &Wallet, strategy: MergeStrategy, mut ask: F) -> MergeReport {
+        println!("-> Wallet::merge({:?}, {:?})", other.path, strategy);
+
+        self.record(format!("merge: {}", other.path.display()), || {
+            let mut report = MergeReport::new();
+
+            // Epics
+            for epic in other.epics() {
+                let handle = epic.handle();
+                if self.add_epic(epic) {
+                    report.epics.push(handle);
+                }
+            }
+
+            // Entries
+            let ours: HashMap<String, (Entry, Option<String>)> = self.entries_with_time().into_iter()
+                .map(|(entry, updated_at)| (entry.id(), (entry, updated_at)))
+                .collect();
+
+            for (theirs, theirs_time) in other.entries_with_time() {
+                match ours.get(&theirs.id()) {
+                    None => {
+                        match self.add(theirs.clone(), false) {
+                            AddResult::Added(_) => report.added.push(theirs),
+                            AddResult::ExistsInIndex | AddResult::ReadOnly(_) => report.skipped += 1,
+                        }
+                    },
+                    Some((ours, _)) if ours.same_content(&theirs) => {
+                        report.unchanged += 1;
+                    },
+                    Some((ours, ours_time)) => {
+                        let resolution = strategy.resolve(ours_time, &theirs_time)
+                            .unwrap_or_else(|| ask(ours, &theirs));
+
+                        if resolution == Resolution::Theirs {
+                            if let Err(error) = self.update(theirs.clone()) {
+                                report.failed.push(MergeFailure {
+                                    entry: theirs,
+                                    error,
+                                });
+                                continue;
+                            }
+                        }
+
+                        report.conflicts.push(Conflict {
+                            ours: ours.clone(),
+                            theirs,
+                            resolution,
+                        });
+                    },
+                }
+            }
+
+            report
+        })
+    }
+
+    /// All Entries by ID.
+    fn entries_by_id(&self) -> HashMap<String, Entry> {
+        self.entries_with_time().into_iter()
+            .map(|(entry, _)| (entry.id(), entry))
+            .collect()
+    }
+
+    fn apply_change(&self, change: &Change) -> Result<(), SealError> {
+        match change {
+            // The ID can still be in the index.
+            Change::Add(entry) => match self.add(entry.clone(), true) {
+                AddResult::ReadOnly(error) => Err(error),
+                _ => Ok(()),
+            },
+            Change::Update(entry) => self.update(entry.clone()).map(|_| ()),
+            Change::Remove(id) => self.remove(id).map(|_| ()),
+        }
+    }
+
+    /// Apply the changes. Returns the applied and the failed ones.
+    fn apply_changes(&self, changes: Vec<Change>) -> (Vec<Change>, Vec<SyncFailure>) {
+        let mut applied = vec![];
+        let mut failed = vec![];
+        for change in changes {
+            match self.apply_change(&change) {
+                Ok(()) => applied.push(change),
+                Err(error) => failed.push(SyncFailure { change, error }),
+            }
+        }
+        (applied, failed)
+    }
+
+    /// Two-way sync with another Wallet. Both Wallets remember the
+    /// versions of all Entries, so the next sync can tell which side
+    /// has changed an Entry. Conflicts are reported and left as they are.
+    /// Changes which cannot be applied, like in sealed months, are
+    /// reported as failed and keep their old version.
+    pub fn sync(&self, other: &Wallet) -> SyncReport {
+        println!("-> Wallet::sync({:?})", other.path);
+
+        let mut our_state = SyncState::open(self.data_dir.clone());
+        let mut their_state = SyncState::open(other.data_dir.clone());
+
+        let base = our_state.versions(&their_state.id());
+        let SyncPlan { pull, push, conflicts, mut versions } = sync_plan(&base, &self.entries_by_id(), &other.entries_by_id());
+
+        let (pulled, pull_failed) = self.record(format!("sync: {}", other.path.display()), || {
+            for epic in other.epics() {
+                self.add_epic(epic);
+            }
+            self.apply_changes(pull)
+        });
+
+        let (pushed, push_failed) = other.record(format!("sync: {}", self.path.display()), || {
+            for epic in self.epics() {
+                other.add_epic(epic);
+            }
+            other.apply_changes(push)
+        });
+
+        let failed: Vec<SyncFailure> = pull_failed.into_iter().chain(push_failed).collect();
+        for failure in &failed {
+            let id = failure.change.id();
+            match base.get(&id) {
+                Some(version) => versions.insert(id, version.clone()),
+                None => versions.remove(&id),
+            };
+        }
+
+        our_state.set_versions(&their_state.id(), versions.clone());
+        their_state.set_versions(&our_state.id(), versions);
+        our_state.save();
+        their_state.save();
+
+        SyncReport {
+            pulled,
+            pushed,
+            conflicts,
+            failed,
+        }
+    }
+
+    /// Retrieve Entries by a set of filters.
+    pub fn filter(&self, options: FilterOptions) -> FilterResult {
+        println!("-> Wallet::filter()");
+        // println!("-> options: {:?}", options);
+
+        // Result
+        let mut all_items: Vec<Entry> = vec![];
+
+        let data_dir = self.data_dir.join("month_");
+
+        // Find files.
+        if let Some(path) = data_dir.to_str() {
+            let mut g = String::from(path);
+
+            // Filter Date
+            if let Some(date) = options.date {
+                if date.has_year() && date.has_month() {
+                    g.push_str(&date.rym());
+                } else if date.has_year() {
+                    g.push_str(&date.year().to_string());
+                    g.push_str("_*");
+                } else if date.has_month() {
+                    g.push_str(&date.rym());
+                } else if date.has_day() {
+                    g.push_str(&date.rym());
+                } else {
+                    g.push_str("*");
+                }
+            } else {
+                g.push_str("*");
+            }
+
+            g.push_str(".yml");
+
+            // Get files.
+            let entries = glob(&g).expect("Failed to read glob pattern");
+            for entry in entries {
+                match entry {
+                    Ok(path) => {
+                        // println!("-> path: {:?}", path.display());
+                        // println!("-> path: {:?}", path);
+
+                        let month_file = YamlFile::open_month(path);
+                        let mut month_items: Vec<Entry> = month_file.get();
+                        // println!("-> month_items: {:?}", month_items);
+
+                        all_items.append(&mut month_items);
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        // Sort
+        sort(&mut all_items, options.sort, options.reverse);
+
+        // Filter
+        let expr = options.to_expr();
+        let filter = all_items.iter().filter(|entry| -> bool {
+            // println!("-> filter: {:?}", entry);
+
+            match &expr {
+                Some(expr) => expr.matches(entry),
+                None => true,
+            }
+        });
+
+        // Result
+        let mut result = FilterResult::new();
+
+        // Apply filter.
+        let entries: Vec<&Entry> = filter.collect();
+
+        // Iterate entries.
+        for entry in entries {
+            // println!("-> entry: {:?}", entry);
+
+            result.add(entry.clone());
+        }
+
+        result
+    }
+
+    /// Search all month files. The best hits come first,
+    /// newer Entries before older ones with the same score.
+    pub fn search(&self, search: &Search) -> Vec<SearchHit> {
+        println!("-> Wallet::search({})", search.query());
+
+        let mut hits: Vec<SearchHit> = vec![];
+
+        for path in self.month_files() {
+            let month_file_name = path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let month_file = YamlFile::open_month(path);
+            let entries: Vec<Entry> = month_file.get();
+
+            for entry in entries {
+                if !search.is_match(&entry) {
+                    continue;
+                }
+
+                hits.push(SearchHit {
+                    score: search.score(&entry),
+                    entry,
+                    month_file: month_file_name.clone(),
+                });
+            }
+        }
+
+        hits.sort_by(|a, b| b.score.cmp(&a.score)
+            .then_with(|| b.entry.date().ymd().cmp(&a.entry.date().ymd())));
+
+        hits
+    }
+
+    /// Sum of all entries before a date, unfiltered.
+    pub fn balance_before(&self, date: Date) -> Number {
+        println!("-> Wallet::balance_before({})", date);
+
+        let mut options = FilterOptions::new();
+        options.query = Some(Expr::term(Field::Date, Op::Lt, Value::Date(date)));
+
+        self.filter(options).balance
+    }
+
+    /// HTML
+    /// With `carry_over` the running balance on month pages
+    /// starts from the balance of all earlier entries.
+    pub fn html(&self, _options: FilterOptions, carry_over: bool) {
+        println!("-> Wallet::html()");
+
+        // Create html directory.
+        create_dir_all(&self.html_dir).expect("Cannot create html directory.");
+
+        // let cwd = current_dir().expect("Cannot get current dir");
+        // println!("-> cwd: {}", cwd.display());
+
+        // let up = cwd.join("..");
+        // println!("-> up: {}", up.display());
+
+        let _result = self.filter(_options);
+
+        // CSS File
+        {
+            let css_file_path = self.html_dir.join("style.css");
+            println!("-> css_file: {}", css_file_path.display());
+            let mut css_file = match File::create(&css_file_path) {
+                Ok(file) => file,
+                Err(why) => panic!("Cannot create {}: {}", css_file_path.display(), why),
+            };
+
+            let bytes = include_bytes!("../../resources/css/style.css");
+            css_file.write_all(bytes)
+                .expect("Cannot write style file.");
+        }
+
+        // Index File
+        {
+            // Path
+            let index_file_path = self.html_dir.join("index.html");
+            println!("-> index_file: {}", index_file_path.display());
+
+            // File
+            // Forecast
+            let forecast = Forecast::from_result(&_result, Date::today(), FORECAST_MONTHS);
+
+            let index_file = IndexMustacheFile::new(index_file_path.to_str().unwrap().to_string());
+            index_file.render(&_result, &forecast);
+        }
+
+        // Balance of all earlier entries, unfiltered, by month.
+        let mut carried: HashMap<(Year, Month), Number> = HashMap::new();
+        if carry_over {
+            let all = self.filter(FilterOptions::new());
+            let mut years: Vec<&YearSummary> = all.years.values().collect();
+            years.sort_by_key(|year_sum| year_sum.year);
+
+            let mut balance = Number::new();
+            for year_sum in years {
+                let mut months: Vec<&Month> = year_sum.months.keys().collect();
+                months.sort();
+
+                for month in months {
+                    carried.insert((year_sum.year, *month), balance);
+                    balance += year_sum.months[month].balance;
+                }
+            }
+        }
+
+        // Month Files
+        let closed = self.closed_months();
+        for year_sum in _result.years.values() {
+            let year_dir = self.html_dir.join("year").join(year_sum.year.to_string());
+            create_dir_all(&year_dir).expect("Cannot create year directory.");
+
+            for (month, month_sum) in &year_sum.months {
+                let carried_over = carried.get(&(year_sum.year, *month)).copied();
+
+                let month_file_path = year_dir.join(format!("month_{:02}.html", month));
+                let month_file = MonthMustacheFile::new(month_file_path.to_str().unwrap().to_string());
+                let is_closed = closed.contains(&format!("{}-{:02}", year_sum.year, month));
+                month_file.render(year_sum.year, *month, month_sum, carried_over, is_closed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_addedresult {
+    use super::AddedResult;
+
+    #[test]
+    fn test_addedresult_new() {
+        AddedResult::new();
+    }
+}
+
+#[cfg(test)]
+mod tests_addresult {
+    use super::{AddedResult, AddResult};
+
+    #[test]
+    fn test_addresult1() {
+        let r1 = AddResult::ExistsInIndex;
+        assert_eq!("No", format!("{}", r1));
+    }
+
+    #[test]
+    fn test_addresult2() {
+        let r1 = AddResult::Added(AddedResult::new());
+        assert_eq!("Yes", format!("{}", r1));
+    }
+}
+
+#[cfg(test)]
+mod tests_filteroptions_basic {
+    use super::FilterOptions;
+
+    #[test]
+    fn test_filteroptions1() {
+        FilterOptions::new();
+    }
+}
+
+#[cfg(test)]
+mod tests_filteroptions_from {
+    use super::FilterOptions;
+    use crate::command::CommandOptions;
+
+    #[test]
+    fn test_filteroptions_from_commandoptions1() {
+        let mut copt1 = CommandOptions::new();
+        copt1.category = Some("test1".to_string());
+
+        let fopt1 = FilterOptions::from(copt1);
+        if let Some(category) = fopt1.category {
+            assert_eq!("test1", category);
+        }
+        else {
+            assert!(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_filteroptions_amount {
+    use super::FilterOptions;
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_filteroptions_amount1() {
+        let e1 = Entry::from("Hi/2001-01-01/30/50");
+
+        let mut o1 = FilterOptions::new();
+        assert_eq!(20.0, o1.amount(&e1));
+
+        o1.filter_revenue = Some(true);
+        assert_eq!(30.0, o1.amount(&e1));
+
+        o1.filter_revenue = None;
+        o1.filter_expense = Some(true);
+        assert_eq!(50.0, o1.amount(&e1));
+    }
+}
+
+#[cfg(test)]
+mod tests_filterresult_basic {
+    use super::FilterResult;
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_filterresult1() {
+        FilterResult::new();
+    }
 
     #[test]
     fn test_filterresult2() {
@@ -919,13 +1947,215 @@ mod tests_wallet_entry {
     }
 }
 
+#[cfg(test)]
+mod tests_wallet_update {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, AddResult};
+    use crate::entry::Entry;
+    use crate::date::Date;
+
+    #[test]
+    fn test_wallet_update_remove() {
+        remove_dir_all("../tmp/tests/wallet5").ok();
+
+        let mut e1 = Entry::new();
+        e1.set_date(Date::from_str("2020-01-05").unwrap());
+        e1.set_title("t1".to_string());
+        let id = e1.id();
+
+        let w1 = Wallet::new("../tmp/tests/wallet5".to_string());
+        w1.add(e1.clone(), false);
+
+        // Move to another month.
+        e1.set_title("t2".to_string());
+        e1.set_date(Date::from_str("2020-02-01").unwrap());
+        assert_eq!(Ok(true), w1.update(e1));
+
+        let e2 = w1.find(&id).unwrap();
+        assert_eq!("t2", e2.title());
+        assert_eq!("2020-02-01", e2.date().to_string());
+        assert_eq!(1, w1.filter(super::FilterOptions::new()).entries.len());
+
+        assert_eq!(Ok(true), w1.remove(&id));
+        assert_eq!(Ok(false), w1.remove(&id));
+        assert!(w1.find(&id).is_none());
+
+        // The ID is free again.
+        let mut e3 = Entry::new();
+        e3.set_id(id);
+        e3.set_date(Date::from_str("2020-01-05").unwrap());
+        assert!(matches!(w1.add(e3, false), AddResult::Added(_)));
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_search {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::date::Date;
+    use crate::search::Search;
+
+    #[test]
+    fn test_wallet_search1() {
+        remove_dir_all("../tmp/tests/wallet6").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet6".to_string());
+
+        for (title, comment, date) in &[("Coffee", "", "2020-01-05"), ("Breakfast", "coffee", "2020-02-01"), ("Tea", "", "2020-02-02")] {
+            let mut entry = Entry::new();
+            entry.set_title(title.to_string());
+            entry.set_comment(comment.to_string());
+            entry.set_date(Date::from_str(date).unwrap());
+            w1.add(entry, false);
+        }
+
+        let hits = w1.search(&Search::new("COFFEE"));
+        assert_eq!(2, hits.len());
+        assert_eq!("Coffee", hits[0].entry.title());
+        assert_eq!("month_2020_01.yml", hits[0].month_file);
+        assert_eq!("month_2020_02.yml", hits[1].month_file);
+
+        let mut o1 = FilterOptions::new();
+        o1.search = Some(Search::regex("^(tea|break)").unwrap());
+        assert_eq!(2, w1.filter(o1).entries.len());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_amount {
+    use std::fs::remove_dir_all;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_wallet_filter_amount1() {
+        remove_dir_all("../tmp/tests/wallet7").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet7".to_string());
+        w1.add(Entry::from("Salary/2020-01-01/150/0"), false);
+        w1.add(Entry::from("Rent/2020-01-02/0/600"), false);
+        w1.add(Entry::from("Coffee/2020-01-03/0/3"), false);
+
+        // Expenses above 500.
+        let mut o1 = FilterOptions::new();
+        o1.filter_expense = Some(true);
+        o1.min = Some(500.0);
+        let r1 = w1.filter(o1);
+        assert_eq!(1, r1.entries.len());
+        assert_eq!("Rent", r1.entries[0].title());
+
+        // Revenues between 100 and 200.
+        let mut o2 = FilterOptions::new();
+        o2.filter_revenue = Some(true);
+        o2.min = Some(100.0);
+        o2.max = Some(200.0);
+        let r2 = w1.filter(o2);
+        assert_eq!(1, r2.entries.len());
+        assert_eq!("Salary", r2.entries[0].title());
+
+        // Absolute balance.
+        let mut o3 = FilterOptions::new();
+        o3.max = Some(150.0);
+        assert_eq!(2, w1.filter(o3).entries.len());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_query {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::date::Date;
+    use crate::query::Expr;
+
+    #[test]
+    fn test_wallet_filter_query1() {
+        remove_dir_all("../tmp/tests/wallet8").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet8".to_string());
+
+        let mut e1 = Entry::from("Groceries/2020-01-05/0/60");
+        e1.set_category("food".to_string());
+        let mut e2 = Entry::from("Pizza refund/2020-01-06/10/0");
+        e2.set_category("food".to_string());
+        let mut e3 = Entry::from("Snack/2020-02-01/0/5");
+        e3.set_category("food".to_string());
+        e3.set_epic("trip".to_string());
+        w1.add(e1, false);
+        w1.add(e2, false);
+        w1.add(e3, false);
+
+        let mut o1 = FilterOptions::new();
+        o1.query = Some(Expr::from_str(r#"category:food and (amount>50 or epic:trip) and not title~"refund""#).unwrap());
+        let r1 = w1.filter(o1);
+        assert_eq!(2, r1.entries.len());
+
+        // Flags and query are combined.
+        let mut o2 = FilterOptions::new();
+        o2.date = Some(Date::from_str("2020-01").unwrap());
+        o2.query = Some(Expr::from_str("category:food").unwrap());
+        assert_eq!(2, w1.filter(o2).entries.len());
+    }
+
+    #[test]
+    fn test_filteroptions_to_expr1() {
+        let mut o1 = FilterOptions::new();
+        assert!(o1.to_expr().is_none());
+
+        o1.filter_expense = Some(true);
+        o1.category = Some("food".to_string());
+        o1.max = Some(10.0);
+        assert_eq!("((expense>0 and category:food) and expense<=10)", o1.to_expr().unwrap().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_sort {
+    use std::fs::remove_dir_all;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::sort::SortBy;
+
+    fn titles(w: &Wallet, options: FilterOptions) -> Vec<String> {
+        w.filter(options).entries.iter().map(|entry| entry.title()).collect()
+    }
+
+    #[test]
+    fn test_wallet_filter_sort1() {
+        remove_dir_all("../tmp/tests/wallet9").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet9".to_string());
+        w1.add(Entry::from("c/2020-02-01/0/1"), false);
+        w1.add(Entry::from("b/2020-01-20/0/30"), false);
+        w1.add(Entry::from("a/2020-01-03/20/0"), false);
+
+        // Chronological by default.
+        assert_eq!(vec!["a", "b", "c"], titles(&w1, FilterOptions::new()));
+
+        let mut o1 = FilterOptions::new();
+        o1.sort = SortBy::Amount;
+        o1.reverse = true;
+        assert_eq!(vec!["b", "a", "c"], titles(&w1, o1));
+    }
+}
+
 #[cfg(test)]
 mod tests_wallet_epic {
-    use super::Wallet;
-    use crate::epic::Epic;
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, FilterOptions};
+    use crate::epic::{Epic, EpicError};
+    use crate::entry::Entry;
+    use crate::date::Date;
 
     #[test]
     fn test_wallet_epic_add() {
+        remove_dir_all("../tmp/tests/wallet3").ok();
+
         let mut e1 = Epic::new();
         e1.set_handle("h1".to_string());
         e1.set_title("t1".to_string());
@@ -934,4 +2164,664 @@ mod tests_wallet_epic {
         let w1 = Wallet::new("../tmp/tests/wallet3".to_string());
         assert!(w1.add_epic(e1));
     }
+
+    #[test]
+    fn test_wallet_epics_categories() {
+        let mut e1 = Epic::new();
+        e1.set_handle("h1".to_string());
+
+        let mut n1 = Entry::new();
+        n1.set_date(Date::from_str("2020-01-05").unwrap());
+        n1.set_category("food".to_string());
+        n1.set_epic("trip".to_string());
+
+        let w1 = Wallet::new("../tmp/tests/wallet4".to_string());
+        w1.add_epic(e1);
+        w1.add(n1, false);
+
+        let handles: Vec<String> = w1.epics().iter().map(|e| e.handle()).collect();
+        assert_eq!(vec!["h1", "trip"], handles);
+        assert_eq!(vec!["food"], w1.categories());
+    }
+
+    fn epic_of(w: &Wallet, title: &str) -> String {
+        w.filter(FilterOptions::new()).entries.iter()
+            .find(|entry| entry.title() == title)
+            .map(|entry| entry.epic())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wallet_epic_manage() {
+        remove_dir_all("../tmp/tests/wallet10").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet10".to_string());
+
+        let mut n1 = Entry::from("a/2020-01-05/0/10");
+        n1.set_epic("trip".to_string());
+        let mut n2 = Entry::from("b/2020-02-05/0/10");
+        n2.set_epic("trip".to_string());
+        let mut n3 = Entry::from("c/2020-02-06/0/10");
+        n3.set_epic("car".to_string());
+        w1.add(n1, false);
+        w1.add(n2, false);
+        w1.add(n3, false);
+
+        // Edit
+        let mut e1 = Epic::new();
+        e1.set_handle("trip".to_string());
+        e1.set_title("Trip".to_string());
+        assert_eq!(Ok(()), w1.update_epic(e1));
+        assert_eq!(Err(EpicError::NotFound("x".to_string())), w1.update_epic({
+            let mut e = Epic::new();
+            e.set_handle("x".to_string());
+            e
+        }));
+
+        // Rename
+        assert_eq!(Err(EpicError::Exists("car".to_string())), w1.rename_epic("trip", "car"));
+        assert_eq!(Ok(2), w1.rename_epic("trip", "italy"));
+        assert_eq!("italy", epic_of(&w1, "a"));
+        assert_eq!("Trip", w1.find_epic("italy").unwrap().title());
+
+        // Remove
+        assert_eq!(Err(EpicError::InUse("car".to_string(), 1)), w1.remove_epic("car", false));
+        assert_eq!(Ok(1), w1.remove_epic("car", true));
+        assert_eq!("default", epic_of(&w1, "c"));
+
+        // Merge
+        let mut e2 = Epic::new();
+        e2.set_handle("travel".to_string());
+        w1.add_epic(e2);
+        assert_eq!(Ok(2), w1.merge_epics("italy", "travel"));
+        assert_eq!("travel", epic_of(&w1, "b"));
+        assert!(w1.find_epic("italy").is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_category {
+    use std::fs::remove_dir_all;
+    use super::{Wallet, FilterOptions, CategoryError};
+    use crate::entry::Entry;
+
+    fn category_of(w: &Wallet, title: &str) -> String {
+        w.filter(FilterOptions::new()).entries.iter()
+            .find(|entry| entry.title() == title)
+            .map(|entry| entry.category())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wallet_category_manage() {
+        remove_dir_all("../tmp/tests/wallet11").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet11".to_string());
+
+        let mut n1 = Entry::from("a/2020-01-05/0/10");
+        n1.set_category("groceris".to_string());
+        let mut n2 = Entry::from("b/2020-02-05/0/10");
+        n2.set_category("groceris".to_string());
+        let mut n3 = Entry::from("c/2020-02-06/0/10");
+        n3.set_category("car".to_string());
+        w1.add(n1, false);
+        w1.add(n2, false);
+        w1.add(n3, false);
+
+        // Rename
+        assert_eq!(Err(CategoryError::Exists("car".to_string())), w1.rename_category("groceris", "car"));
+        assert_eq!(Err(CategoryError::NotFound("x".to_string())), w1.rename_category("x", "y"));
+        assert_eq!(Ok(2), w1.rename_category("groceris", "groceries"));
+        assert_eq!("groceries", category_of(&w1, "a"));
+        assert_eq!("groceries", category_of(&w1, "b"));
+
+        // Merge
+        assert_eq!(Err(CategoryError::Same), w1.merge_categories("car", "car"));
+        assert_eq!(Err(CategoryError::NotFound("x".to_string())), w1.merge_categories("car", "x"));
+        assert_eq!(Ok(1), w1.merge_categories("car", "groceries"));
+        assert_eq!(vec!["groceries"], w1.categories());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_bulk_edit {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::{Entry, EntryChanges, EntryField};
+    use crate::query::Expr;
+
+    #[test]
+    fn test_wallet_bulk_edit() {
+        remove_dir_all("../tmp/tests/wallet12").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet12".to_string());
+
+        w1.add(Entry::from("Netflix/2019-01-03/0/10"), false);
+        w1.add(Entry::from("Netflix Premium/2019-06-03/0/15"), false);
+        w1.add(Entry::from("Netflix/2020-01-03/0/15"), false);
+        w1.add(Entry::from("Food/2019-06-04/0/20"), false);
+
+        let o1 = || {
+            let mut options = FilterOptions::new();
+            options.query = Some(Expr::from_str("title~netflix and date:2019").unwrap());
+            options
+        };
+
+        let mut c1 = EntryChanges::new();
+        c1.category = Some("subscriptions".to_string());
+
+        let p1 = w1.bulk_edit_preview(o1(), &c1);
+        assert_eq!(2, p1.len());
+        assert_eq!(EntryField::Category, p1[0].1[0].field);
+
+        assert_eq!(Ok(2), w1.bulk_edit(o1(), &c1));
+        assert_eq!(Ok(0), w1.bulk_edit(o1(), &c1));
+
+        let mut categories: Vec<(String, String)> = w1.filter(FilterOptions::new()).entries.iter()
+            .map(|entry| (entry.date().ymd(), entry.category()))
+            .collect();
+        categories.sort();
+        assert_eq!(vec![
+            ("2019-01-03".to_string(), "subscriptions".to_string()),
+            ("2019-06-03".to_string(), "subscriptions".to_string()),
+            ("2019-06-04".to_string(), "default".to_string()),
+            ("2020-01-03".to_string(), "default".to_string()),
+        ], categories);
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_history {
+    use std::fs::remove_dir_all;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::history::HistoryError;
+
+    fn titles(w: &Wallet) -> Vec<String> {
+        let mut titles: Vec<String> = w.filter(FilterOptions::new()).entries.iter()
+            .map(|entry| entry.title())
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn test_wallet_undo_redo() {
+        remove_dir_all("../tmp/tests/wallet13").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet13".to_string());
+
+        w1.add(Entry::from("Groceries/2020-01-05/0/23.4"), false);
+        w1.add(Entry::from("Rent/2020-02-01/0/500"), false);
+
+        // Nested operations are recorded once.
+        w1.record("import: x".to_string(), || {
+            w1.add(Entry::from("a/2020-02-02/0/1"), false);
+            w1.add(Entry::from("b/2020-03-02/0/1"), false);
+        });
+
+        let history = w1.history();
+        let names: Vec<String> = history.operations().iter().map(|(operation, _)| operation.name.clone()).collect();
+        assert_eq!(vec!["add: Groceries 2020-01-05 -23.40", "add: Rent 2020-02-01 -500.00", "import: x"], names);
+
+        assert_eq!("import: x", w1.undo().unwrap().name);
+        assert_eq!(vec!["Groceries", "Rent"], titles(&w1));
+        assert!(w1.filter(FilterOptions::new()).entries.iter().all(|entry| entry.date().month() < 3));
+
+        assert_eq!("add: Rent 2020-02-01 -500.00", w1.undo().unwrap().name);
+        assert_eq!(vec!["Groceries"], titles(&w1));
+
+        assert_eq!("add: Rent 2020-02-01 -500.00", w1.redo().unwrap().name);
+        assert_eq!(vec!["Groceries", "Rent"], titles(&w1));
+
+        // A new operation drops the undone ones.
+        w1.add(Entry::from("c/2020-01-06/0/1"), false);
+        assert_eq!(Err(HistoryError::NothingToRedo), w1.redo().map(|operation| operation.name));
+        assert_eq!(3, w1.history().operations().len());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_html {
+    use std::fs::{read_to_string, remove_dir_all};
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_wallet_html_carry_over() {
+        remove_dir_all("../tmp/tests/wallet_html1").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet_html1".to_string());
+        w1.add(Entry::from("Salary/2019-12-31/1000/0"), false);
+        w1.add(Entry::from("Rent/2020-01-01/0/600"), false);
+        w1.add(Entry::from("Food/2020-02-03/0/50"), false);
+
+        w1.html(FilterOptions::new(), true);
+        let m01 = read_to_string("../tmp/tests/wallet_html1/html/year/2020/month_01.html").unwrap();
+        let m02 = read_to_string("../tmp/tests/wallet_html1/html/year/2020/month_02.html").unwrap();
+        assert!(m01.contains("1000.00"));
+        assert!(m01.contains("400.00"));
+        assert!(m02.contains("350.00"));
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_git {
+    use std::fs::remove_dir_all;
+    use super::Wallet;
+    use crate::entry::Entry;
+
+    #[test]
+    fn test_wallet_git() {
+        remove_dir_all("../tmp/tests/wallet14").ok();
+
+        let mut w1 = Wallet::new("../tmp/tests/wallet14".to_string());
+        assert!(w1.git().is_none());
+        w1.init_git().unwrap();
+
+        w1.add(Entry::from("Groceries/2020-01-05/0/23.4"), false);
+        w1.undo().unwrap();
+
+        // Enabled by the marker in the git config.
+        let w2 = Wallet::new("../tmp/tests/wallet14".to_string());
+        let messages: Vec<String> = w2.git().unwrap().log(10).unwrap().into_iter()
+            .map(|commit| commit.message)
+            .collect();
+        assert_eq!(vec!["undo: add: Groceries 2020-01-05 -23.40", "add: Groceries 2020-01-05 -23.40", "init"], messages);
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_merge {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::date::Date;
+    use crate::chain::SealError;
+    use crate::merge::{MergeStrategy, Resolution};
+
+    fn wallets(n: u8) -> (Wallet, Wallet) {
+        let ours = format!("../tmp/tests/wallet_merge{}a", n);
+        let theirs = format!("../tmp/tests/wallet_merge{}b", n);
+        remove_dir_all(&ours).ok();
+        remove_dir_all(&theirs).ok();
+
+        let w1 = Wallet::new(ours);
+        let w2 = Wallet::new(theirs);
+
+        let mut e1 = Entry::from("Rent/2020-01-01/0/500");
+        e1.set_id("e1".to_string());
+        let mut e2 = Entry::from("Food/2020-01-02/0/20");
+        e2.set_id("e2".to_string());
+        w1.add(e1.clone(), false);
+        w1.add(e2.clone(), false);
+
+        e2.set_title("Groceries".to_string());
+        let mut e3 = Entry::from("Salary/2020-02-01/1000/0");
+        e3.set_id("e3".to_string());
+        e3.set_epic("job".to_string());
+        w2.add(e1, false);
+        w2.add(e2, false);
+        w2.add(e3, false);
+
+        (w1, w2)
+    }
+
+    fn title_of(w: &Wallet, id: &str) -> String {
+        w.find(id).unwrap().title()
+    }
+
+    #[test]
+    fn test_wallet_merge_ours() {
+        let (w1, w2) = wallets(1);
+
+        let r1 = w1.merge(&w2, MergeStrategy::Ours, |_, _| unreachable!());
+        assert_eq!(1, r1.added.len());
+        assert_eq!(1, r1.unchanged);
+        assert_eq!(1, r1.conflicts.len());
+        assert_eq!(vec!["job"], r1.epics);
+        assert_eq!("Food", title_of(&w1, "e2"));
+        assert_eq!(3, w1.filter(FilterOptions::new()).entries.len());
+
+        // Nothing left to add.
+        let r2 = w1.merge(&w2, MergeStrategy::Ours, |_, _| unreachable!());
+        assert_eq!(0, r2.added.len());
+        assert_eq!(2, r2.unchanged);
+    }
+
+    #[test]
+    fn test_wallet_merge_theirs() {
+        let (w1, w2) = wallets(2);
+
+        let r1 = w1.merge(&w2, MergeStrategy::Interactive, |ours, theirs| {
+            assert_eq!("Food", ours.title());
+            assert_eq!("Groceries", theirs.title());
+            Resolution::Theirs
+        });
+        assert_eq!(Resolution::Theirs, r1.conflicts[0].resolution);
+        assert_eq!("Groceries", title_of(&w1, "e2"));
+    }
+
+    #[test]
+    fn test_wallet_merge_newest() {
+        remove_dir_all("../tmp/tests/wallet_merge3a").ok();
+        remove_dir_all("../tmp/tests/wallet_merge3b").ok();
+        let w1 = Wallet::new("../tmp/tests/wallet_merge3a".to_string());
+        let w2 = Wallet::new("../tmp/tests/wallet_merge3b".to_string());
+
+        // Changed by them after us, then an unrelated change in our month.
+        let mut ours = Entry::from("Food/2020-01-02/0/20");
+        ours.set_id("e2".to_string());
+        ours.set_updated_at(Some("2020-01-03T10:00:00+00:00".to_string()));
+        let mut theirs = ours.clone();
+        theirs.set_title("Groceries".to_string());
+        theirs.set_updated_at(Some("2020-01-04T10:00:00+00:00".to_string()));
+        w1.add(ours, false);
+        w2.add(theirs, false);
+        w1.add(Entry::from("Coffee/2020-01-05/0/3"), false);
+
+        let r1 = w1.merge(&w2, MergeStrategy::Newest, |_, _| unreachable!());
+        assert_eq!(Resolution::Theirs, r1.conflicts[0].resolution);
+        assert_eq!("Groceries", title_of(&w1, "e2"));
+    }
+
+    #[test]
+    fn test_wallet_merge_failed() {
+        let (w1, w2) = wallets(4);
+        assert!(w1.close(&Date::from_str("2020-01").unwrap()));
+
+        let r1 = w1.merge(&w2, MergeStrategy::Theirs, |_, _| unreachable!());
+        assert!(r1.conflicts.is_empty());
+        assert_eq!(1, r1.failed.len());
+        assert_eq!("e2", r1.failed[0].entry.id());
+        assert_eq!(SealError::Closed("2020-01".to_string()), r1.failed[0].error);
+        assert_eq!("Food", title_of(&w1, "e2"));
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_sync {
+    use std::fs::remove_dir_all;
+    use std::str::FromStr;
+    use super::Wallet;
+    use crate::entry::Entry;
+    use crate::date::Date;
+
+    fn entry(id: &str, s: &str) -> Entry {
+        let mut entry = Entry::from(s);
+        entry.set_id(id.to_string());
+        entry
+    }
+
+    fn title_of(w: &Wallet, id: &str) -> Option<String> {
+        w.find(id).map(|entry| entry.title())
+    }
+
+    #[test]
+    fn test_wallet_sync() {
+        remove_dir_all("../tmp/tests/wallet_sync1a").ok();
+        remove_dir_all("../tmp/tests/wallet_sync1b").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet_sync1a".to_string());
+        let w2 = Wallet::new("../tmp/tests/wallet_sync1b".to_string());
+
+        w1.add(entry("a", "A/2020-01-01/0/1"), false);
+        w1.add(entry("b", "B/2020-01-02/0/2"), false);
+        w2.add(entry("c", "C/2020-02-03/0/3"), false);
+
+        // First sync copies everything.
+        let r1 = w1.sync(&w2);
+        assert_eq!(1, r1.pulled.len());
+        assert_eq!(2, r1.pushed.len());
+        assert_eq!(Some("C".to_string()), title_of(&w1, "c"));
+        assert_eq!(Some("A".to_string()), title_of(&w2, "a"));
+
+        // Edit, remove and conflict.
+        w1.update(entry("a", "A1/2020-01-01/0/1")).unwrap();
+        w2.remove("b").unwrap();
+        w1.update(entry("c", "C1/2020-02-03/0/3")).unwrap();
+        w2.update(entry("c", "C2/2020-02-03/0/3")).unwrap();
+
+        // Synced from the other side.
+        let r2 = w2.sync(&w1);
+        assert_eq!(1, r2.pulled.len());
+        assert_eq!(1, r2.pushed.len());
+        assert_eq!(1, r2.conflicts.len());
+        assert_eq!(Some("A1".to_string()), title_of(&w2, "a"));
+        assert_eq!(None, title_of(&w1, "b"));
+        assert_eq!(Some("C1".to_string()), title_of(&w1, "c"));
+        assert_eq!(Some("C2".to_string()), title_of(&w2, "c"));
+
+        // The conflict is reported again until both sides are equal.
+        assert_eq!(1, w1.sync(&w2).conflicts.len());
+        w2.update(entry("c", "C1/2020-02-03/0/3")).unwrap();
+        let r3 = w1.sync(&w2);
+        assert!(r3.conflicts.is_empty());
+        assert!(r3.pulled.is_empty());
+        assert!(r3.pushed.is_empty());
+
+        // A change in a sealed month fails and is tried again.
+        w2.seal(&Date::from_str("2020-01").unwrap()).unwrap();
+        w1.update(entry("a", "A2/2020-01-01/0/1")).unwrap();
+        let r4 = w1.sync(&w2);
+        assert!(r4.pushed.is_empty());
+        assert_eq!(1, r4.failed.len());
+        assert_eq!("a", r4.failed[0].change.id());
+        assert_eq!(Some("A1".to_string()), title_of(&w2, "a"));
+        assert_eq!(1, w1.sync(&w2).failed.len());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_crypt {
+    use std::fs::{read_to_string, remove_dir_all, remove_file, write};
+    use std::path::Path;
+    use super::{Wallet, FilterOptions};
+    use crate::entry::Entry;
+    use crate::crypt::{self, CryptError};
+
+    #[test]
+    fn test_wallet_crypt() {
+        remove_dir_all("../tmp/tests/wallet15").ok();
+
+        let w1 = Wallet::new("../tmp/tests/wallet15".to_string());
+        w1.add(Entry::from("Groceries/2020-01-05/0/23.4"), false);
+        assert!(!w1.is_encrypted());
+        assert_eq!(Err(CryptError::NotEncrypted), w1.decrypt());
+
+        // Encrypt
+        assert_eq!(Ok(3), w1.encrypt("secret"));
+        assert_eq!(Err(CryptError::AlreadyEncrypted), w1.encrypt("secret"));
+        let raw = read_to_string("../tmp/tests/wallet15/data/month_2020_01.yml").unwrap();
+        assert!(raw.starts_with("wallet-encrypted:1:"));
+        assert!(!raw.contains("Groceries"));
+        assert!(!Path::new("../tmp/tests/wallet15/data/month_2020_01.yml.tmp").exists());
+        assert_eq!(0, w1.history().operations().len());
+        assert_eq!(Ok(()), w1.check_files());
+
+        // Plain file
+        write("../tmp/tests/wallet15/data/month_2020_02.yml", "days: {}\n").unwrap();
+        assert_eq!(Err(CryptError::Plain("month_2020_02.yml".to_string())), w1.check_files());
+        remove_file("../tmp/tests/wallet15/data/month_2020_02.yml").unwrap();
+
+        // Written encrypted
+        w1.add(Entry::from("Rent/2020-01-10/0/500"), false);
+        let raw = read_to_string("../tmp/tests/wallet15/data/month_2020_01.yml").unwrap();
+        assert!(!raw.contains("Rent"));
+
+        // Locked
+        crypt::lock(Path::new("../tmp/tests/wallet15/data"));
+        let w2 = Wallet::new("../tmp/tests/wallet15".to_string());
+        assert!(w2.is_locked());
+        assert_eq!(Err(CryptError::WrongPassphrase), w2.unlock("wrong"));
+        assert_eq!(Ok(()), w2.unlock("secret"));
+        assert_eq!(2, w2.filter(FilterOptions::new()).entries.len());
+
+        // Decrypt
+        assert_eq!(Ok(3), w2.decrypt());
+        assert!(!w2.is_encrypted());
+        let raw = read_to_string("../tmp/tests/wallet15/data/month_2020_01.yml").unwrap();
+        assert!(raw.contains("Groceries"));
+        assert!(raw.contains("Rent"));
+
+        // Git keeps the plain files.
+        let mut w3 = Wallet::new("../tmp/tests/wallet15".to_string());
+        w3.init_git().unwrap();
+        assert_eq!(Err(CryptError::Git), w3.encrypt("secret"));
+        assert!(!w3.is_encrypted());
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_chain {
+    use std::fs::{read_to_string, remove_dir_all, write};
+    use std::str::FromStr;
+    use super::{Wallet, AddResult, CategoryError, FilterOptions};
+    use crate::entry::Entry;
+    use crate::date::Date;
+    use crate::chain::{ChainBreak, SealError};
+
+    fn month(s: &str) -> Date {
+        Date::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_wallet_chain() {
+        remove_dir_all("../tmp/tests/wallet16").ok();
+
+        let mut w1 = Wallet::new("../tmp/tests/wallet16".to_string());
+        w1.add(Entry::from("Groceries/2020-02-05/0/23.4"), false);
+        w1.add(Entry::from("Rent/2020-03-01/0/500"), false);
+        w1.add(Entry::from("Salary/2020-01-31/1000/0"), false);
+
+        // Jan was added before Feb, so Feb and Mar were linked again.
+        let s1 = w1.verify();
+        assert_eq!(vec!["2020-01", "2020-02", "2020-03"], s1.iter().map(|status| status.month.as_str()).collect::<Vec<&str>>());
+        assert!(s1.iter().all(|status| status.error.is_none()));
+
+        // Seal
+        assert_eq!(Err(SealError::NotFound("2021-01".to_string())), w1.seal(&month("2021-01")));
+        assert_eq!(Ok(vec!["2020-01".to_string(), "2020-02".to_string()]), w1.seal(&month("2020-02")));
+        assert_eq!(Ok(vec![]), w1.seal(&month("2020-01")));
+        assert_eq!(Some("2020-02".to_string()), w1.sealed_until());
+
+        // Read-only
+        assert!(matches!(w1.add(Entry::from("Coffee/2020-02-06/0/3"), false), AddResult::ReadOnly(SealError::Sealed(month)) if month == "2020-02"));
+        assert!(matches!(w1.add(Entry::from("Coffee/2019-12-06/0/3"), false), AddResult::ReadOnly(_)));
+        assert!(matches!(w1.add(Entry::from("Coffee/2020-03-06/0/3"), false), AddResult::Added(_)));
+
+        let e1 = w1.filter(FilterOptions::new()).entries.iter().find(|entry| entry.title() == "Groceries").unwrap().as_ref().clone();
+        assert_eq!(Err(SealError::Sealed("2020-02".to_string())), w1.remove(&e1.id()));
+        assert_eq!(Err(CategoryError::Sealed(SealError::Sealed("2020-01".to_string()))), w1.rename_category("default", "other"));
+        assert_eq!(vec!["default".to_string()], w1.categories());
+
+        // Unsealed
+        w1.set_unseal(true);
+        let mut e2 = e1.clone();
+        e2.set_title("Food".to_string());
+        assert_eq!(Ok(true), w1.update(e2));
+        assert!(w1.verify().iter().all(|status| status.error.is_none()));
+
+        // Changed outside of the wallet.
+        let path = "../tmp/tests/wallet16/data/month_2020_02.yml";
+        write(path, read_to_string(path).unwrap().replace("Food", "Fool")).unwrap();
+        let s2 = w1.verify();
+        assert_eq!(Some(ChainBreak::Content), s2[1].error);
+        assert_eq!(None, s2[2].error);
+        assert_eq!(Err(SealError::Broken("2020-02".to_string())), w1.seal(&month("2020-03")));
+
+        // The break stays after writing other months.
+        w1.add(Entry::from("Bonus/2020-01-15/100/0"), false);
+        assert_eq!(Some(ChainBreak::Content), w1.verify()[1].error);
+
+        // The broken month is read-only and keeps its break when unsealed.
+        w1.set_unseal(false);
+        assert!(matches!(w1.add(Entry::from("Coffee/2020-02-07/0/3"), false), AddResult::ReadOnly(SealError::Broken(month)) if month == "2020-02"));
+        w1.set_unseal(true);
+        assert!(matches!(w1.add(Entry::from("Coffee/2020-02-07/0/3"), false), AddResult::Added(_)));
+        assert_eq!(Some(ChainBreak::Content), w1.verify()[1].error);
+
+        // The seal is part of the hash.
+        let path = "../tmp/tests/wallet16/data/month_2020_01.yml";
+        assert_eq!(None, w1.verify()[0].error);
+        write(path, read_to_string(path).unwrap().replace("sealed_at", "unsealed_at")).unwrap();
+        assert_eq!(Some(ChainBreak::Content), w1.verify()[0].error);
+    }
+}
+
+#[cfg(test)]
+mod tests_wallet_close {
+    use std::fs::{read_to_string, remove_dir_all, write};
+    use std::str::FromStr;
+    use super::{Wallet, AddResult, FilterOptions};
+    use crate::entry::{Entry, EntryChanges};
+    use crate::date::Date;
+    use crate::chain::{ChainBreak, SealError};
+    use crate::history::HistoryError;
+
+    #[test]
+    fn test_wallet_close() {
+        remove_dir_all("../tmp/tests/wallet17").ok();
+
+        let mut w1 = Wallet::new("../tmp/tests/wallet17".to_string());
+        assert!(w1.closed_months().is_empty());
+
+        w1.add(Entry::from("Rent/2020-03-01/0/500"), false);
+        let mut e1 = Entry::from("Coffee/2020-02-10/0/3");
+        w1.add(e1.clone(), false);
+
+        // Close
+        assert!(w1.close(&Date::from_str("2020-03").unwrap()));
+        assert!(!w1.close(&Date::from_str("2020-03").unwrap()));
+        assert_eq!(vec!["2020-03".to_string()], w1.closed_months());
+
+        // Only the touched files are recorded.
+        let history = w1.history();
+        let operations = history.operations();
+        let files: Vec<&str> = operations.last().unwrap().0.files.iter().map(|change| change.file.as_str()).collect();
+        assert_eq!(vec!["index.yml", "month_2020_03.yml"], files);
+
+        // The close cannot be undone.
+        assert!(matches!(w1.undo(), Err(HistoryError::ReadOnly(SealError::Closed(month))) if month == "2020-03"));
+        assert_eq!(vec!["2020-03".to_string()], w1.closed_months());
+
+        // Read-only
+        assert!(matches!(w1.add(Entry::from("Food/2020-03-05/0/20"), false), AddResult::ReadOnly(SealError::Closed(month)) if month == "2020-03"));
+        assert!(matches!(w1.add(Entry::from("Food/2020-04-05/0/20"), false), AddResult::Added(_)));
+
+        e1.set_date(Date::from_str("2020-03-10").unwrap());
+        assert_eq!(Err(SealError::Closed("2020-03".to_string())), w1.update(e1.clone()));
+
+        let rent = w1.filter(FilterOptions::new()).entries.iter().find(|entry| entry.title() == "Rent").unwrap().id();
+        assert_eq!(Err(SealError::Closed("2020-03".to_string())), w1.remove(&rent));
+
+        let mut c1 = EntryChanges::new();
+        c1.category = Some("home".to_string());
+        assert_eq!(Err(SealError::Closed("2020-03".to_string())), w1.bulk_edit(FilterOptions::new(), &c1));
+        assert_eq!(vec!["default".to_string()], w1.categories());
+
+        // Unsealed
+        w1.set_unseal(true);
+        assert_eq!(Ok(true), w1.update(e1));
+        assert_eq!(Ok(3), w1.bulk_edit(FilterOptions::new(), &c1));
+
+        // Badge
+        w1.html(FilterOptions::new(), false);
+        let m03 = read_to_string("../tmp/tests/wallet17/html/year/2020/month_03.html").unwrap();
+        let m04 = read_to_string("../tmp/tests/wallet17/html/year/2020/month_04.html").unwrap();
+        assert!(m03.contains("<span class=\"badge\">closed</span>"));
+        assert!(!m04.contains("<span class=\"badge\">closed</span>"));
+
+        // The close is part of the hash.
+        assert!(w1.verify().iter().all(|status| status.error.is_none()));
+        let index = "../tmp/tests/wallet17/data/index.yml";
+        write(index, read_to_string(index).unwrap().replace("closed", "opened")).unwrap();
+        assert_eq!(vec!["2020-03".to_string()], w1.closed_months());
+
+        let path = "../tmp/tests/wallet17/data/month_2020_03.yml";
+        write(path, read_to_string(path).unwrap().replace("closed_at", "opened_at")).unwrap();
+        assert_eq!(Some(ChainBreak::Content), w1.verify()[1].error);
+    }
 }
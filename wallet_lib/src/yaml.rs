@@ -1,11 +1,14 @@
 
-use std::path::PathBuf;
-use std::fs::{read_to_string, File};
-use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::fs::{read_dir, read_to_string, rename, remove_file, File};
+use std::io::{self, Write};
 use std::string::ToString;
 use yaml_rust::{Yaml, YamlLoader, YamlEmitter};
 use yaml_rust::yaml::Hash;
 use chrono::{DateTime, Utc};
+use crate::crypt::{self, CryptError};
+use crate::chain::{self, Link};
+use crate::history;
 
 #[derive(Debug)]
 enum YamlFileKind {
@@ -19,6 +22,14 @@ pub struct YamlFile {
     path: PathBuf,
     changed: bool,
     content: Yaml,
+    /// Month file changed outside of the wallet.
+    broken: bool,
+}
+
+/// Plain content of a data file.
+fn decode_file(path: &Path) -> Result<String, CryptError> {
+    let raw = read_to_string(path).expect("Cannot read file");
+    crypt::decode(path, raw)
 }
 
 impl YamlFile {
@@ -45,17 +56,24 @@ impl YamlFile {
             path,
             changed: false,
             content: Yaml::Hash(Hash::new()),
+            broken: false,
         };
-        _f.init();
+        _f.init().unwrap_or_else(|error| panic!("{}", error));
         _f
     }
 
-    fn init(&mut self) {
+    /// Fails when a data file cannot be decoded, like a plain file in an
+    /// encrypted wallet.
+    pub fn check(path: &Path) -> Result<(), CryptError> {
+        decode_file(path).map(|_| ())
+    }
+
+    fn init(&mut self) -> Result<(), CryptError> {
         println!("-> YamlFile::init()");
 
         if self.path.exists() && self.path.is_file() {
             // println!("-> read existing file");
-            self.read();
+            self.read()?;
         } else {
             println!("-> create new file");
 
@@ -99,17 +117,22 @@ impl YamlFile {
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn read(&mut self) {
+    fn read(&mut self) -> Result<(), CryptError> {
         println!("-> YamlFile::read()");
-        let raw = read_to_string(&self.path).expect("Cannot read file");
+        let raw = decode_file(&self.path)?;
         // println!("-> raw: '{}'", raw);
 
         let docs = YamlLoader::load_from_str(&raw).unwrap();
         // println!("-> docs: '{:?}'", docs);
 
         self.content = docs[0].clone();
+        self.broken = self.link().is_some_and(|link| link.is_broken());
+
+        Ok(())
     }
 
     pub fn add<T: ToYaml>(&mut self, obj: T) {
@@ -259,6 +282,283 @@ impl YamlFile {
         false
     }
 
+    /// Remove an item. For the index file the ID itself, for the epics
+    /// file the Epic handle and for month files the Entry ID.
+    pub fn remove<T: ToYaml>(&mut self, id: T) -> bool {
+        println!("-> YamlFile::remove() -> {:?}", self.kind);
+
+        let idy = id.to_yaml();
+        let mut removed = false;
+
+        if let Yaml::Hash(ref mut content_ref) = self.content {
+            match &self.kind {
+                YamlFileKind::IndexFile => {
+                    let index_key = "index".to_string().to_yaml();
+
+                    if let Yaml::Array(ref mut index_ref) = content_ref[&index_key] {
+                        let len = index_ref.len();
+                        index_ref.retain(|x| x != &idy);
+                        removed = index_ref.len() != len;
+                    }
+                },
+                YamlFileKind::EpicsFile => {
+                    let index_key = "epics".to_string().to_yaml();
+
+                    if let Yaml::Array(ref mut index_ref) = content_ref[&index_key] {
+                        let len = index_ref.len();
+                        index_ref.retain(|x| x["handle"] != idy);
+                        removed = index_ref.len() != len;
+                    }
+                },
+                YamlFileKind::MonthFile => {
+                    let index_key = "days".to_string().to_yaml();
+
+                    if let Some(Yaml::Hash(ref mut index_ref)) = content_ref.get_mut(&index_key) {
+                        for (_, day) in index_ref.iter_mut() {
+                            if let Yaml::Array(ref mut day_ref) = day {
+                                let len = day_ref.len();
+                                day_ref.retain(|x| x["id"] != idy);
+                                removed |= day_ref.len() != len;
+                            }
+                        }
+
+                        // Remove empty days.
+                        let empty: Vec<Yaml> = index_ref.iter()
+                            .filter(|(_, day)| matches!(day, Yaml::Array(day_ref) if day_ref.is_empty()))
+                            .map(|(key, _)| key.clone())
+                            .collect();
+                        for key in empty {
+                            index_ref.remove(&key);
+                        }
+                    }
+                },
+            }
+        }
+
+        if removed {
+            self.changed = true;
+        }
+
+        removed
+    }
+
+    /// Replace an item in place. Epics are matched by handle, Entries by
+    /// ID. An Entry with a changed date is moved to its new day.
+    pub fn update<T: ToYaml>(&mut self, obj: T) -> bool {
+        println!("-> YamlFile::update() -> {:?}", self.kind);
+
+        let v = obj.to_yaml();
+        let mut updated = false;
+        let mut moved = false;
+
+        if let Yaml::Hash(ref mut content_ref) = self.content {
+            match &self.kind {
+                YamlFileKind::EpicsFile => {
+                    let index_key = "epics".to_string().to_yaml();
+
+                    if let Some(Yaml::Array(ref mut index_ref)) = content_ref.get_mut(&index_key) {
+                        if let Some(item) = index_ref.iter_mut().find(|x| x["handle"] == v["handle"]) {
+                            *item = v.clone();
+                            updated = true;
+                        }
+                    }
+                },
+                YamlFileKind::MonthFile => {
+                    let index_key = "days".to_string().to_yaml();
+
+                    if let Some(Yaml::Hash(ref mut index_ref)) = content_ref.get_mut(&index_key) {
+                        for (day_key, day) in index_ref.iter_mut() {
+                            if let Yaml::Array(ref mut day_ref) = day {
+                                if let Some(item) = day_ref.iter_mut().find(|x| x["id"] == v["id"]) {
+                                    if day_key == &v["date"] {
+                                        *item = v.clone();
+                                        updated = true;
+                                    } else {
+                                        moved = true;
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                },
+                _ => unreachable!("Yaml::update() not implemented for {:?}", self.kind),
+            }
+        }
+
+        if moved {
+            if let Yaml::Hash(hash) = v.clone() {
+                self.remove(v["id"].as_str().unwrap_or_default().to_string());
+                self.add(hash);
+                updated = true;
+            }
+        }
+
+        if updated {
+            self.changed = true;
+        }
+
+        updated
+    }
+
+    /// Last update of a month file, like `2020-01-05T10:00:00+00:00`.
+    pub fn updated_at(&self) -> Option<String> {
+        match self.kind {
+            YamlFileKind::MonthFile => {
+                let meta = &self.content["meta"];
+                meta["updated_at"].as_str().map(|updated_at| updated_at.to_string())
+            },
+            _ => None,
+        }
+    }
+
+    fn meta(&self, key: &str) -> Option<String> {
+        self.content["meta"][key].as_str().map(|value| value.to_string())
+    }
+
+    fn set_meta(&mut self, key: &str, value: String) {
+        if let Yaml::Hash(ref mut content_ref) = self.content {
+            if let Some(Yaml::Hash(meta)) = content_ref.get_mut(&"meta".to_string().to_yaml()) {
+                meta.insert(key.to_string().to_yaml(), value.to_yaml());
+            }
+        }
+    }
+
+    fn dump_days(&self) -> String {
+        let mut out_str = String::new();
+        {
+            let mut emitter = YamlEmitter::new(&mut out_str);
+            if emitter.dump(&self.content["days"]).is_err() {
+                return String::new();
+            }
+        }
+        out_str
+    }
+
+    /// Hash chain fields of a month file.
+    pub fn link(&self) -> Option<Link> {
+        match self.kind {
+            YamlFileKind::MonthFile => {
+                let prev_hash = self.meta("prev_hash");
+                let sealed_at = self.meta("sealed_at");
+                let closed_at = self.meta("closed_at");
+                Some(Link {
+                    hash: self.meta("hash"),
+                    computed: chain::hash(prev_hash.as_deref().unwrap_or_default(), &self.dump_days(), sealed_at.as_deref(), closed_at.as_deref()),
+                    prev_hash,
+                    sealed_at,
+                    closed_at,
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Make the month read-only.
+    pub fn seal(&mut self) {
+        let utc: DateTime<Utc> = Utc::now();
+        self.set_meta("sealed_at", utc.format("%FT%T%:z").to_string());
+        self.changed = true;
+    }
+
+    /// Mark the month as closed, so the close is part of the hash.
+    pub fn mark_closed(&mut self) {
+        if self.meta("closed_at").is_some() {
+            return;
+        }
+        let utc: DateTime<Utc> = Utc::now();
+        self.set_meta("closed_at", utc.format("%FT%T%:z").to_string());
+        self.changed = true;
+    }
+
+    /// Closed months of the index file, like `2020-03`.
+    pub fn closed(&self) -> Vec<String> {
+        match (&self.kind, &self.content["closed"]) {
+            (YamlFileKind::IndexFile, Yaml::Array(months)) => months.iter()
+                .filter_map(|month| month.as_str())
+                .map(|month| month.to_string())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Add a closed month to the index file. Returns false when it is
+    /// closed already.
+    pub fn close_month(&mut self, month: String) -> bool {
+        let mut closed = self.closed();
+        if closed.contains(&month) {
+            return false;
+        }
+        closed.push(month);
+        closed.sort();
+
+        if let (YamlFileKind::IndexFile, Yaml::Hash(ref mut content_ref)) = (&self.kind, &mut self.content) {
+            let months = closed.into_iter().map(|month| month.to_yaml()).collect();
+            content_ref.insert("closed".to_string().to_yaml(), Yaml::Array(months));
+            self.changed = true;
+        }
+
+        self.changed
+    }
+
+    /// The month file was changed outside of the wallet when it was read.
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Link the month file to the previous hash.
+    fn set_chain(&mut self, prev_hash: String) {
+        let hash = chain::hash(&prev_hash, &self.dump_days(), self.meta("sealed_at").as_deref(), self.meta("closed_at").as_deref());
+        self.set_meta("prev_hash", prev_hash);
+        self.set_meta("hash", hash);
+    }
+
+    /// Month files next to this one, sorted by name.
+    fn month_paths(&self) -> Vec<PathBuf> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let mut paths: Vec<PathBuf> = match read_dir(dir) {
+            Ok(dir) => dir.filter_map(|item| item.ok()).map(|item| item.path()).collect(),
+            Err(_) => return vec![],
+        };
+        paths.retain(|path| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            file_name.starts_with("month_") && file_name.ends_with(".yml")
+        });
+        paths.sort();
+        paths
+    }
+
+    /// Hash of the month file before this one. Empty for the first.
+    fn previous_hash(&self) -> String {
+        self.month_paths().into_iter()
+            .rfind(|path| path < &self.path)
+            .and_then(|path| YamlFile::open_month(path).meta("hash"))
+            .unwrap_or_default()
+    }
+
+    /// Link this and the following month files to the months before.
+    /// Stops at a month which was changed outside, so the break stays.
+    fn relink(&self) {
+        println!("-> YamlFile::relink()");
+
+        let mut prev_hash = self.previous_hash();
+        for path in self.month_paths().into_iter().filter(|path| path >= &self.path) {
+            let mut month_file = YamlFile::open_month(path);
+            let link = month_file.link().unwrap();
+            if !link.is_valid() {
+                break;
+            }
+            if link.prev_hash.as_ref() != Some(&prev_hash) {
+                month_file.set_chain(prev_hash);
+                month_file.write();
+            } else if month_file.path != self.path {
+                // The rest of the chain is unchanged.
+                break;
+            }
+            prev_hash = month_file.meta("hash").unwrap_or_default();
+        }
+    }
+
     pub fn get<T: FromYaml>(&self) -> Vec<T> {
         println!("-> YamlFile::get() -> {:?}", self.kind);
 
@@ -291,6 +591,17 @@ impl YamlFile {
                         }
                     }
                 },
+                YamlFileKind::EpicsFile => {
+                    // println!("-> EpicsFile");
+
+                    let index_key = "epics".to_string().to_yaml();
+
+                    if let Yaml::Array(ref index_ref) = content_ref[&index_key] {
+                        for item in index_ref.iter() {
+                            items.push(T::from_yaml(item));
+                        }
+                    }
+                },
                 _ => unreachable!("Yaml::get() not implemented for {:?}", self.kind),
             }
         }
@@ -298,8 +609,12 @@ impl YamlFile {
         items
     }
 
-    fn write(&mut self) {
-        println!("-> YamlFile::write()");
+    /// Content as written to disk, encrypted for encrypted wallets.
+    fn encode(&self) -> Result<String, crypt::CryptError> {
+        crypt::encode(&self.path, self.dump())
+    }
+
+    fn dump(&self) -> String {
         let mut out_str = String::new();
         {
             let mut emitter = YamlEmitter::new(&mut out_str);
@@ -308,6 +623,15 @@ impl YamlFile {
         out_str.push_str("\n");
         // println!("out: '{}'", out_str);
 
+        out_str
+    }
+
+    fn write(&mut self) {
+        println!("-> YamlFile::write()");
+        let out_str = self.encode().unwrap_or_else(|error| panic!("{}", error));
+
+        history::before_write(&self.path);
+
         println!("-> File::create");
         let mut file = File::create(&self.path)
             .expect("Cannot open file for writing");
@@ -319,6 +643,59 @@ impl YamlFile {
         self.changed = false;
     }
 
+    fn tmp_path(&self) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap().to_os_string();
+        file_name.push(".tmp");
+        self.path.with_file_name(file_name)
+    }
+
+    /// Write the changed content to a temporary file next to the file.
+    /// `commit()` moves it into place, `discard()` removes it.
+    pub fn stage(&mut self) -> io::Result<()> {
+        println!("-> YamlFile::stage()");
+
+        if !self.changed {
+            return Ok(());
+        }
+
+        self.update_meta();
+
+        let out_str = self.encode().map_err(|error| io::Error::other(error.to_string()))?;
+
+        let mut file = File::create(self.tmp_path())?;
+        file.write_all(out_str.as_bytes())?;
+        file.sync_all()
+    }
+
+    pub fn commit(&mut self) -> io::Result<()> {
+        println!("-> YamlFile::commit()");
+
+        if !self.changed {
+            return Ok(());
+        }
+
+        history::before_write(&self.path);
+        rename(self.tmp_path(), &self.path)?;
+        self.changed = false;
+
+        if let YamlFileKind::MonthFile = self.kind {
+            self.relink();
+        }
+
+        Ok(())
+    }
+
+    /// Drop all changes without writing the file.
+    pub fn discard(&mut self) {
+        println!("-> YamlFile::discard()");
+
+        let tmp_path = self.tmp_path();
+        if tmp_path.exists() {
+            remove_file(tmp_path).ok();
+        }
+        self.changed = false;
+    }
+
     /// Write file if content has changed.
     fn close(&mut self) {
         println!("-> YamlFile::close()");
@@ -327,6 +704,16 @@ impl YamlFile {
             return;
         }
 
+        self.update_meta();
+        self.write();
+
+        if let YamlFileKind::MonthFile = self.kind {
+            self.relink();
+        }
+    }
+
+    /// Set version and update time of a month file.
+    fn update_meta(&mut self) {
         if let Yaml::Hash(ref mut content_ref) = self.content {
             // println!("content_ref: {:?}", content_ref);
 
@@ -366,7 +753,11 @@ impl YamlFile {
             }
         }
 
-        self.write();
+        // Hash Chain. A broken month keeps its hash, so the break stays.
+        if let (YamlFileKind::MonthFile, false) = (&self.kind, self.broken) {
+            let prev_hash = self.previous_hash();
+            self.set_chain(prev_hash);
+        }
     }
 }
 
@@ -386,6 +777,7 @@ pub trait FromYaml {
 
 #[cfg(test)]
 mod tests_file {
+    use std::fs::remove_file;
     use std::path::PathBuf;
     use std::str::FromStr;
     use super::YamlFile;
@@ -421,6 +813,14 @@ mod tests_file {
         assert!(f1.exists("h1".to_string()));
         assert!(!f1.exists("h2".to_string()));
 
+        let epics: Vec<Epic> = f1.get();
+        assert_eq!(1, epics.len());
+        assert_eq!("h1", epics[0].handle());
+
+        assert!(f1.remove("h1".to_string()));
+        assert!(!f1.remove("h1".to_string()));
+        assert!(!f1.exists("h1".to_string()));
+
         let p1 = PathBuf::from(ps1);
         assert!(p1.is_file());
 
@@ -446,4 +846,81 @@ mod tests_file {
 
         // assert!(false);
     }
+
+    #[test]
+    fn test_yaml_month_remove() {
+        remove_file("../tmp/tests/month_remove.yml").ok();
+
+        let mut e1 = Entry::new();
+        e1.set_date(Date::from_str("1987-02-21").unwrap());
+        let id1 = e1.id();
+
+        let mut e2 = Entry::new();
+        e2.set_date(Date::from_str("1987-02-22").unwrap());
+
+        let p1 = PathBuf::from("../tmp/tests/month_remove.yml");
+        let mut f1 = YamlFile::open_month(p1);
+        f1.add(e1);
+        f1.add(e2);
+
+        assert!(f1.remove(id1.clone()));
+        assert!(!f1.remove(id1));
+
+        let entries: Vec<Entry> = f1.get();
+        assert_eq!(1, entries.len());
+        assert_eq!("1987-02-22", entries[0].date().to_string());
+    }
+
+    #[test]
+    fn test_yaml_month_update() {
+        remove_file("../tmp/tests/month_update.yml").ok();
+
+        let mut e1 = Entry::new();
+        e1.set_date(Date::from_str("1987-02-21").unwrap());
+        e1.set_title("a".to_string());
+
+        let mut e2 = Entry::new();
+        e2.set_date(Date::from_str("1987-02-21").unwrap());
+
+        let p1 = PathBuf::from("../tmp/tests/month_update.yml");
+        let mut f1 = YamlFile::open_month(p1);
+        f1.add(e1.clone());
+        f1.add(e2);
+
+        // In place.
+        e1.set_title("b".to_string());
+        assert!(f1.update(e1.clone()));
+        let entries: Vec<Entry> = f1.get();
+        assert_eq!("b", entries[0].title());
+
+        // Moved to another day.
+        e1.set_date(Date::from_str("1987-02-23").unwrap());
+        assert!(f1.update(e1.clone()));
+        let entries: Vec<Entry> = f1.get();
+        assert_eq!(2, entries.len());
+        assert_eq!("1987-02-23", entries[1].date().to_string());
+
+        assert!(!f1.update(Entry::new()));
+    }
+
+    #[test]
+    fn test_yaml_epics_update() {
+        remove_file("../tmp/tests/epics_update.yml").ok();
+
+        let mut e1 = Epic::new();
+        e1.set_handle("h1".to_string());
+
+        let p1 = PathBuf::from("../tmp/tests/epics_update.yml");
+        let mut f1 = YamlFile::open_epics(p1);
+        f1.add(e1);
+
+        let mut e2 = Epic::new();
+        e2.set_handle("h1".to_string());
+        e2.set_title("Title".to_string());
+        assert!(f1.update(e2));
+
+        let epics: Vec<Epic> = f1.get();
+        assert_eq!(1, epics.len());
+        assert_eq!("Title", epics[0].title());
+    }
 }